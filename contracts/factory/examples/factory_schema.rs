@@ -4,9 +4,10 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema_with_title, remove_schemas, schema_for};
 
 use novaswap::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg, MigrationParams,
+    PairsResponse, PoolsResponse, QueryMsg,
 };
-use novaswap::pairinfo::PairInfo;
+use novaswap::pairinfo::{PairInfo, PoolInfo};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -19,6 +20,10 @@ fn main() {
     export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
     export_schema_with_title(&schema_for!(PairInfo), &out_dir, "PairInfo");
     export_schema_with_title(&schema_for!(PairsResponse), &out_dir, "PairsResponse");
+    export_schema_with_title(&schema_for!(PoolInfo), &out_dir, "PoolInfo");
+    export_schema_with_title(&schema_for!(PoolsResponse), &out_dir, "PoolsResponse");
     export_schema_with_title(&schema_for!(ConfigResponse), &out_dir, "ConfigResponse");
+    export_schema_with_title(&schema_for!(FeeInfoResponse), &out_dir, "FeeInfoResponse");
     export_schema_with_title(&schema_for!(MigrateMsg), &out_dir, "MigrateMsg");
+    export_schema_with_title(&schema_for!(MigrationParams), &out_dir, "MigrationParams");
 }
@@ -1,21 +1,29 @@
 use std::collections::HashSet;
 
 use cosmwasm_std::{
-    attr, entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, ReplyOn,
-    Response, StdResult, SubMsg, WasmMsg,
+    attr, entry_point, from_binary, to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Order, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use novaswap::asset::AssetInfo;
 use novaswap::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg, MigrationParams,
+    PairsResponse, PoolsResponse, QueryMsg,
 };
-use novaswap::pair::InstantiateMsg as PairInstantiateMsg;
-use novaswap::pairinfo::{PairConfig, PairInfo, PairType};
+use novaswap::pair::{
+    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg, PoolStatus,
+    PoolStatusUpdateParams,
+};
+use novaswap::pairinfo::{PairConfig, PairInfo, PairType, PoolInfo};
+use novaswap::pool::{InstantiateMsg as PoolInstantiateMsg, MAX_POOL_ASSETS, MIN_POOL_ASSETS};
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::querier::query_pair_info;
+use crate::migration::migrate_from_v100;
+use crate::querier::{query_pair_info, query_pool_info};
 use crate::state::{
-    pair_key, read_pairs, Config, TmpPairInfo, CONFIG, PAIRS, PAIR_CONFIGS, TMP_PAIR_INFO,
+    pair_key, pool_key, read_pairs, read_pools, Config, TmpPairInfo, CONFIG, PAIRS, PAIR_CONFIGS,
+    POOLS, TMP_PAIR_INFO,
 };
 
 /// Contract name that is used for migration.
@@ -27,6 +35,9 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// A `reply` call code ID used in a sub-message.
 const INSTANTIATE_PAIR_REPLY_ID: u64 = 1;
 
+/// A `reply` call code ID used in a sub-message instantiating a multi-asset pool.
+const INSTANTIATE_POOL_REPLY_ID: u64 = 2;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -39,6 +50,10 @@ pub fn instantiate(
     let config = Config {
         owner: deps.api.addr_validate(&msg.owner)?,
         token_code_id: msg.token_code_id,
+        fee_address: msg
+            .fee_address
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
     };
 
     let config_set: HashSet<String> = msg
@@ -52,6 +67,9 @@ pub fn instantiate(
     }
 
     for pc in msg.pair_configs.iter() {
+        if !pc.valid_fee_bps() {
+            return Err(ContractError::PairConfigInvalidFeeBps {});
+        }
         PAIR_CONFIGS.save(deps.storage, pc.clone().pair_type.to_string(), pc)?;
     }
 
@@ -64,6 +82,8 @@ pub fn instantiate(
 pub struct UpdateConfig {
     /// This is the CW20 token contract code identifier
     token_code_id: Option<u64>,
+    /// Address that receives the protocol share of swap commissions
+    fee_address: Option<String>,
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -74,16 +94,32 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { token_code_id } => {
-            execute_update_config(deps, info, UpdateConfig { token_code_id })
-        }
+        ExecuteMsg::UpdateConfig {
+            token_code_id,
+            fee_address,
+        } => execute_update_config(
+            deps,
+            info,
+            UpdateConfig {
+                token_code_id,
+                fee_address,
+            },
+        ),
         ExecuteMsg::UpdatePairConfig { config } => execute_update_pair_config(deps, info, config),
         ExecuteMsg::CreatePair {
             pair_type,
             asset_infos,
             init_params,
         } => execute_create_pair(deps, env, pair_type, asset_infos, init_params),
+        ExecuteMsg::CreatePool {
+            pair_type,
+            asset_infos,
+            init_params,
+        } => execute_create_pool(deps, env, pair_type, asset_infos, init_params),
         ExecuteMsg::UpdateOwner { new_owner } => execute_update_owner(deps, info, new_owner),
+        ExecuteMsg::UpdatePairsStatus { pairs, status } => {
+            execute_update_pairs_status(deps, info, pairs, status)
+        }
     }
 }
 
@@ -102,6 +138,10 @@ fn execute_update_config(
         config.token_code_id = token_code_id;
     }
 
+    if let Some(fee_address) = params.fee_address {
+        config.fee_address = Some(deps.api.addr_validate(fee_address.as_str())?);
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -123,6 +163,40 @@ fn execute_update_owner(
     Ok(Response::new())
 }
 
+// Sets the status of the given pairs, the pairs accept status updates from their factory.
+fn execute_update_pairs_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    pairs: Vec<String>,
+    status: PoolStatus,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let params = to_binary(&PoolStatusUpdateParams::UpdateStatus { status })?;
+    let messages = pairs
+        .iter()
+        .map(|pair| {
+            Ok(WasmMsg::Execute {
+                contract_addr: deps.api.addr_validate(pair)?.to_string(),
+                msg: to_binary(&PairExecuteMsg::UpdateConfig {
+                    params: params.clone(),
+                })?,
+                funds: vec![],
+            })
+        })
+        .collect::<StdResult<Vec<WasmMsg>>>()?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "update_pairs_status"),
+        attr("pairs", pairs.join(",")),
+        attr("status", format!("{:?}", status)),
+    ]))
+}
+
 fn execute_update_pair_config(
     deps: DepsMut,
     info: MessageInfo,
@@ -134,6 +208,10 @@ fn execute_update_pair_config(
         return Err(ContractError::Unauthorized {});
     }
 
+    if !pair_config.valid_fee_bps() {
+        return Err(ContractError::PairConfigInvalidFeeBps {});
+    }
+
     PAIR_CONFIGS.save(
         deps.storage,
         pair_config.pair_type.to_string(),
@@ -157,6 +235,10 @@ fn execute_create_pair(
         return Err(ContractError::DoublingAssets {});
     }
 
+    if pair_type == (PairType::StablePool {}) {
+        return Err(ContractError::PairTypeMismatch {});
+    }
+
     let config = CONFIG.load(deps.storage)?;
 
     if PAIRS
@@ -206,15 +288,113 @@ fn execute_create_pair(
         ]))
 }
 
+fn execute_create_pool(
+    deps: DepsMut,
+    env: Env,
+    pair_type: PairType,
+    asset_infos: Vec<AssetInfo>,
+    init_params: Option<Binary>,
+) -> Result<Response, ContractError> {
+    if asset_infos.len() < MIN_POOL_ASSETS || asset_infos.len() > MAX_POOL_ASSETS {
+        return Err(ContractError::InvalidNumberOfAssets {});
+    }
+
+    for asset_info in asset_infos.iter() {
+        asset_info.check(deps.api)?;
+    }
+
+    let unique_assets: HashSet<String> = asset_infos.iter().map(|a| a.to_string()).collect();
+    if unique_assets.len() != asset_infos.len() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    if pair_type != (PairType::StablePool {}) {
+        return Err(ContractError::PairTypeMismatch {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    if POOLS
+        .may_load(deps.storage, &pool_key(&asset_infos))?
+        .is_some()
+    {
+        return Err(ContractError::PoolWasCreated {});
+    }
+
+    // Get pool type from config
+    let pair_config = PAIR_CONFIGS
+        .load(deps.storage, pair_type.to_string())
+        .map_err(|_| ContractError::PairConfigNotFound {})?;
+
+    // Check if pool config is disabled
+    if pair_config.is_disabled {
+        return Err(ContractError::PairConfigDisabled {});
+    }
+
+    TMP_PAIR_INFO.save(
+        deps.storage,
+        &TmpPairInfo {
+            pair_key: pool_key(&asset_infos),
+        },
+    )?;
+
+    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+        id: INSTANTIATE_POOL_REPLY_ID,
+        msg: WasmMsg::Instantiate {
+            admin: Some(config.owner.to_string()),
+            code_id: pair_config.code_id,
+            msg: to_binary(&PoolInstantiateMsg {
+                asset_infos: asset_infos.clone(),
+                token_code_id: config.token_code_id,
+                factory_addr: env.contract.address.to_string(),
+                init_params,
+            })?,
+            funds: vec![],
+            label: "Novaswap pool".to_string(),
+        }
+        .into(),
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }];
+
+    let pool_name: Vec<String> = asset_infos.iter().map(|a| a.to_string()).collect();
+
+    Ok(Response::new()
+        .add_submessages(sub_msg)
+        .add_attributes(vec![
+            attr("action", "create_pool"),
+            attr("pool", pool_name.join("-")),
+        ]))
+}
+
 /// The entry point to the contract for processing replies from submessages.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     let tmp = TMP_PAIR_INFO.load(deps.storage)?;
+
+    if msg.id == INSTANTIATE_POOL_REPLY_ID {
+        if POOLS.may_load(deps.storage, &tmp.pair_key)?.is_some() {
+            return Err(ContractError::PoolWasRegistered {});
+        }
+
+        let res = cw_utils::parse_reply_instantiate_data(msg)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        let pool_contract = deps.api.addr_validate(res.contract_address.as_str())?;
+
+        POOLS.save(deps.storage, &tmp.pair_key, &pool_contract)?;
+
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "register"),
+            attr("pool_contract_addr", pool_contract),
+        ]));
+    }
+
     if PAIRS.may_load(deps.storage, &tmp.pair_key)?.is_some() {
         return Err(ContractError::PairWasRegistered {});
     }
 
-    let res = cw_utils::parse_reply_instantiate_data(msg).unwrap();
+    let res = cw_utils::parse_reply_instantiate_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
     let pair_contract = deps.api.addr_validate(res.contract_address.as_str())?;
 
     PAIRS.save(deps.storage, &tmp.pair_key, &pair_contract)?;
@@ -234,6 +414,11 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pairs { start_after, limit } => {
             to_binary(&query_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::FeeInfo { pair_type } => to_binary(&query_fee_info(deps, pair_type)?),
+        QueryMsg::Pool { asset_infos } => to_binary(&query_pool(deps, asset_infos)?),
+        QueryMsg::Pools { start_after, limit } => {
+            to_binary(&query_pools(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -242,13 +427,11 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let resp = ConfigResponse {
         owner: config.owner,
         token_code_id: config.token_code_id,
+        fee_address: config.fee_address,
         pair_configs: PAIR_CONFIGS
             .range(deps.storage, None, None, Order::Ascending)
-            .map(|item| {
-                let (_, cfg) = item.unwrap();
-                cfg
-            })
-            .collect(),
+            .map(|item| item.map(|(_, cfg)| cfg))
+            .collect::<StdResult<Vec<_>>>()?,
     };
 
     Ok(resp)
@@ -264,16 +447,87 @@ pub fn query_pairs(
     start_after: Option<[AssetInfo; 2]>,
     limit: Option<u32>,
 ) -> StdResult<PairsResponse> {
-    let pairs: Vec<PairInfo> = read_pairs(deps, start_after, limit)
+    let pairs = read_pairs(deps, start_after, limit)?
         .iter()
-        .map(|pair_addr| query_pair_info(deps, pair_addr).unwrap())
-        .collect();
+        .map(|pair_addr| query_pair_info(deps, pair_addr))
+        .collect::<StdResult<Vec<PairInfo>>>()?;
 
     Ok(PairsResponse { pairs })
 }
 
-// Used for contract migration.
+pub fn query_pool(deps: Deps, asset_infos: Vec<AssetInfo>) -> StdResult<PoolInfo> {
+    let pool_addr = POOLS.load(deps.storage, &pool_key(&asset_infos))?;
+    query_pool_info(deps, &pool_addr)
+}
+
+pub fn query_pools(
+    deps: Deps,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<PoolsResponse> {
+    let pools = read_pools(deps, start_after, limit)?
+        .iter()
+        .map(|pool_addr| query_pool_info(deps, pool_addr))
+        .collect::<StdResult<Vec<PoolInfo>>>()?;
+
+    Ok(PoolsResponse { pools })
+}
+
+/// Returns the fee split for pairs of the given type.
+pub fn query_fee_info(deps: Deps, pair_type: PairType) -> StdResult<FeeInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pair_config = PAIR_CONFIGS.load(deps.storage, pair_type.to_string())?;
+
+    Ok(FeeInfoResponse {
+        fee_address: config.fee_address,
+        maker_fee_rate: Decimal::from_ratio(pair_config.maker_fee_bps, 10000u128),
+    })
+}
+
+/// Used for contract migration.
+/// Rejects other contracts and downgrades, then upgrades the state stored by older versions
+/// with the [`MigrationParams`] given in `msg.params`.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    if contract_version.contract != CONTRACT_NAME {
+        return Err(ContractError::MigrationError {});
+    }
+
+    let stored_version: Version = contract_version
+        .version
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    if stored_version > new_version {
+        return Err(ContractError::MigrationError {});
+    }
+
+    let params: MigrationParams = from_binary(&msg.params)?;
+    let fee_address = params
+        .fee_address
+        .map(|fee_address| deps.api.addr_validate(fee_address.as_str()))
+        .transpose()?;
+
+    if stored_version < Version::new(1, 1, 0) {
+        let maker_fee_bps = params.maker_fee_bps.unwrap_or_default();
+        if maker_fee_bps > 10_000 {
+            return Err(ContractError::PairConfigInvalidFeeBps {});
+        }
+
+        migrate_from_v100(deps.storage, fee_address, maker_fee_bps)?;
+    } else if let Some(fee_address) = fee_address {
+        let mut config = CONFIG.load(deps.storage)?;
+        config.fee_address = Some(fee_address);
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("previous_contract_version", contract_version.version),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
 }
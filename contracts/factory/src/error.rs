@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use novaswap::pool::{MAX_POOL_ASSETS, MIN_POOL_ASSETS};
 use thiserror::Error;
 
 /// This enum describes factory contract errors!
@@ -25,6 +26,25 @@ pub enum ContractError {
     #[error("Pair config disabled")]
     PairConfigDisabled {},
 
+    #[error("Pair config maker fee bps must be less than or equal to 10000")]
+    PairConfigInvalidFeeBps {},
+
+    #[error("Pool was already created")]
+    PoolWasCreated {},
+
+    #[error("Pool was already registered")]
+    PoolWasRegistered {},
+
+    #[error("Pair type mismatch, multi-asset pools must be created with CreatePool")]
+    PairTypeMismatch {},
+
+    #[error(
+        "The number of assets in a pool must be between {} and {}",
+        MIN_POOL_ASSETS,
+        MAX_POOL_ASSETS
+    )]
+    InvalidNumberOfAssets {},
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
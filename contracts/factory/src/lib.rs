@@ -1,5 +1,6 @@
 pub mod contract;
 pub mod error;
+pub mod migration;
 pub mod querier;
 pub mod state;
 
@@ -0,0 +1,61 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+use novaswap::pairinfo::{PairConfig, PairType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Config, CONFIG, PAIR_CONFIGS};
+
+/// Factory settings stored by the 1.0.0 factory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV100 {
+    pub owner: Addr,
+    pub token_code_id: u64,
+}
+
+/// Pair type configurations stored by the 1.0.0 factory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairConfigV100 {
+    pub code_id: u64,
+    pub pair_type: PairType,
+    pub is_disabled: bool,
+}
+
+pub const CONFIG_V100: Item<ConfigV100> = Item::new("config");
+
+pub const PAIR_CONFIGS_V100: Map<String, PairConfigV100> = Map::new("pair_configs");
+
+/// Upgrades the settings stored by a 1.0.0 factory, which had no fee address and no maker fees.
+pub fn migrate_from_v100(
+    storage: &mut dyn Storage,
+    fee_address: Option<Addr>,
+    maker_fee_bps: u16,
+) -> StdResult<()> {
+    let config = CONFIG_V100.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            owner: config.owner,
+            token_code_id: config.token_code_id,
+            fee_address,
+        },
+    )?;
+
+    let pair_configs = PAIR_CONFIGS_V100
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, PairConfigV100)>>>()?;
+    for (key, pair_config) in pair_configs {
+        PAIR_CONFIGS.save(
+            storage,
+            key,
+            &PairConfig {
+                code_id: pair_config.code_id,
+                pair_type: pair_config.pair_type,
+                maker_fee_bps,
+                is_disabled: pair_config.is_disabled,
+            },
+        )?;
+    }
+
+    Ok(())
+}
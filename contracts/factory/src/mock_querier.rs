@@ -4,7 +4,8 @@ use cosmwasm_std::{
     SystemError, SystemResult, WasmQuery,
 };
 use novaswap::pair::QueryMsg;
-use novaswap::pairinfo::PairInfo;
+use novaswap::pairinfo::{PairInfo, PoolInfo};
+use novaswap::pool::QueryMsg as PoolQueryMsg;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
@@ -23,6 +24,7 @@ pub fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
     novaswap_pair_querier: NovaswapPairQuerier,
+    novaswap_pool_querier: NovaswapPoolQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -38,6 +40,21 @@ impl NovaswapPairQuerier {
     }
 }
 
+#[derive(Clone, Default)]
+pub struct NovaswapPoolQuerier {
+    pools: HashMap<String, PoolInfo>,
+}
+
+impl NovaswapPoolQuerier {
+    pub fn new(pools: &[(&String, &PoolInfo)]) -> Self {
+        let mut pools_map: HashMap<String, PoolInfo> = HashMap::new();
+        for (key, pool) in pools.iter() {
+            pools_map.insert(key.to_string(), (*pool).clone());
+        }
+        NovaswapPoolQuerier { pools: pools_map }
+    }
+}
+
 pub(crate) fn pairs_to_map(pairs: &[(&String, &PairInfo)]) -> HashMap<String, PairInfo> {
     let mut pairs_map: HashMap<String, PairInfo> = HashMap::new();
     for (key, pair) in pairs.iter() {
@@ -67,6 +84,7 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             novaswap_pair_querier: NovaswapPairQuerier::default(),
+            novaswap_pool_querier: NovaswapPoolQuerier::default(),
         }
     }
 
@@ -75,8 +93,23 @@ impl WasmMockQuerier {
         self.novaswap_pair_querier = NovaswapPairQuerier::new(pairs);
     }
 
+    // Configure the Novaswap multi-asset pools
+    pub fn with_novaswap_pools(&mut self, pools: &[(&String, &PoolInfo)]) {
+        self.novaswap_pool_querier = NovaswapPoolQuerier::new(pools);
+    }
+
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if matches!(from_binary(msg), Ok(PoolQueryMsg::PoolInfo {})) =>
+            {
+                match self.novaswap_pool_querier.pools.get(contract_addr) {
+                    Some(v) => SystemResult::Ok(to_binary(v).into()),
+                    None => SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    }),
+                }
+            }
             QueryRequest::Wasm(WasmQuery::Smart {contract_addr, msg})// => {
                 => match from_binary(msg).unwrap() {
                     QueryMsg::Pair {} => {
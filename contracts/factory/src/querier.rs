@@ -1,6 +1,10 @@
 use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
 
-use novaswap::{pair::QueryMsg, pairinfo::PairInfo};
+use novaswap::{
+    pair::QueryMsg,
+    pairinfo::{PairInfo, PoolInfo},
+    pool::QueryMsg as PoolQueryMsg,
+};
 
 /// Send `QueryMsg::Pair {}` message to get the [`PairInfo`] from pair contract
 pub fn query_pair_info(deps: Deps, pair_contract: &Addr) -> StdResult<PairInfo> {
@@ -9,3 +13,11 @@ pub fn query_pair_info(deps: Deps, pair_contract: &Addr) -> StdResult<PairInfo>
         msg: to_binary(&QueryMsg::Pair {})?,
     }))
 }
+
+/// Send `QueryMsg::PoolInfo {}` message to get the [`PoolInfo`] from pool contract
+pub fn query_pool_info(deps: Deps, pool_contract: &Addr) -> StdResult<PoolInfo> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pool_contract.to_string(),
+        msg: to_binary(&PoolQueryMsg::PoolInfo {})?,
+    }))
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr, Deps, Order};
+use cosmwasm_std::{Addr, Deps, Order, StdResult};
 use cw_storage_plus::{Bound, Item, Map};
 use novaswap::{asset::AssetInfo, pairinfo::PairConfig};
 use schemars::JsonSchema;
@@ -10,6 +10,8 @@ pub struct Config {
     pub owner: Addr,
     /// CW20 token contract code identifier
     pub token_code_id: u64,
+    /// Address that receives the protocol share of swap commissions
+    pub fee_address: Option<Addr>,
 }
 
 /// Structure for storing pair keys
@@ -27,6 +29,9 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Saves created pairs (from olders to latest)
 pub const PAIRS: Map<&[u8], Addr> = Map::new("pair_info");
 
+/// Saves created multi-asset pools (from olders to latest)
+pub const POOLS: Map<&[u8], Addr> = Map::new("pool_info");
+
 /// Calculate unique pair key from asset_infos
 pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> Vec<u8> {
     let mut asset_infos = asset_infos.to_vec();
@@ -35,6 +40,18 @@ pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> Vec<u8> {
     [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()].concat()
 }
 
+/// Calculate unique pool key from asset_infos
+pub fn pool_key(asset_infos: &[AssetInfo]) -> Vec<u8> {
+    let mut asset_infos = asset_infos.to_vec();
+    asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+    asset_infos
+        .iter()
+        .map(|asset_info| asset_info.as_bytes())
+        .collect::<Vec<&[u8]>>()
+        .concat()
+}
+
 /// Saves pair type configurations
 pub const PAIR_CONFIGS: Map<String, PairConfig> = Map::new("pair_configs");
 
@@ -50,17 +67,14 @@ pub fn read_pairs(
     deps: Deps,
     start_after: Option<[AssetInfo; 2]>,
     limit: Option<u32>,
-) -> Vec<Addr> {
+) -> StdResult<Vec<Addr>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(start_after).map(Bound::ExclusiveRaw);
 
     PAIRS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
-        .map(|item| {
-            let (_, pair_addr) = item.unwrap();
-            pair_addr
-        })
+        .map(|item| item.map(|(_, pair_addr)| pair_addr))
         .collect()
 }
 
@@ -78,3 +92,26 @@ fn calc_range_start(start_after: Option<[AssetInfo; 2]>) -> Option<Vec<u8>> {
         v
     })
 }
+
+/// Reads pools from the [`POOLS`] map according to the `start_after` and `limit` variables.
+/// Otherwise, it returns the default number of pools, starting from the oldest one.
+pub fn read_pools(
+    deps: Deps,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Addr>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|asset_infos| {
+            let mut v = pool_key(&asset_infos);
+            v.push(1);
+            v
+        })
+        .map(Bound::ExclusiveRaw);
+
+    POOLS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, pool_addr)| pool_addr))
+        .collect()
+}
@@ -1,16 +1,27 @@
 use crate::mock_querier::mock_dependencies;
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{attr, from_binary, to_binary, Addr, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg, Uint128};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, Decimal, DepsMut, Env, MessageInfo, Reply, ReplyOn,
+    Response, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
 use novaswap::asset::AssetInfo;
-use novaswap::factory::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use novaswap::pair::{InstantiateMsg as PairInstantiateMsg, StablePoolParams};
-use novaswap::pairinfo::{PairConfig, PairInfo, PairType};
-use novaswap::querier::query_factory_config;
+use novaswap::factory::{
+    ConfigResponse, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg, MigrationParams,
+    PoolsResponse, QueryMsg,
+};
+use novaswap::pair::{
+    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg, PoolStatus,
+    PoolStatusUpdateParams, StablePoolParams,
+};
+use novaswap::pairinfo::{PairConfig, PairInfo, PairType, PoolInfo};
+use novaswap::pool::InstantiateMsg as PoolInstantiateMsg;
 use prost::Message;
 
-use crate::contract::{execute, instantiate, query, reply};
+use crate::contract::{execute, instantiate, migrate, query, reply};
 use crate::error::ContractError;
+use crate::migration::{ConfigV100, PairConfigV100, CONFIG_V100, PAIR_CONFIGS_V100};
 use crate::state::CONFIG;
 
 #[derive(Clone, PartialEq, Message)]
@@ -31,10 +42,12 @@ fn init_contract(
         pair_configs: vec![PairConfig {
             code_id: 1u64,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false,
         }],
         token_code_id: 1u64,
         owner,
+        fee_address: None,
     };
 
     instantiate(deps, env, info, msg)
@@ -48,10 +61,12 @@ fn proper_initialization() {
         pair_configs: vec![PairConfig {
             code_id: 1u64,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false,
         }],
         token_code_id: 1u64,
         owner: owner.clone(),
+        fee_address: None,
     };
 
     let env = mock_env();
@@ -76,16 +91,19 @@ fn duplicated_initialization() {
             PairConfig {
                 code_id: 1u64,
                 pair_type: PairType::Stable {},
+                maker_fee_bps: 0,
                 is_disabled: false,
             },
             PairConfig {
                 code_id: 1u64,
                 pair_type: PairType::Stable {},
+                maker_fee_bps: 0,
                 is_disabled: false,
             },
         ],
         token_code_id: 1u64,
         owner,
+        fee_address: None,
     };
 
     let env = mock_env();
@@ -128,6 +146,154 @@ fn update_config() {
     assert_eq!(config.owner, "addr0000");
 }
 
+#[test]
+fn update_pairs_status() {
+    let mut deps = mock_dependencies();
+    let owner = "owner0000".to_string();
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // init
+    init_contract(deps.as_mut(), env.clone(), info, owner).unwrap();
+
+    let msg = ExecuteMsg::UpdatePairsStatus {
+        pairs: vec!["pair0000".to_string(), "pair0001".to_string()],
+        status: PoolStatus::WithdrawOnly,
+    };
+
+    // err: the user is not an owner.
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // the status update is sent to every pair
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let pair_msg = to_binary(&PairExecuteMsg::UpdateConfig {
+        params: to_binary(&PoolStatusUpdateParams::UpdateStatus {
+            status: PoolStatus::WithdrawOnly,
+        })
+        .unwrap(),
+    })
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "pair0000".to_string(),
+                msg: pair_msg.clone(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "pair0001".to_string(),
+                msg: pair_msg,
+                funds: vec![],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn migrate_from_v100() {
+    let mut deps = mock_dependencies();
+
+    CONFIG_V100
+        .save(
+            deps.as_mut().storage,
+            &ConfigV100 {
+                owner: Addr::unchecked("owner0000"),
+                token_code_id: 1u64,
+            },
+        )
+        .unwrap();
+    for (pair_type, code_id) in [(PairType::Stable {}, 2u64), (PairType::Xyk {}, 3u64)] {
+        PAIR_CONFIGS_V100
+            .save(
+                deps.as_mut().storage,
+                pair_type.to_string(),
+                &PairConfigV100 {
+                    code_id,
+                    pair_type,
+                    is_disabled: false,
+                },
+            )
+            .unwrap();
+    }
+
+    let msg = MigrateMsg {
+        params: to_binary(&MigrationParams {
+            fee_address: Some("fee0000".to_string()),
+            maker_fee_bps: Some(5000),
+        })
+        .unwrap(),
+    };
+
+    // err: other contracts and newer versions cannot be migrated
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-stable", "1.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-factory", "9.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
+
+    // err: the maker fee share cannot exceed the commission
+    set_contract_version(deps.as_mut().storage, "novaswap-factory", "1.0.0").unwrap();
+    let invalid_msg = MigrateMsg {
+        params: to_binary(&MigrationParams {
+            fee_address: None,
+            maker_fee_bps: Some(10001),
+        })
+        .unwrap(),
+    };
+    let res = migrate(deps.as_mut(), mock_env(), invalid_msg).unwrap_err();
+    assert_eq!(res, ContractError::PairConfigInvalidFeeBps {});
+
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let result = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&result).unwrap();
+    assert_eq!(config.owner, "owner0000");
+    assert_eq!(config.token_code_id, 1u64);
+    assert_eq!(config.fee_address, Some(Addr::unchecked("fee0000")));
+    assert_eq!(
+        config.pair_configs,
+        vec![
+            PairConfig {
+                code_id: 2u64,
+                pair_type: PairType::Stable {},
+                maker_fee_bps: 5000,
+                is_disabled: false,
+            },
+            PairConfig {
+                code_id: 3u64,
+                pair_type: PairType::Xyk {},
+                maker_fee_bps: 5000,
+                is_disabled: false,
+            },
+        ]
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // later migrations only update the fee address
+    let msg = MigrateMsg {
+        params: to_binary(&MigrationParams {
+            fee_address: Some("fee0001".to_string()),
+            maker_fee_bps: Some(0),
+        })
+        .unwrap(),
+    };
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let result = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&result).unwrap();
+    assert_eq!(config.fee_address, Some(Addr::unchecked("fee0001")));
+    assert_eq!(config.pair_configs[0].maker_fee_bps, 5000);
+}
+
 #[test]
 fn update_owner() {
     let mut deps = mock_dependencies();
@@ -142,6 +308,7 @@ fn update_owner() {
     let info = mock_info("addr0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
         token_code_id: Some(2u64),
+        fee_address: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
     assert_eq!(res, ContractError::Unauthorized {});
@@ -155,6 +322,73 @@ fn update_owner() {
     assert_eq!(config.token_code_id, 2u64);
 }
 
+#[test]
+fn fee_info() {
+    let mut deps = mock_dependencies();
+    let owner = "owner0000".to_string();
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+
+    init_contract(deps.as_mut(), env.clone(), info.clone(), owner).unwrap();
+
+    // no fee address yet, so nothing goes to the protocol.
+    let result = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::FeeInfo {
+            pair_type: PairType::Stable {},
+        },
+    )
+    .unwrap();
+    let fee_info: FeeInfoResponse = from_binary(&result).unwrap();
+    assert_eq!(fee_info.fee_address, None);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        token_code_id: None,
+        fee_address: Some("collector0000".to_string()),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // maker fee share must not exceed 100%
+    let msg = ExecuteMsg::UpdatePairConfig {
+        config: PairConfig {
+            code_id: 1u64,
+            pair_type: PairType::Stable {},
+            maker_fee_bps: 10001,
+            is_disabled: false,
+        },
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::PairConfigInvalidFeeBps {});
+
+    let msg = ExecuteMsg::UpdatePairConfig {
+        config: PairConfig {
+            code_id: 1u64,
+            pair_type: PairType::Stable {},
+            maker_fee_bps: 5000,
+            is_disabled: false,
+        },
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let result = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::FeeInfo {
+            pair_type: PairType::Stable {},
+        },
+    )
+    .unwrap();
+    let fee_info: FeeInfoResponse = from_binary(&result).unwrap();
+    assert_eq!(
+        fee_info,
+        FeeInfoResponse {
+            fee_address: Some(Addr::unchecked("collector0000")),
+            maker_fee_rate: Decimal::percent(50),
+        }
+    );
+}
+
 #[test]
 fn unauthorized_update_pair_config() {
     let mut deps = mock_dependencies();
@@ -167,6 +401,7 @@ fn unauthorized_update_pair_config() {
         config: PairConfig {
             code_id: 5,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false,
         },
     };
@@ -192,6 +427,7 @@ fn update_pair_config() {
         vec![PairConfig {
             code_id: 1u64,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false
         }]
     );
@@ -199,6 +435,7 @@ fn update_pair_config() {
     let new_pair_config = PairConfig {
         code_id: 5u64,
         pair_type: PairType::Stable {},
+        maker_fee_bps: 0,
         is_disabled: false,
     };
 
@@ -226,12 +463,14 @@ fn add_pair_config() {
     let current_pair_config = PairConfig {
         code_id: 1u64,
         pair_type: PairType::Stable {},
+        maker_fee_bps: 0,
         is_disabled: false,
     };
 
     let new_pair_config = PairConfig {
         code_id: 6,
         pair_type: PairType::Xyk {},
+        maker_fee_bps: 0,
         is_disabled: false,
     };
 
@@ -325,10 +564,12 @@ fn cannot_create_pair() {
         pair_configs: vec![PairConfig {
             code_id: 1u64,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false,
         }],
         token_code_id: 1u64,
         owner,
+        fee_address: None,
     };
 
     instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
@@ -368,10 +609,12 @@ fn register() {
         pair_configs: vec![PairConfig {
             code_id: 1u64,
             pair_type: PairType::Stable {},
+            maker_fee_bps: 0,
             is_disabled: false,
         }],
         token_code_id: 1u64,
         owner,
+        fee_address: None,
     };
 
     instantiate(deps.as_mut(), env, info, msg).unwrap();
@@ -389,7 +632,15 @@ fn register() {
     let msg = ExecuteMsg::CreatePair {
         pair_type: PairType::Stable {},
         asset_infos: asset_infos.clone(),
-        init_params: Some(to_binary(&StablePoolParams { amp: 50 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 50,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
@@ -451,6 +702,220 @@ fn register() {
     assert_eq!(err, ContractError::PairWasRegistered {});
 }
 
+#[test]
+fn create_pool() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let info = mock_info("owner0000", &[]);
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![
+            PairConfig {
+                code_id: 1u64,
+                pair_type: PairType::Stable {},
+                maker_fee_bps: 0,
+                is_disabled: false,
+            },
+            PairConfig {
+                code_id: 2u64,
+                pair_type: PairType::StablePool {},
+                maker_fee_bps: 0,
+                is_disabled: false,
+            },
+        ],
+        token_code_id: 1u64,
+        owner: "owner0000".to_string(),
+        fee_address: None,
+    };
+    instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let asset_info = |addr: &str| AssetInfo::Token {
+        contract_addr: Addr::unchecked(addr),
+    };
+    let asset_infos = vec![
+        asset_info("token0000"),
+        asset_info("token0001"),
+        asset_info("token0002"),
+    ];
+
+    // the number of assets must be within the limits
+    for asset_infos in [
+        vec![asset_info("token0000")],
+        (0..6)
+            .map(|i| asset_info(&format!("token000{}", i)))
+            .collect(),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::CreatePool {
+                pair_type: PairType::StablePool {},
+                asset_infos,
+                init_params: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidNumberOfAssets {});
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CreatePool {
+            pair_type: PairType::StablePool {},
+            asset_infos: vec![
+                asset_info("token0000"),
+                asset_info("token0001"),
+                asset_info("token0000"),
+            ],
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    // pair types must match the create message
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CreatePool {
+            pair_type: PairType::Stable {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PairTypeMismatch {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CreatePair {
+            pair_type: PairType::StablePool {},
+            asset_infos: [asset_info("token0000"), asset_info("token0001")],
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PairTypeMismatch {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::CreatePool {
+            pair_type: PairType::StablePool {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "create_pool"),
+            attr("pool", "token0000-token0001-token0002")
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg {
+            msg: WasmMsg::Instantiate {
+                msg: to_binary(&PoolInstantiateMsg {
+                    factory_addr: String::from(MOCK_CONTRACT_ADDR),
+                    asset_infos: asset_infos.clone(),
+                    token_code_id: 1u64,
+                    init_params: None
+                })
+                .unwrap(),
+                code_id: 2u64,
+                funds: vec![],
+                admin: Some("owner0000".to_string()),
+                label: String::from("Novaswap pool"),
+            }
+            .into(),
+            id: 2,
+            gas_limit: None,
+            reply_on: ReplyOn::Success
+        }]
+    );
+
+    // register the instantiated pool
+    let pool_info = PoolInfo {
+        asset_infos: asset_infos.clone(),
+        contract_addr: Addr::unchecked("pool0000"),
+        liquidity_token: Addr::unchecked("liquidity0000"),
+        pair_type: PairType::StablePool {},
+    };
+    deps.querier
+        .with_novaswap_pools(&[(&"pool0000".to_string(), &pool_info)]);
+
+    let data = MsgInstantiateContractResponse {
+        contract_address: String::from("pool0000"),
+        data: vec![],
+    };
+    let mut encoded_instantiate_reply = Vec::<u8>::with_capacity(data.encoded_len());
+    data.encode(&mut encoded_instantiate_reply).unwrap();
+    let reply_msg = Reply {
+        id: 2,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(encoded_instantiate_reply.into()),
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+
+    // the pool is found regardless of the order of the assets
+    let mut reversed_asset_infos = asset_infos.clone();
+    reversed_asset_infos.reverse();
+    let res: PoolInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Pool {
+                asset_infos: reversed_asset_infos,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, pool_info);
+
+    let res: PoolsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Pools {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pools, vec![pool_info]);
+
+    let err = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
+    assert_eq!(err, ContractError::PoolWasRegistered {});
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CreatePool {
+            pair_type: PairType::StablePool {},
+            asset_infos,
+            init_params: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PoolWasCreated {});
+}
+
 #[test]
 fn test_calculate_optimal_price_ratio() {
     let reserve_b = Uint128::new(100);
@@ -458,7 +923,7 @@ fn test_calculate_optimal_price_ratio() {
     let amount_a = Uint128::new(53);
 
     let amount_b = reserve_b * amount_a / reserve_a;
-    let optimal_amount_b  = amount_b + Uint128::new(1);
+    let optimal_amount_b = amount_b + Uint128::new(1);
 
     assert_eq!(optimal_amount_b, Uint128::new(36));
-}
\ No newline at end of file
+}
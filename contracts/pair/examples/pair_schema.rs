@@ -6,8 +6,10 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema_with_title, remove_schemas, schema_for};
 
 use novaswap::pair::{
-    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse,
-    QueryMsg, ReverseSimulationResponse, SimulationResponse,
+    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ObserveResponse,
+    PoolResponse, QueryMsg, RatesResponse, ReverseSimulationResponse,
+    SimulateWithdrawOneCoinResponse, SimulateWithdrawResponse, SimulationResponse,
+    SpotPriceResponse, TwapPriceResponse, VirtualPriceResponse,
 };
 use novaswap::pairinfo::PairInfo;
 
@@ -33,10 +35,37 @@ fn main() {
         &out_dir,
         "SimulationResponse",
     );
+    export_schema_with_title(
+        &schema_for!(SimulateWithdrawResponse),
+        &out_dir,
+        "SimulateWithdrawResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SimulateWithdrawOneCoinResponse),
+        &out_dir,
+        "SimulateWithdrawOneCoinResponse",
+    );
+    export_schema_with_title(&schema_for!(RatesResponse), &out_dir, "RatesResponse");
     export_schema_with_title(&schema_for!(MigrateMsg), &out_dir, "MigrateMsg");
     export_schema_with_title(
         &schema_for!(CumulativePricesResponse),
         &out_dir,
         "CumulativePricesResponse",
     );
+    export_schema_with_title(
+        &schema_for!(VirtualPriceResponse),
+        &out_dir,
+        "VirtualPriceResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SpotPriceResponse),
+        &out_dir,
+        "SpotPriceResponse",
+    );
+    export_schema_with_title(&schema_for!(ObserveResponse), &out_dir, "ObserveResponse");
+    export_schema_with_title(
+        &schema_for!(TwapPriceResponse),
+        &out_dir,
+        "TwapPriceResponse",
+    );
 }
@@ -1,31 +1,52 @@
-use cosmwasm_std::{attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg, Uint256};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128, Uint256,
+    WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
-use novaswap::asset::{format_lp_token_name, Asset, AssetInfo};
+use novaswap::asset::{format_lp_token_name, format_lp_token_symbol, Asset, AssetInfo};
 use novaswap::pair::{
     ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg,
-    PoolResponse, QueryMsg, ReverseSimulationResponse, SimulationResponse, StablePoolConfig,
-    StablePoolParams, StablePoolUpdateParams,
+    Observation, ObserveResponse, PoolResponse, PoolStatus, QueryMsg, RateSource, RatesResponse,
+    ReverseSimulationResponse, SimulateWithdrawOneCoinResponse, SimulateWithdrawResponse,
+    SimulationResponse, SpotPriceResponse, StablePoolConfig, StablePoolParams,
+    StablePoolUpdateParams, TwapPriceResponse, VirtualPriceResponse, TWAP_PRECISION,
 };
 
 use novaswap::pairinfo::{PairInfo, PairType};
 use novaswap::token::InstantiateMsg as TokenInstantiateMsg;
 
 use crate::error::ContractError;
-use crate::math::{compute_current_amp, compute_d, AMP_PRECISION, MAX_AMP, N_COINS, MINIMUM_AMP};
-use crate::state::{Config, CONFIG};
+use crate::math::{
+    compute_current_amp, compute_d, compute_spot_price, AMP_PRECISION, MAX_AMP, MINIMUM_AMP,
+    N_COINS,
+};
+use crate::migration::migrate_config_from_v100;
+use crate::state::{
+    Config, FlashLoan, RateConfig, CONFIG, FLASH_LOAN, FLASH_SWAP, RATES, RESERVES,
+};
 
 use crate::utils::{
-    accumulate_prices, adjust_precision, assert_max_spread, compute_global_fee,
-    compute_offer_amount, compute_swap, get_share_in_assets, mint_liquidity_token_message,
-    pool_info, start_changing_amp, stop_changing_amp,
+    accumulate_prices, adjust_precision, apply_rate, assert_deadline, assert_deposits_allowed,
+    assert_max_spread, assert_slippage_tolerance, assert_swaps_allowed, compute_fee_rate,
+    compute_flash_fee, compute_imbalance_fee_rate, compute_imbalance_fees, compute_maker_fee,
+    compute_offer_amount, compute_swap, compute_withdraw_imbalance, compute_withdraw_one_coin,
+    get_share_in_assets, load_rates, load_reserves, mint_liquidity_token_message, observe,
+    pool_info, query_rates, remove_rate, start_changing_amp, stop_changing_amp, store_observation,
+    update_fee, update_rates, update_status, DEFAULT_FEE_BPS, MAX_FEE_BPS,
+};
+use novaswap::querier::{
+    query_factory_config, query_fee_info, query_supply, query_token_precision,
 };
-use novaswap::querier::{query_factory_config, query_supply, query_token_precision};
 use novaswap::U256;
+use semver::Version;
 
 const CONTRACT_NAME: &str = "novaswap-pair-stable";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+const FLASH_LOAN_REPLY_ID: u64 = 2;
+const FLASH_SWAP_REPLY_ID: u64 = 3;
 
 // instantiate new novaswap pair stable contract.
 // this will mint new token represents LP shares.
@@ -43,19 +64,29 @@ pub fn instantiate(
         return Err(ContractError::DoublingAssets {});
     }
 
-    if msg.init_params.is_none() {
-        return Err(ContractError::InitParamsNotFound {});
-    }
-
-    let params: StablePoolParams = from_binary(&msg.init_params.unwrap())?;
+    let params: StablePoolParams = from_binary(
+        &msg.init_params
+            .ok_or(ContractError::InitParamsNotFound {})?,
+    )?;
 
     // Zero AMP means to be constant product market making model
     if params.amp == 0 || params.amp > MAX_AMP {
         return Err(ContractError::IncorrectAmp {});
     }
 
+    let fee_bps = params.fee_bps.unwrap_or(DEFAULT_FEE_BPS);
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // the decimals of the assets never change, so they are queried once and cached.
+    let precisions = [
+        query_token_precision(&deps.querier, msg.asset_infos[0].clone())?,
+        query_token_precision(&deps.querier, msg.asset_infos[1].clone())?,
+    ];
+
     // set config
     let config = Config {
         pair_info: PairInfo {
@@ -64,6 +95,7 @@ pub fn instantiate(
             asset_infos: msg.asset_infos.clone(),
             pair_type: PairType::Stable {},
         },
+        precisions,
         factory_addr: deps.api.addr_validate(msg.factory_addr.as_str())?,
         block_time_last: 0,
         price0_cumulative_last: Uint128::zero(),
@@ -72,11 +104,40 @@ pub fn instantiate(
         init_amp_time: env.block.time.seconds(),
         next_amp: params.amp * AMP_PRECISION,
         next_amp_time: env.block.time.seconds(),
+        fee_bps,
+        status: PoolStatus::Active,
     };
 
     CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(); 2])?;
+
+    if let Some(rate_params) = params.rates {
+        let (rate_provider, rates) = match rate_params.source {
+            RateSource::Owner { rates } => {
+                if rates.iter().any(|rate| rate.is_zero()) {
+                    return Err(ContractError::IncorrectRate {});
+                }
+                (None, rates)
+            }
+            RateSource::Provider { contract_addr } => (
+                Some(deps.api.addr_validate(&contract_addr)?),
+                [Decimal::one(); 2],
+            ),
+        };
+
+        RATES.save(
+            deps.storage,
+            &RateConfig {
+                rate_provider,
+                rates,
+                rates_updated_at: env.block.time.seconds(),
+                max_rate_age: rate_params.max_rate_age,
+            },
+        )?;
+    }
 
-    let token_name = format_lp_token_name(msg.asset_infos, &deps.querier)?;
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
+    let token_symbol = format_lp_token_symbol(&msg.asset_infos, &deps.querier, "SLP")?;
 
     // Create LP Token
     let sub_msg: Vec<SubMsg> = vec![SubMsg {
@@ -84,13 +145,15 @@ pub fn instantiate(
             code_id: msg.token_code_id,
             msg: to_binary(&TokenInstantiateMsg {
                 name: token_name,
-                symbol: "uLP".to_string(),
-                decimals: 6,
+                symbol: token_symbol,
+                // the minted shares are scaled to the greater precision of the assets.
+                decimals: precisions[0].max(precisions[1]),
                 initial_balances: vec![],
                 mint: Some(MinterResponse {
                     minter: env.contract.address.to_string(),
                     cap: None,
                 }),
+                marketing: params.lp_token_marketing,
             })?,
             funds: vec![],
             admin: None,
@@ -106,14 +169,27 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_TOKEN_REPLY_ID => set_liquidity_token(deps, msg),
+        FLASH_LOAN_REPLY_ID => repay_flash_loan(deps, env),
+        FLASH_SWAP_REPLY_ID => pay_flash_swap(deps, env),
+        _ => Err(ContractError::Std(StdError::generic_err(
+            "Unknown reply id",
+        ))),
+    }
+}
+
+// Saves the address of the LP token instantiated by the pair.
+fn set_liquidity_token(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
     if config.pair_info.liquidity_token != Addr::unchecked("") {
         return Err(ContractError::Unauthorized {});
     }
 
-    let res = cw_utils::parse_reply_instantiate_data(msg).unwrap();
+    let res = cw_utils::parse_reply_instantiate_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
 
     config.pair_info.liquidity_token = deps.api.addr_validate(res.contract_address.as_str())?;
 
@@ -127,6 +203,7 @@ pub struct SwapParams {
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    forward_msg: Option<Binary>,
 }
 
 // Exposes all the execute functions.
@@ -137,6 +214,12 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // the pool must not be used while its balances are lent out
+    if FLASH_LOAN.may_load(deps.storage)?.is_some() || FLASH_SWAP.may_load(deps.storage)?.is_some()
+    {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
@@ -145,7 +228,11 @@ pub fn execute(
             belief_price,
             max_spread,
             to,
+            forward_msg,
+            deadline,
         } => {
+            assert_deadline(&env.block, deadline)?;
+
             let to_addr = if let Some(addr) = to {
                 Some(deps.api.addr_validate(addr.as_str())?)
             } else {
@@ -166,11 +253,78 @@ pub fn execute(
                     belief_price,
                     max_spread,
                     to: to_addr,
+                    forward_msg,
+                },
+            )
+        }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            max_offer_amount,
+            to,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            let to_addr = if let Some(addr) = to {
+                Some(deps.api.addr_validate(addr.as_str())?)
+            } else {
+                None
+            };
+
+            // the offer asset is the other asset of the pair
+            let config: Config = CONFIG.load(deps.storage)?;
+            let offer_asset = Asset {
+                info: if ask_asset.info.equal(&config.pair_info.asset_infos[0]) {
+                    config.pair_info.asset_infos[1].clone()
+                } else {
+                    config.pair_info.asset_infos[0].clone()
                 },
+                amount: max_offer_amount,
+            };
+
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            swap_exact_out(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                ask_asset,
+                to_addr,
             )
         }
-        ExecuteMsg::ProvideLiquidity { assets, receiver } => {
-            provide_liquidity(deps, env, info, assets, receiver)
+        ExecuteMsg::FlashLoan {
+            assets,
+            callback_contract,
+            msg,
+        } => flash_loan(deps, env, assets, callback_contract, msg),
+        ExecuteMsg::FlashSwap {
+            ask_asset,
+            callback,
+        } => flash_swap(deps, env, info, ask_asset, callback),
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env, info),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            min_lp_to_receive,
+            receiver,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                min_lp_to_receive,
+                receiver,
+            )
         }
     }
 }
@@ -188,7 +342,11 @@ fn receive_cw20(
             belief_price,
             max_spread,
             to,
+            forward_msg,
+            deadline,
         }) => {
+            assert_deadline(&env.block, deadline)?;
+
             let mut authorized = false;
             let config: Config = CONFIG.load(deps.storage)?;
 
@@ -225,12 +383,87 @@ fn receive_cw20(
                     belief_price,
                     max_spread,
                     to: to_addr,
+                    forward_msg,
+                },
+            )
+        }
+        Ok(Cw20HookMsg::SwapExactOut {
+            ask_asset,
+            to,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            let config: Config = CONFIG.load(deps.storage)?;
+            let authorized = config.pair_info.asset_infos.iter().any(|asset_info| {
+                matches!(asset_info, AssetInfo::Token { contract_addr } if contract_addr == &info.sender)
+            });
+            if !authorized {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let sender = deps.api.addr_validate(msg.sender.as_str())?;
+
+            swap_exact_out(
+                deps,
+                env,
+                info,
+                sender,
+                Asset {
+                    info: AssetInfo::Token { contract_addr },
+                    amount: msg.amount,
                 },
+                ask_asset,
+                to_addr,
             )
         }
-        Ok(Cw20HookMsg::WithdrawLiquidity {}) => {
-            withdraw_liquidity(deps, env, info, Addr::unchecked(msg.sender), msg.amount)
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out,
+            receiver,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(msg.sender),
+                msg.amount,
+                min_assets_out,
+                receiver,
+            )
         }
+        Ok(Cw20HookMsg::WithdrawLiquidityOneCoin {
+            asset_info,
+            min_amount,
+        }) => withdraw_liquidity_one_coin(
+            deps,
+            env,
+            info,
+            Addr::unchecked(msg.sender),
+            msg.amount,
+            asset_info,
+            min_amount,
+        ),
+        Ok(Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets,
+            max_burn_amount,
+        }) => withdraw_liquidity_imbalance(
+            deps,
+            env,
+            info,
+            Addr::unchecked(msg.sender),
+            msg.amount,
+            assets,
+            max_burn_amount,
+        ),
         Err(err) => Err(ContractError::Std(err)),
     }
 }
@@ -240,6 +473,8 @@ fn provide_liquidity(
     env: Env,
     info: MessageInfo,
     assets: [Asset; 2],
+    slippage_tolerance: Option<Decimal>,
+    min_lp_to_receive: Option<Uint128>,
     receiver: Option<String>,
 ) -> Result<Response, ContractError> {
     assets[0].info.check(deps.api)?;
@@ -252,9 +487,8 @@ fn provide_liquidity(
 
     // get pools asset from pair contract
     let mut config: Config = CONFIG.load(deps.storage)?;
-    let mut pools: [Asset; 2] = config
-        .pair_info
-        .query_pools(&deps.querier, env.contract.address.clone())?;
+    assert_deposits_allowed(&config)?;
+    let pools = load_reserves(deps.storage, &config)?;
 
     // get the amount of coins the user want to deposit.
     let mut deposits: [Uint128; 2] = [
@@ -262,46 +496,28 @@ fn provide_liquidity(
             .iter()
             .find(|a| a.info.equal(&pools[0].info))
             .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
+            .ok_or_else(|| StdError::generic_err("Wrong asset info is given"))?,
         assets
             .iter()
             .find(|a| a.info.equal(&pools[1].info))
             .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
+            .ok_or_else(|| StdError::generic_err("Wrong asset info is given"))?,
     ];
 
     if deposits[0].is_zero() && deposits[1].is_zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let mut messages: Vec<CosmosMsg> = vec![];
-    for (i, pool) in pools.iter_mut().enumerate() {
+    for (i, pool) in pools.iter().enumerate() {
         if deposits[i].is_zero() && pool.amount.is_zero() {
             return Err(ContractError::InvalidProvideLPsWithSingleToken {});
         }
-
-        if !deposits[i].is_zero() {
-            if let AssetInfo::Token { contract_addr } = &pool.info {
-                // Add TransferFrom message to messages.
-                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: contract_addr.to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
-                        owner: info.sender.to_string(),
-                        recipient: env.contract.address.to_string(),
-                        amount: deposits[i],
-                    })?,
-                    funds: vec![],
-                }))
-            } else {
-                // If the asset is a native token, the pool balance already increased.
-                pool.amount = pool.amount.checked_sub(deposits[i])?;
-            }
-        }
     }
 
     // assert_provided_with_as_same_ratio
+    let mut refunds = [Uint128::zero(); 2];
     let amp = compute_current_amp(&config, &env)?;
-    if amp == MINIMUM_AMP && !pools[0].amount.is_zero() && !pools[1].amount.is_zero(){
+    if amp == MINIMUM_AMP && !pools[0].amount.is_zero() && !pools[1].amount.is_zero() {
         let reserve_a = Uint256::from(pools[0].amount);
         let reserve_b = Uint256::from(pools[1].amount);
         let amount_a = Uint256::from(deposits[0]);
@@ -313,26 +529,59 @@ fn provide_liquidity(
         let optimal_amount_a = Uint128::try_from(real_amount_a)?;
 
         if deposits[1] > optimal_amount_b {
+            refunds[1] = deposits[1] - optimal_amount_b;
             deposits[1] = optimal_amount_b;
         } else if deposits[0] > optimal_amount_a {
+            refunds[0] = deposits[0] - optimal_amount_a;
             deposits[0] = optimal_amount_a;
         }
     }
 
-    // Assert that slippage tolerance is respected
-    // assert_slippage_tolerance(&slippage_tolerance, &deposits, &pools)?;
+    // only the clipped deposits are taken, the excess native tokens are sent back.
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (i, pool) in pools.iter().enumerate() {
+        if let AssetInfo::Token { contract_addr } = &pool.info {
+            if !deposits[i].is_zero() {
+                // Add TransferFrom message to messages.
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i],
+                    })?,
+                    funds: vec![],
+                }))
+            }
+        } else if !refunds[i].is_zero() {
+            let refund_asset = Asset {
+                info: pool.info.clone(),
+                amount: refunds[i],
+            };
+            messages.push(refund_asset.transfer_msg(info.sender.clone())?);
+        }
+    }
 
     // decimals of each token.
-    let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
-    let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+    let token_precision_0 = config.precisions[0];
+    let token_precision_1 = config.precisions[1];
     let greater_precision = token_precision_0.max(token_precision_1);
 
-    let deposit_amount_0 = adjust_precision(deposits[0], token_precision_0, greater_precision)?;
-    let deposit_amount_1 = adjust_precision(deposits[1], token_precision_1, greater_precision)?;
+    // deposits and balances are valued at the redemption rates of the assets.
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+    let deposit_amount_0 = apply_rate(
+        adjust_precision(deposits[0], token_precision_0, greater_precision)?,
+        rates[0],
+    )?;
+    let deposit_amount_1 = apply_rate(
+        adjust_precision(deposits[1], token_precision_1, greater_precision)?,
+        rates[1],
+    )?;
 
     // total supply of liquidity token
     let total_supply = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
 
+    let mut fee_amounts = [Uint128::zero(); 2];
     let share = if total_supply.is_zero() {
         // If I'm a first liquidity provider.
         let lp_token_precision = query_token_precision(
@@ -354,36 +603,104 @@ fn provide_liquidity(
     } else {
         let leverage = compute_current_amp(&config, &env)?
             .checked_mul(u64::from(N_COINS))
-            .unwrap();
-
-        let mut pool_amount_0 =
-            adjust_precision(pools[0].amount, token_precision_0, greater_precision)?;
-        let mut pool_amount_1 =
-            adjust_precision(pools[1].amount, token_precision_1, greater_precision)?;
+            .ok_or(ContractError::MathOverflow {})?;
+
+        let old_pool_amounts = [
+            apply_rate(
+                adjust_precision(pools[0].amount, token_precision_0, greater_precision)?,
+                rates[0],
+            )?,
+            apply_rate(
+                adjust_precision(pools[1].amount, token_precision_1, greater_precision)?,
+                rates[1],
+            )?,
+        ];
+        let mut pool_amount_0 = old_pool_amounts[0];
+        let mut pool_amount_1 = old_pool_amounts[1];
 
         let d_before_addition_liquidity =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128())?;
 
         pool_amount_0 = pool_amount_0.checked_add(deposit_amount_0)?;
         pool_amount_1 = pool_amount_1.checked_add(deposit_amount_1)?;
 
         let d_after_addition_liquididty =
-            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+            compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128())?;
 
         if d_before_addition_liquidity >= d_after_addition_liquididty {
             return Err(ContractError::LiquidityAmountTooSmall {});
         }
 
-        total_supply.multiply_ratio(
-            d_after_addition_liquididty - d_before_addition_liquidity,
+        // charge the imbalance fee, otherwise a lopsided deposit followed by
+        // a proportional withdrawal would be a swap without fees.
+        let fees = compute_imbalance_fees(
+            old_pool_amounts,
+            [pool_amount_0, pool_amount_1],
+            Uint128::new(d_before_addition_liquidity),
+            Uint128::new(d_after_addition_liquididty),
+            compute_imbalance_fee_rate(compute_fee_rate(&config)),
+        );
+        fee_amounts = [
+            adjust_precision(
+                remove_rate(fees[0], rates[0])?,
+                greater_precision,
+                token_precision_0,
+            )?,
+            adjust_precision(
+                remove_rate(fees[1], rates[1])?,
+                greater_precision,
+                token_precision_1,
+            )?,
+        ];
+
+        pool_amount_0 = pool_amount_0.checked_sub(fees[0])?;
+        pool_amount_1 = pool_amount_1.checked_sub(fees[1])?;
+
+        let d_after_fee = compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128())?;
+
+        if d_before_addition_liquidity >= d_after_fee {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // the fee stays in the pool and is credited to the existing providers
+        // before minting, so the depositor does not get a part of it back.
+        let share = total_supply.multiply_ratio(
+            d_after_fee - d_before_addition_liquidity,
+            d_before_addition_liquidity + (d_after_addition_liquididty - d_after_fee),
+        );
+
+        // Assert that slippage tolerance is respected
+        let expected_share = total_supply.multiply_ratio(
+            deposit_amount_0.checked_add(deposit_amount_1)?,
             d_before_addition_liquidity,
-        )
+        );
+        assert_slippage_tolerance(slippage_tolerance, share, expected_share)?;
+
+        share
     };
 
     if share.is_zero() {
         return Err(ContractError::LiquidityAmountTooSmall {});
     }
 
+    if let Some(min_lp_to_receive) = min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpAmountAssertion {
+                minimum: min_lp_to_receive.u128(),
+                received: share.u128(),
+            });
+        }
+    }
+
+    // the deposits are added to the reserves, the imbalance fees stay in the pool.
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_add(deposits[0])?,
+            pools[1].amount.checked_add(deposits[1])?,
+        ],
+    )?;
+
     // mint lp token
     let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
     messages.push(mint_liquidity_token_message(
@@ -400,11 +717,18 @@ fn provide_liquidity(
         token_precision_0,
         pools[1].amount,
         token_precision_1,
+        rates,
     )? {
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
     }
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
@@ -412,8 +736,28 @@ fn provide_liquidity(
         attr("sender", info.sender.as_str()),
         attr("receiver", receiver.as_str()),
         attr("assets", format!("{}, {}", assets[0], assets[1])),
-        attr("deposits_evaluated", format!("{}, {}", format!("{}{}", deposits[0], pools[0].info), format!("{}{}", deposits[1], pools[1].info))),
+        attr(
+            "deposits_evaluated",
+            format!(
+                "{}{}, {}{}",
+                deposits[0], pools[0].info, deposits[1], pools[1].info
+            ),
+        ),
         attr("share", share.to_string()),
+        attr(
+            "fee_amounts",
+            format!(
+                "{}{}, {}{}",
+                fee_amounts[0], pools[0].info, fee_amounts[1], pools[1].info
+            ),
+        ),
+        attr(
+            "refund_assets",
+            format!(
+                "{}{}, {}{}",
+                refunds[0], pools[0].info, refunds[1], pools[1].info
+            ),
+        ),
     ]))
 }
 
@@ -423,34 +767,75 @@ fn withdraw_liquidity(
     info: MessageInfo,
     sender: Addr,
     amount: Uint128,
+    min_assets_out: Option<[Asset; 2]>,
+    receiver: Option<String>,
 ) -> Result<Response, ContractError> {
-    let mut config: Config = CONFIG.load(deps.storage).unwrap();
+    let mut config: Config = CONFIG.load(deps.storage)?;
 
     if info.sender != config.pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
 
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => sender.clone(),
+    };
+
     let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
     let refund_assets: [Asset; 2] = get_share_in_assets(&pools, amount, total_share);
 
-    // Accumulate prices for the assets in the pool
+    if let Some(min_assets_out) = min_assets_out {
+        for min_asset in min_assets_out.iter() {
+            let refund_asset = refund_assets
+                .iter()
+                .find(|a| a.info.equal(&min_asset.info))
+                .ok_or_else(|| StdError::generic_err("Wrong asset info is given"))?;
+
+            if refund_asset.amount < min_asset.amount {
+                return Err(ContractError::WithdrawAmountTooSmall {
+                    minimum: min_asset.amount.u128(),
+                    received: refund_asset.amount.u128(),
+                    asset: min_asset.info.clone(),
+                });
+            }
+        }
+    }
+
+    // Accumulate prices for the assets in the pool.
+    // Stale rates are fine here, a proportional withdrawal does not depend on them.
+    let rates = query_rates(deps.as_ref(), &env, &config)?.rates;
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
         env,
         &config,
         pools[0].amount,
-        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        config.precisions[0],
         pools[1].amount,
-        query_token_precision(&deps.querier, pools[1].info.clone())?,
+        config.precisions[1],
+        rates,
     )? {
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
     }
 
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_sub(refund_assets[0].amount)?,
+            pools[1].amount.checked_sub(refund_assets[1].amount)?,
+        ],
+    )?;
+
     let messages: Vec<CosmosMsg> = vec![
-        refund_assets[0].clone().transfer_msg(sender.clone())?,
-        refund_assets[1].clone().transfer_msg(sender.clone())?,
+        refund_assets[0].clone().transfer_msg(receiver.clone())?,
+        refund_assets[1].clone().transfer_msg(receiver.clone())?,
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: config.pair_info.liquidity_token.to_string(),
             msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
@@ -461,11 +846,12 @@ fn withdraw_liquidity(
     let attributes = vec![
         attr("action", "withdraw_liquidity"),
         attr("sender", sender.as_str()),
-        attr("withdrawn_share", &amount.to_string()),
+        attr("withdrawn_share", amount.to_string()),
         attr(
             "refund_assets",
             format!("{}, {}", refund_assets[0], refund_assets[1]),
         ),
+        attr("receiver", receiver.as_str()),
     ];
 
     Ok(Response::new()
@@ -473,86 +859,373 @@ fn withdraw_liquidity(
         .add_attributes(attributes))
 }
 
-/// Update configuration (mainly amplification parameter)
-fn update_config(
+fn withdraw_liquidity_one_coin(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    params: Binary,
+    sender: Addr,
+    amount: Uint128,
+    asset_info: AssetInfo,
+    min_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+    let mut config: Config = CONFIG.load(deps.storage)?;
 
-    if info.sender != factory_config.owner {
+    if info.sender != config.pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
 
-    match from_binary::<StablePoolUpdateParams>(&params)? {
-        StablePoolUpdateParams::StartChangingAmp {
-            next_amp,
-            next_amp_time,
-        } => start_changing_amp(config, deps, env, next_amp, next_amp_time)?,
-        StablePoolUpdateParams::StopChangingAmp {} => stop_changing_amp(config, deps, env)?,
+    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+    let (return_amount, fee_amount) = simulate_withdraw_one_coin(
+        &env,
+        &config,
+        &pools,
+        rates,
+        total_share,
+        amount,
+        &asset_info,
+    )?;
+
+    if let Some(min_amount) = min_amount {
+        if return_amount < min_amount {
+            return Err(ContractError::WithdrawAmountTooSmall {
+                minimum: min_amount.u128(),
+                received: return_amount.u128(),
+                asset: asset_info,
+            });
+        }
     }
 
-    Ok(Response::default())
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        config.precisions[0],
+        pools[1].amount,
+        config.precisions[1],
+        rates,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
+    }
+
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        if pool.info.equal(&asset_info) {
+            *reserve = reserve.checked_sub(return_amount)?;
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
+
+    let refund_asset = Asset {
+        info: asset_info,
+        amount: return_amount,
+    };
+
+    let messages: Vec<CosmosMsg> = vec![
+        refund_asset.clone().transfer_msg(sender.clone())?,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.pair_info.liquidity_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+            funds: vec![],
+        }),
+    ];
+
+    let attributes = vec![
+        attr("action", "withdraw_liquidity_one_coin"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("refund_asset", refund_asset.to_string()),
+        attr("fee_amount", fee_amount.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
 }
 
-fn swap(
+fn withdraw_liquidity_imbalance(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
-    params: SwapParams,
+    amount: Uint128,
+    assets: [Asset; 2],
+    max_burn_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    params.offer_asset.assert_sent_native_token_balance(&info)?;
-
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    let pools: Vec<Asset> = config
-        .pair_info
-        .query_pools(&deps.querier, env.clone().contract.address)?
-        .into_iter()
-        .map(|mut p| {
-            if p.info.equal(&params.offer_asset.info) {
-                p.amount = p.amount.checked_sub(params.offer_asset.amount).unwrap();
-            }
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
 
-            p
-        })
-        .collect();
+    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
 
-    let offer_pool: Asset;
-    let ask_pool: Asset;
+    if assets[0].info.equal(&assets[1].info) {
+        return Err(ContractError::DoublingAssets {});
+    }
 
-    if params.offer_asset.info.equal(&pools[0].info) {
-        offer_pool = pools[0].clone();
-        ask_pool = pools[1].clone();
-    } else if params.offer_asset.info.equal(&pools[1].info) {
-        offer_pool = pools[1].clone();
-        ask_pool = pools[0].clone();
-    } else {
-        return Err(ContractError::AssetMismatch {});
+    // get the amount of coins the user wants to receive, in the pool order.
+    let mut withdrawals = [Uint128::zero(); 2];
+    for asset in assets.iter() {
+        match pools.iter().position(|p| p.info.equal(&asset.info)) {
+            Some(i) => withdrawals[i] = asset.amount,
+            None => return Err(ContractError::AssetMismatch {}),
+        }
     }
 
-    let offer_amount = params.offer_asset.amount;
-    let ask_pool_info = ask_pool.info.clone();
+    if withdrawals[0].is_zero() && withdrawals[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
 
-    let (return_amount, spread_amount, commission_amount) = compute_swap(
-        offer_pool.amount,
-        query_token_precision(&deps.querier, offer_pool.info)?,
-        ask_pool.amount,
-        query_token_precision(&deps.querier, ask_pool.info)?,
-        offer_amount,
-        compute_global_fee(),
+    let token_precision_0 = config.precisions[0];
+    let token_precision_1 = config.precisions[1];
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+
+    let (burn_amount, fee_amounts) = compute_withdraw_imbalance(
+        [pools[0].amount, pools[1].amount],
+        [token_precision_0, token_precision_1],
+        rates,
+        withdrawals,
+        total_share,
+        compute_fee_rate(&config),
         compute_current_amp(&config, &env)?,
     )?;
 
-    // Check the max spread limit
-    assert_max_spread(
-        params.belief_price,
-        params.max_spread,
-        offer_amount,
+    let max_burn_amount = max_burn_amount.unwrap_or(amount).min(amount);
+    if burn_amount > max_burn_amount {
+        return Err(ContractError::MaxBurnAmountAssertion {
+            maximum: max_burn_amount.u128(),
+            required: burn_amount.u128(),
+        });
+    }
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        token_precision_0,
+        pools[1].amount,
+        token_precision_1,
+        rates,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
+    }
+
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_sub(withdrawals[0])?,
+            pools[1].amount.checked_sub(withdrawals[1])?,
+        ],
+    )?;
+
+    let refund_assets: Vec<Asset> = pools
+        .iter()
+        .zip(withdrawals)
+        .map(|(pool, amount)| Asset {
+            info: pool.info.clone(),
+            amount,
+        })
+        .collect();
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for refund_asset in refund_assets.iter() {
+        if !refund_asset.amount.is_zero() {
+            messages.push(refund_asset.clone().transfer_msg(sender.clone())?);
+        }
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: burn_amount,
+        })?,
+        funds: vec![],
+    }));
+
+    // give back the LP tokens which were not needed
+    let refund_share = amount.checked_sub(burn_amount)?;
+    if !refund_share.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.pair_info.liquidity_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: sender.to_string(),
+                amount: refund_share,
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    let attributes = vec![
+        attr("action", "withdraw_liquidity_imbalance"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", burn_amount.to_string()),
+        attr("refund_share", refund_share.to_string()),
+        attr(
+            "refund_assets",
+            format!("{}, {}", refund_assets[0], refund_assets[1]),
+        ),
+        attr(
+            "fee_amounts",
+            format!(
+                "{}{}, {}{}",
+                fee_amounts[0], pools[0].info, fee_amounts[1], pools[1].info
+            ),
+        ),
+    ];
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+// Returns the amount of `asset_info` and the fee for burning `amount` LP tokens into one coin.
+#[allow(clippy::too_many_arguments)]
+fn simulate_withdraw_one_coin(
+    env: &Env,
+    config: &Config,
+    pools: &[Asset; 2],
+    rates: [Decimal; 2],
+    total_share: Uint128,
+    amount: Uint128,
+    asset_info: &AssetInfo,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let (withdraw_index, other_index) = if asset_info.equal(&pools[0].info) {
+        (0, 1)
+    } else if asset_info.equal(&pools[1].info) {
+        (1, 0)
+    } else {
+        return Err(StdError::generic_err("Given asset doesn't belong to pairs").into());
+    };
+
+    compute_withdraw_one_coin(
+        pools[withdraw_index].amount,
+        config.precisions[withdraw_index],
+        rates[withdraw_index],
+        pools[other_index].amount,
+        config.precisions[other_index],
+        rates[other_index],
+        amount,
+        total_share,
+        compute_fee_rate(config),
+        compute_current_amp(config, env)?,
+    )
+}
+
+/// Update configuration (mainly amplification parameter)
+fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+
+    let params = from_binary::<StablePoolUpdateParams>(&params)?;
+
+    // the factory updates the status of its pairs in bulk
+    let is_factory_status_update = matches!(params, StablePoolUpdateParams::UpdateStatus { .. })
+        && info.sender == config.factory_addr;
+    if info.sender != factory_config.owner && !is_factory_status_update {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match params {
+        StablePoolUpdateParams::StartChangingAmp {
+            next_amp,
+            next_amp_time,
+        } => start_changing_amp(config, deps, env, next_amp, next_amp_time)?,
+        StablePoolUpdateParams::StopChangingAmp {} => stop_changing_amp(config, deps, env)?,
+        StablePoolUpdateParams::UpdateFee { fee_bps } => update_fee(config, deps, fee_bps)?,
+        StablePoolUpdateParams::UpdateRates { rates } => update_rates(deps, env, rates)?,
+        StablePoolUpdateParams::UpdateStatus { status } => update_status(config, deps, status)?,
+    }
+
+    Ok(Response::default())
+}
+
+fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    params: SwapParams,
+) -> Result<Response, ContractError> {
+    params.offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    let pools = load_reserves(deps.storage, &config)?;
+
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+    let offer_rate: Decimal;
+    let ask_rate: Decimal;
+    let offer_precision: u8;
+    let ask_precision: u8;
+
+    if params.offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+        offer_rate = rates[0];
+        ask_rate = rates[1];
+        offer_precision = config.precisions[0];
+        ask_precision = config.precisions[1];
+    } else if params.offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+        offer_rate = rates[1];
+        ask_rate = rates[0];
+        offer_precision = config.precisions[1];
+        ask_precision = config.precisions[0];
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let offer_amount = params.offer_asset.amount;
+    let ask_pool_info = ask_pool.info.clone();
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool.amount,
+        offer_precision,
+        offer_rate,
+        ask_pool.amount,
+        ask_precision,
+        ask_rate,
+        offer_amount,
+        compute_fee_rate(&config),
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    // Check the max spread limit
+    assert_max_spread(
+        params.belief_price,
+        params.max_spread,
+        offer_amount,
         return_amount + commission_amount,
         spread_amount,
     )?;
@@ -564,21 +1237,62 @@ fn swap(
     };
 
     let receiver = params.to.unwrap_or_else(|| sender.clone());
-    let messages = vec![return_asset.transfer_msg(receiver.clone())?];
+    let return_msg = match params.forward_msg {
+        Some(msg) => return_asset.send_msg(receiver.clone(), msg)?,
+        None => return_asset.transfer_msg(receiver.clone())?,
+    };
+    let mut messages = vec![return_msg];
+
+    // Send the protocol share of the commission to the fee address
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+    let maker_fee_amount = compute_maker_fee(&fee_info, commission_amount);
+    let mut sent_maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            sent_maker_fee_amount = maker_fee_amount;
+            let maker_fee = Asset {
+                info: ask_pool_info.clone(),
+                amount: maker_fee_amount,
+            };
+            messages.push(maker_fee.transfer_msg(fee_address)?);
+        }
+    }
+
+    // the offer joins the reserves, the return and the maker fee leave them.
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        if pool.info.equal(&params.offer_asset.info) {
+            *reserve = reserve.checked_add(offer_amount)?;
+        } else {
+            *reserve = reserve.checked_sub(return_amount + sent_maker_fee_amount)?;
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
         env,
         &config,
         pools[0].amount,
-        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        config.precisions[0],
         pools[1].amount,
-        query_token_precision(&deps.querier, pools[1].info.clone())?,
+        config.precisions[1],
+        rates,
     )? {
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
     }
 
     Ok(Response::new()
@@ -591,7 +1305,518 @@ fn swap(
         .add_attribute("offer_amount", offer_amount.to_string())
         .add_attribute("return_amount", return_amount.to_string())
         .add_attribute("spread_amount", spread_amount.to_string())
-        .add_attribute("commission_amount", commission_amount.to_string()))
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+// Swaps the offer asset for exactly `ask_asset`, using only the offer amount the swap needs.
+// `offer_asset` holds the maximum offer amount, the part of it that is not needed is refunded.
+fn swap_exact_out(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset: Asset,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    let asset_infos = &config.pair_info.asset_infos;
+    let (offer_index, ask_index) =
+        if offer_asset.info.equal(&asset_infos[0]) && ask_asset.info.equal(&asset_infos[1]) {
+            (0, 1)
+        } else if offer_asset.info.equal(&asset_infos[1]) && ask_asset.info.equal(&asset_infos[0]) {
+            (1, 0)
+        } else {
+            return Err(ContractError::AssetMismatch {});
+        };
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pools = load_reserves(deps.storage, &config)?;
+
+    if ask_asset.amount >= pools[ask_index].amount {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The ask amount exceeds the pool",
+        )));
+    }
+
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+    let fee_rate = compute_fee_rate(&config);
+    let amp = compute_current_amp(&config, &env)?;
+
+    let (offer_amount, _, _) = compute_offer_amount(
+        pools[offer_index].amount,
+        config.precisions[offer_index],
+        rates[offer_index],
+        pools[ask_index].amount,
+        config.precisions[ask_index],
+        rates[ask_index],
+        ask_asset.amount,
+        fee_rate,
+        amp,
+    )?;
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferAmountAssertion {
+            maximum: offer_asset.amount.u128(),
+            required: offer_amount.u128(),
+        });
+    }
+
+    // The reverse simulation rounds up, so the swap returns at least the ask amount.
+    // Any excess of the swap stays in the pool.
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        pools[offer_index].amount,
+        config.precisions[offer_index],
+        rates[offer_index],
+        pools[ask_index].amount,
+        config.precisions[ask_index],
+        rates[ask_index],
+        offer_amount,
+        fee_rate,
+        amp,
+    )?;
+    if return_amount < ask_asset.amount {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The offer amount does not cover the ask amount",
+        )));
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut messages = vec![ask_asset.clone().transfer_msg(receiver.clone())?];
+
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount)?;
+    if !refund_amount.is_zero() {
+        let refund_asset = Asset {
+            info: offer_asset.info.clone(),
+            amount: refund_amount,
+        };
+        messages.push(refund_asset.transfer_msg(sender.clone())?);
+    }
+
+    // Send the protocol share of the commission to the fee address
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+    let maker_fee_amount = compute_maker_fee(&fee_info, commission_amount);
+    let mut sent_maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            sent_maker_fee_amount = maker_fee_amount;
+            let maker_fee = Asset {
+                info: ask_asset.info.clone(),
+                amount: maker_fee_amount,
+            };
+            messages.push(maker_fee.transfer_msg(fee_address)?);
+        }
+    }
+
+    // the used offer joins the reserves, the ask asset and the maker fee leave them.
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    reserves[offer_index] = reserves[offer_index].checked_add(offer_amount)?;
+    reserves[ask_index] =
+        reserves[ask_index].checked_sub(ask_asset.amount + sent_maker_fee_amount)?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        config.precisions[0],
+        pools[1].amount,
+        config.precisions[1],
+        rates,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_asset.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", ask_asset.amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+// Lends `assets` to `callback_contract` and executes `msg` on it.
+// The balances the pool must be repaid to are checked in the reply of the callback.
+fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    assets: Vec<Asset>,
+    callback_contract: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    if assets.is_empty() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let callback_contract = deps.api.addr_validate(&callback_contract)?;
+    let pools = load_reserves(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut lent = [false; 2];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut owed = [Uint128::zero(); 2];
+    let mut fees: Vec<Asset> = vec![];
+    for asset in assets.iter() {
+        let i = pools
+            .iter()
+            .position(|pool| pool.info.equal(&asset.info))
+            .ok_or(ContractError::AssetMismatch {})?;
+        if lent[i] {
+            return Err(ContractError::DoublingAssets {});
+        }
+        lent[i] = true;
+
+        if asset.amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+
+        if asset.amount > pools[i].amount {
+            return Err(ContractError::Std(StdError::generic_err(
+                "The loan amount exceeds the pool",
+            )));
+        }
+
+        owed[i] = compute_flash_fee(&config, asset.amount)?;
+        fees.push(Asset {
+            info: asset.info.clone(),
+            amount: owed[i],
+        });
+        messages.push(asset.clone().transfer_msg(callback_contract.clone())?);
+    }
+
+    FLASH_LOAN.save(
+        deps.storage,
+        &FlashLoan {
+            balances: [balances[0].amount, balances[1].amount],
+            fees: owed,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: callback_contract.to_string(),
+                msg,
+                funds: vec![],
+            },
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attribute("action", "flash_loan")
+        .add_attribute("callback_contract", callback_contract)
+        .add_attribute(
+            "assets",
+            assets
+                .iter()
+                .map(|asset| asset.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+        .add_attribute(
+            "fees",
+            fees.iter()
+                .map(|fee| fee.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+}
+
+// Checks that the flash loan and its fee are back in the pool, and unlocks the pair.
+fn repay_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let flash_loan = FLASH_LOAN.load(deps.storage)?;
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    // the pair must hold its balances from before the loan and the fees on top of them
+    for (i, pool) in pools.iter().enumerate() {
+        let expected = flash_loan.balances[i].checked_add(flash_loan.fees[i])?;
+        if pool.amount < expected {
+            return Err(ContractError::FlashLoanNotRepaid {
+                expected: expected.u128(),
+                balance: pool.amount.u128(),
+                asset: pool.info.clone(),
+            });
+        }
+    }
+
+    // the fees join the reserves, a repayment above them can be skimmed.
+    let reserves = RESERVES.load(deps.storage)?;
+    RESERVES.save(
+        deps.storage,
+        &[
+            reserves[0].checked_add(flash_loan.fees[0])?,
+            reserves[1].checked_add(flash_loan.fees[1])?,
+        ],
+    )?;
+    FLASH_LOAN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("pools", format!("{}, {}", pools[0], pools[1])))
+}
+
+// Sends `ask_asset` to the caller and executes `callback` on it.
+// The offer of a swap to `ask_asset` is checked against the reserves in the reply of the callback.
+fn flash_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ask_asset: Asset,
+    callback: Binary,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    let pools = load_reserves(deps.storage, &config)?;
+    let (offer_index, ask_index) = if ask_asset.info.equal(&pools[1].info) {
+        (0, 1)
+    } else if ask_asset.info.equal(&pools[0].info) {
+        (1, 0)
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    };
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if ask_asset.amount >= pools[ask_index].amount {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The ask amount exceeds the pool",
+        )));
+    }
+
+    let rates = load_rates(deps.as_ref(), &env, &config)?;
+    let fee_rate = compute_fee_rate(&config);
+    let amp = compute_current_amp(&config, &env)?;
+
+    let (offer_amount, _, _) = compute_offer_amount(
+        pools[offer_index].amount,
+        config.precisions[offer_index],
+        rates[offer_index],
+        pools[ask_index].amount,
+        config.precisions[ask_index],
+        rates[ask_index],
+        ask_asset.amount,
+        fee_rate,
+        amp,
+    )?;
+    let (_, spread_amount, commission_amount) = compute_swap(
+        pools[offer_index].amount,
+        config.precisions[offer_index],
+        rates[offer_index],
+        pools[ask_index].amount,
+        config.precisions[ask_index],
+        rates[ask_index],
+        offer_amount,
+        fee_rate,
+        amp,
+    )?;
+
+    let mut messages = vec![ask_asset.clone().transfer_msg(info.sender.clone())?];
+
+    // Send the protocol share of the commission to the fee address
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+    let maker_fee_amount = compute_maker_fee(&fee_info, commission_amount);
+    let mut sent_maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            sent_maker_fee_amount = maker_fee_amount;
+            let maker_fee = Asset {
+                info: ask_asset.info.clone(),
+                amount: maker_fee_amount,
+            };
+            messages.push(maker_fee.transfer_msg(fee_address)?);
+        }
+    }
+
+    // the reserves once the offer is paid, the ask asset and the maker fee leave them.
+    let mut reserves = pools.clone();
+    reserves[offer_index].amount = reserves[offer_index].amount.checked_add(offer_amount)?;
+    reserves[ask_index].amount = reserves[ask_index]
+        .amount
+        .checked_sub(ask_asset.amount + sent_maker_fee_amount)?;
+    FLASH_SWAP.save(deps.storage, &reserves)?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        config.precisions[0],
+        pools[1].amount,
+        config.precisions[1],
+        rates,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: info.sender.to_string(),
+                msg: callback,
+                funds: vec![],
+            },
+            FLASH_SWAP_REPLY_ID,
+        ))
+        .add_attribute("action", "flash_swap")
+        .add_attribute("receiver", info.sender)
+        .add_attribute("offer_asset", pools[offer_index].info.to_string())
+        .add_attribute("ask_asset", ask_asset.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+// Checks that the reserves plus the offer of the flash swap are in the pool, and unlocks the pair.
+fn pay_flash_swap(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let expected = FLASH_SWAP.load(deps.storage)?;
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    for (pool, expected) in pools.iter().zip(expected.iter()) {
+        if pool.amount < expected.amount {
+            return Err(ContractError::FlashSwapNotPaid {
+                expected: expected.amount.u128(),
+                balance: pool.amount.u128(),
+                asset: pool.info.clone(),
+            });
+        }
+    }
+
+    // the offer joins the reserves, a payment above it can be skimmed.
+    RESERVES.save(deps.storage, &[expected[0].amount, expected[1].amount])?;
+    FLASH_SWAP.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "pay_flash_swap")
+        .add_attribute("pools", format!("{}, {}", pools[0], pools[1])))
+}
+
+// Sends the balances of the pair above its reserves to `to`.
+fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let to = deps.api.addr_validate(&to)?;
+
+    let reserves = load_reserves(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<String> = vec![];
+    for (balance, reserve) in balances.iter().zip(reserves.iter()) {
+        let surplus = Asset {
+            info: balance.info.clone(),
+            amount: balance.amount.saturating_sub(reserve.amount),
+        };
+        if !surplus.amount.is_zero() {
+            skimmed.push(surplus.to_string());
+            messages.push(surplus.transfer_msg(to.clone())?);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to)
+        .add_attribute("assets", skimmed.join(", ")))
+}
+
+// Sets the reserves of the pair to its balances, the prices are accumulated with the former reserves.
+// Only the factory owner can sync, as the tokens sent to the pair are credited to the providers.
+fn sync(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reserves = load_reserves(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    // Stale rates are fine here, the reserves are taken as they are.
+    let rates = query_rates(deps.as_ref(), &env, &config)?.rates;
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        reserves[0].amount,
+        config.precisions[0],
+        reserves[1].amount,
+        config.precisions[1],
+        rates,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+        store_observation(
+            deps.storage,
+            block_time,
+            price0_cumulative_new,
+            price1_cumulative_new,
+        )?;
+    }
+
+    RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", format!("{}, {}", balances[0], balances[1])))
 }
 
 // Exposes all query functions.
@@ -609,6 +1834,25 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_binary(&query_reverse_simulation(deps, env, ask_asset)?)
         }
         QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::SimulateWithdrawOneCoin {
+            lp_amount,
+            asset_info,
+        } => to_binary(&query_simulate_withdraw_one_coin(
+            deps, env, lp_amount, asset_info,
+        )?),
+        QueryMsg::SimulateWithdraw { lp_amount } => {
+            to_binary(&query_simulate_withdraw(deps, lp_amount)?)
+        }
+        QueryMsg::Rates {} => to_binary(&query_rates_in_use(deps, env)?),
+        QueryMsg::VirtualPrice {} => to_binary(&query_virtual_price(deps, env)?),
+        QueryMsg::SpotPrice { offer_asset_info } => {
+            to_binary(&query_spot_price(deps, env, offer_asset_info)?)
+        }
+        QueryMsg::Observe { seconds_ago } => to_binary(&query_observe(deps, env, seconds_ago)?),
+        QueryMsg::TwapPrice {
+            asset_info,
+            window_seconds,
+        } => to_binary(&query_twap_price(deps, env, asset_info, window_seconds)?),
     }
 }
 
@@ -639,21 +1883,45 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<[Asset; 2]> {
     Ok(owned_assets)
 }
 
+// Returns the assets refunded for withdrawing `lp_amount` LP tokens
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    lp_amount: Uint128,
+) -> StdResult<SimulateWithdrawResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config)?;
+
+    Ok(SimulateWithdrawResponse {
+        refund_assets: get_share_in_assets(&pools, lp_amount, total_share),
+    })
+}
+
 // Returns information about a swap simulation
 pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<SimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools = load_reserves(deps.storage, &config)?;
+    let rates = load_rates(deps, &env, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
+    let offer_rate: Decimal;
+    let ask_rate: Decimal;
+    let offer_precision: u8;
+    let ask_precision: u8;
     if offer_asset.info.equal(&pools[0].info) {
         offer_pool = pools[0].clone();
         ask_pool = pools[1].clone();
+        offer_rate = rates[0];
+        ask_rate = rates[1];
+        offer_precision = config.precisions[0];
+        ask_precision = config.precisions[1];
     } else if offer_asset.info.equal(&pools[1].info) {
         offer_pool = pools[1].clone();
         ask_pool = pools[0].clone();
+        offer_rate = rates[1];
+        ask_rate = rates[0];
+        offer_precision = config.precisions[1];
+        ask_precision = config.precisions[0];
     } else {
         return Err(StdError::generic_err(
             "Given offer asset doesn't belong to pairs",
@@ -662,18 +1930,27 @@ pub fn query_simulation(deps: Deps, env: Env, offer_asset: Asset) -> StdResult<S
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
         offer_pool.amount,
-        query_token_precision(&deps.querier, offer_pool.info)?,
+        offer_precision,
+        offer_rate,
         ask_pool.amount,
-        query_token_precision(&deps.querier, ask_pool.info)?,
+        ask_precision,
+        ask_rate,
         offer_asset.amount,
-        compute_global_fee(),
+        compute_fee_rate(&config),
         compute_current_amp(&config, &env)?,
     )?;
 
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+
     Ok(SimulationResponse {
         return_amount,
         spread_amount,
         commission_amount,
+        maker_fee_amount: compute_maker_fee(&fee_info, commission_amount),
     })
 }
 
@@ -684,18 +1961,29 @@ pub fn query_reverse_simulation(
     ask_asset: Asset,
 ) -> StdResult<ReverseSimulationResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let contract_addr = config.pair_info.contract_addr.clone();
-
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools = load_reserves(deps.storage, &config)?;
+    let rates = load_rates(deps, &env, &config)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
+    let offer_rate: Decimal;
+    let ask_rate: Decimal;
+    let offer_precision: u8;
+    let ask_precision: u8;
     if ask_asset.info.equal(&pools[0].info) {
         ask_pool = pools[0].clone();
         offer_pool = pools[1].clone();
+        ask_rate = rates[0];
+        offer_rate = rates[1];
+        ask_precision = config.precisions[0];
+        offer_precision = config.precisions[1];
     } else if ask_asset.info.equal(&pools[1].info) {
         ask_pool = pools[1].clone();
         offer_pool = pools[0].clone();
+        ask_rate = rates[1];
+        offer_rate = rates[0];
+        ask_precision = config.precisions[1];
+        offer_precision = config.precisions[0];
     } else {
         return Err(StdError::generic_err(
             "Given ask asset doesn't belong to pairs",
@@ -704,11 +1992,13 @@ pub fn query_reverse_simulation(
 
     let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
         offer_pool.amount,
-        query_token_precision(&deps.querier, offer_pool.info)?,
+        offer_precision,
+        offer_rate,
         ask_pool.amount,
-        query_token_precision(&deps.querier, ask_pool.info)?,
+        ask_precision,
+        ask_rate,
         ask_asset.amount,
-        compute_global_fee(),
+        compute_fee_rate(&config),
         compute_current_amp(&config, &env)?,
     )?;
 
@@ -719,6 +2009,33 @@ pub fn query_reverse_simulation(
     })
 }
 
+// Returns information about a single asset withdrawal simulation
+pub fn query_simulate_withdraw_one_coin(
+    deps: Deps,
+    env: Env,
+    lp_amount: Uint128,
+    asset_info: AssetInfo,
+) -> StdResult<SimulateWithdrawOneCoinResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+    let rates = load_rates(deps, &env, &config)?;
+
+    let (return_amount, fee_amount) = simulate_withdraw_one_coin(
+        &env,
+        &config,
+        &pools,
+        rates,
+        total_share,
+        lp_amount,
+        &asset_info,
+    )?;
+
+    Ok(SimulateWithdrawOneCoinResponse {
+        return_amount,
+        fee_amount,
+    })
+}
+
 // Returns price oracle
 pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
@@ -726,14 +2043,16 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
 
     let mut price0_cumulative_last = config.price0_cumulative_last;
     let mut price1_cumulative_last = config.price1_cumulative_last;
+    let rates = query_rates(deps, &env, &config)?.rates;
 
     if let Some((price0_cumulative_new, price1_cumulative_new, _)) = accumulate_prices(
         env,
         &config,
         assets[0].amount,
-        query_token_precision(&deps.querier, assets[0].info.clone())?,
+        config.precisions[0],
         assets[1].amount,
-        query_token_precision(&deps.querier, assets[1].info.clone())?,
+        config.precisions[1],
+        rates,
     )? {
         price0_cumulative_last = price0_cumulative_new;
         price1_cumulative_last = price1_cumulative_new;
@@ -749,20 +2068,234 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     Ok(resp)
 }
 
+// Returns the cumulative prices `seconds_ago` from the current block
+pub fn query_observe(deps: Deps, env: Env, seconds_ago: Vec<u64>) -> StdResult<ObserveResponse> {
+    let block_time = env.block.time.seconds();
+    let latest = query_cumulative_prices(deps, env)?;
+    let latest = Observation {
+        timestamp: block_time,
+        price0_cumulative: latest.price0_cumulative_last,
+        price1_cumulative: latest.price1_cumulative_last,
+    };
+
+    let observations = seconds_ago
+        .into_iter()
+        .map(|seconds_ago| {
+            let timestamp = block_time.checked_sub(seconds_ago).ok_or_else(|| {
+                StdError::generic_err("The requested time is older than the oldest observation")
+            })?;
+            observe(deps.storage, &latest, timestamp)
+        })
+        .collect::<StdResult<Vec<Observation>>>()?;
+
+    Ok(ObserveResponse { observations })
+}
+
+// Returns the time-weighted average price of `asset_info` over the last `window_seconds`
+pub fn query_twap_price(
+    deps: Deps,
+    env: Env,
+    asset_info: AssetInfo,
+    window_seconds: u64,
+) -> StdResult<TwapPriceResponse> {
+    if window_seconds == 0 {
+        return Err(StdError::generic_err("The window must not be empty"));
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let asset_infos = config.pair_info.asset_infos;
+    let index = if asset_info.equal(&asset_infos[0]) {
+        0
+    } else if asset_info.equal(&asset_infos[1]) {
+        1
+    } else {
+        return Err(StdError::generic_err("Given asset doesn't belong to pairs"));
+    };
+
+    let res = query_observe(deps, env, vec![window_seconds, 0])?;
+    let (start, end) = (&res.observations[0], &res.observations[1]);
+    let cumulative_diff = if index == 0 {
+        end.price0_cumulative.wrapping_sub(start.price0_cumulative)
+    } else {
+        end.price1_cumulative.wrapping_sub(start.price1_cumulative)
+    };
+
+    Ok(TwapPriceResponse {
+        asset_info,
+        ask_asset_info: asset_infos[1 - index].clone(),
+        price: Decimal::from_ratio(
+            cumulative_diff,
+            Uint128::from(window_seconds)
+                .checked_mul(Uint128::new(10_u128.pow(u32::from(TWAP_PRECISION))))?,
+        ),
+    })
+}
+
+// Returns the value of one LP token, which is D divided by the LP supply
+pub fn query_virtual_price(deps: Deps, env: Env) -> StdResult<VirtualPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config.clone())?;
+
+    if total_share.is_zero() {
+        return Ok(VirtualPriceResponse {
+            virtual_price: Decimal::zero(),
+        });
+    }
+
+    let rates = load_rates(deps, &env, &config)?;
+    let token_precision_0 = config.precisions[0];
+    let token_precision_1 = config.precisions[1];
+    let greater_precision = token_precision_0.max(token_precision_1);
+
+    let leverage = compute_current_amp(&config, &env)?
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
+    let d = compute_d(
+        leverage,
+        apply_rate(
+            adjust_precision(pools[0].amount, token_precision_0, greater_precision)?,
+            rates[0],
+        )?
+        .u128(),
+        apply_rate(
+            adjust_precision(pools[1].amount, token_precision_1, greater_precision)?,
+            rates[1],
+        )?
+        .u128(),
+    )?;
+
+    let lp_token_precision = query_token_precision(
+        &deps.querier,
+        AssetInfo::Token {
+            contract_addr: config.pair_info.liquidity_token,
+        },
+    )?;
+
+    Ok(VirtualPriceResponse {
+        virtual_price: Decimal::from_ratio(
+            adjust_precision(Uint128::new(d), greater_precision, lp_token_precision)?,
+            total_share,
+        ),
+    })
+}
+
+// Returns the marginal price of the offer asset in the other asset of the pair
+pub fn query_spot_price(
+    deps: Deps,
+    env: Env,
+    offer_asset_info: AssetInfo,
+) -> StdResult<SpotPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools = load_reserves(deps.storage, &config)?;
+    let rates = load_rates(deps, &env, &config)?;
+
+    let (offer_index, ask_index) = if offer_asset_info.equal(&pools[0].info) {
+        (0, 1)
+    } else if offer_asset_info.equal(&pools[1].info) {
+        (1, 0)
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs",
+        ));
+    };
+
+    let token_precision_0 = config.precisions[0];
+    let token_precision_1 = config.precisions[1];
+    let greater_precision = token_precision_0.max(token_precision_1);
+    let xp = [
+        apply_rate(
+            adjust_precision(pools[0].amount, token_precision_0, greater_precision)?,
+            rates[0],
+        )?,
+        apply_rate(
+            adjust_precision(pools[1].amount, token_precision_1, greater_precision)?,
+            rates[1],
+        )?,
+    ];
+
+    if xp[0].is_zero() || xp[1].is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    let leverage = compute_current_amp(&config, &env)?
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
+    let price = compute_spot_price(leverage, xp[offer_index].u128(), xp[ask_index].u128())?;
+
+    // the derivative is taken between the valued amounts
+    let price = price
+        .checked_mul(rates[offer_index])
+        .ok()
+        .and_then(|price| {
+            Decimal::checked_from_ratio(price.atomics(), rates[ask_index].atomics()).ok()
+        })
+        .ok_or(ContractError::MathOverflow {})?;
+
+    Ok(SpotPriceResponse {
+        offer_asset_info,
+        ask_asset_info: pools[ask_index].info.clone(),
+        price,
+    })
+}
+
+// Returns the redemption rates in use
+pub fn query_rates_in_use(deps: Deps, env: Env) -> StdResult<RatesResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    query_rates(deps, &env, &config)
+}
+
 // Returns current configuration
 pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
     let config = CONFIG.load(deps.storage)?;
 
     Ok(ConfigResponse {
         block_time_last: config.block_time_last,
+        status: config.status,
         params: Some(to_binary(&StablePoolConfig {
             amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_bps: config.fee_bps,
         })?),
     })
 }
 
 // Used for contract migration.
+// Rejects other contracts and downgrades, then upgrades the state stored by older versions.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    if contract_version.contract != CONTRACT_NAME {
+        return Err(ContractError::MigrationError {});
+    }
+
+    let stored_version: Version = contract_version
+        .version
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    if stored_version > new_version {
+        return Err(ContractError::MigrationError {});
+    }
+
+    if stored_version < Version::new(1, 1, 0) {
+        migrate_config_from_v100(deps.branch())?;
+    }
+
+    // Pairs instantiated before the reserves were tracked start from their balances.
+    if RESERVES.may_load(deps.storage)?.is_none() {
+        let config: Config = CONFIG.load(deps.storage)?;
+        let pools: [Asset; 2] = config
+            .pair_info
+            .query_pools(&deps.querier, env.contract.address)?;
+        RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("previous_contract_version", contract_version.version),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
 }
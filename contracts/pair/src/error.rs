@@ -1,7 +1,8 @@
 use crate::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use crate::utils::MAX_FEE_BPS;
 use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError};
-use thiserror::Error;
 use novaswap::asset::AssetInfo;
+use thiserror::Error;
 
 /// ## Description
 /// This enum describes stableswap pair contract errors!
@@ -22,8 +23,27 @@ pub enum ContractError {
     #[error("Insufficient amount of liquidity")]
     LiquidityAmountTooSmall {},
 
-    #[error("Insuffcient amount of liquidity, minimum: {minimum}, provided: {provided}, asset: {asset}")]
-    InsufficientLiquidity { minimum: u128, provided: u128, asset: AssetInfo },
+    #[error(
+        "Insuffcient amount of liquidity, minimum: {minimum}, provided: {provided}, asset: {asset}"
+    )]
+    InsufficientLiquidity {
+        minimum: u128,
+        provided: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Withdrawn amount is less than the minimum, minimum: {minimum}, received: {received}, asset: {asset}")]
+    WithdrawAmountTooSmall {
+        minimum: u128,
+        received: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Required LP amount exceeds the maximum, maximum: {maximum}, required: {required}")]
+    MaxBurnAmountAssertion { maximum: u128, required: u128 },
+
+    #[error("Required offer amount exceeds the maximum, maximum: {maximum}, required: {required}")]
+    MaxOfferAmountAssertion { maximum: u128, required: u128 },
 
     #[error("Provided spread amount exceeds allowed limit")]
     AllowedSpreadAssertion {},
@@ -34,6 +54,12 @@ pub enum ContractError {
     #[error("Operation exceeds max splippage tolerance")]
     MaxSlippageAssertion {},
 
+    #[error("Provided slippage tolerance exceeds allowed limit")]
+    AllowedSlippageAssertion {},
+
+    #[error("Minted LP amount is less than the minimum, minimum: {minimum}, received: {received}")]
+    MinLpAmountAssertion { minimum: u128, received: u128 },
+
     #[error("Native token balance mismatch between the argument and the transferred")]
     AssetMismatch {},
 
@@ -58,11 +84,61 @@ pub enum ContractError {
     )]
     MinAmpChangingTimeAssertion {},
 
+    #[error("Fee must be less than or equal to {} bps", MAX_FEE_BPS)]
+    IncorrectFee {},
+
+    #[error("Redemption rates must be greater than zero")]
+    IncorrectRate {},
+
+    #[error("Redemption rates are stale, last updated at {updated_at}")]
+    StaleRates { updated_at: u64 },
+
+    #[error("Redemption rates of this pair are not set by the owner")]
+    RatesNotUpdatable {},
+
     #[error("You need to provide init params")]
     InitParamsNotFound {},
 
     #[error("It is not possible to provide liquidity with one token for an empty pool")]
     InvalidProvideLPsWithSingleToken {},
+
+    #[error("Swaps are paused in this pool")]
+    SwapsPaused {},
+
+    #[error("The pool only allows withdrawals")]
+    WithdrawOnly {},
+
+    #[error("The transaction deadline has passed")]
+    Expired {},
+
+    #[error("The pair is locked while a flash loan is out")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan is not repaid, expected: {expected}, balance: {balance}, asset: {asset}")]
+    FlashLoanNotRepaid {
+        expected: u128,
+        balance: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Flash swap is not paid, expected: {expected}, balance: {balance}, asset: {asset}")]
+    FlashSwapNotPaid {
+        expected: u128,
+        balance: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("The stableswap invariant did not converge")]
+    NonConvergence {},
+
+    #[error("Arithmetic overflow in the pair math")]
+    MathOverflow {},
+
+    #[error("Insufficient reserves, requested: {requested}, available: {available}")]
+    InsufficientReserves { requested: u128, available: u128 },
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
 }
 
 impl From<OverflowError> for ContractError {
@@ -75,4 +151,14 @@ impl From<ConversionOverflowError> for ContractError {
     fn from(o: ConversionOverflowError) -> Self {
         StdError::from(o).into()
     }
-}
\ No newline at end of file
+}
+
+// Queries return the standard errors as they are and the errors of the pair as generic errors.
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 pub mod contract;
 pub mod error;
 pub mod math;
+pub mod migration;
 pub mod state;
 pub mod utils;
 
@@ -1,12 +1,14 @@
 /// Stableswap Algorithm
 /// reference: https://miguelmota.com/blog/understanding-stableswap-curve/
+use crate::error::ContractError;
 use crate::state::Config;
-use cosmwasm_std::{Env, StdResult, Uint128};
+use cosmwasm_std::{Decimal, Env, StdResult, Uint128};
 use novaswap::U256;
 use std::convert::TryFrom;
 
 const N_COINS_SQUARED: u8 = 4;
 const ITERATIONS: u8 = 32;
+const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
 
 pub const N_COINS: u8 = 2;
 pub const MAX_AMP: u64 = 1_000_000;
@@ -30,16 +32,21 @@ pub fn calc_ask_amount(
     ask_pool: u128,
     offer_amount: u128,
     amp: u64,
-) -> Option<u128> {
-    let leverage = amp.checked_mul(u64::from(N_COINS)).unwrap();
-    let new_offer_pool = offer_pool + offer_amount;
+) -> Result<u128, ContractError> {
+    let leverage = amp
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
+    let new_offer_pool = offer_pool
+        .checked_add(offer_amount)
+        .ok_or(ContractError::MathOverflow {})?;
 
-    let d = compute_d(leverage, offer_pool, ask_pool).unwrap();
+    let d = compute_d(leverage, offer_pool, ask_pool)?;
 
     let new_ask_pool = compute_new_balance(leverage, new_offer_pool, d)?;
 
-    let amount_swapped = ask_pool - new_ask_pool;
-    Some(amount_swapped)
+    // The rounding of D can leave the new balance a unit above the pool for dust amounts
+    let amount_swapped = ask_pool.saturating_sub(new_ask_pool);
+    Ok(amount_swapped)
 }
 
 /// ## Description
@@ -57,16 +64,26 @@ pub fn calc_offer_amount(
     ask_pool: u128,
     ask_amount: u128,
     amp: u64,
-) -> Option<u128> {
-    let leverage = amp.checked_mul(u64::from(N_COINS)).unwrap();
+) -> Result<u128, ContractError> {
+    // The whole ask pool can never be bought, its price grows without bound
+    if ask_amount >= ask_pool {
+        return Err(ContractError::InsufficientReserves {
+            requested: ask_amount,
+            available: ask_pool,
+        });
+    }
+
+    let leverage = amp
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
     let new_ask_pool = ask_pool - ask_amount;
 
-    let d = compute_d(leverage, offer_pool, ask_pool).unwrap();
+    let d = compute_d(leverage, offer_pool, ask_pool)?;
 
     let new_offer_pool = compute_new_balance(leverage, new_ask_pool, d)?;
 
-    let amount_swapped = new_offer_pool - offer_pool;
-    Some(amount_swapped)
+    let amount_swapped = new_offer_pool.saturating_sub(offer_pool);
+    Ok(amount_swapped)
 }
 
 /// ## Description
@@ -82,37 +99,55 @@ pub fn calc_offer_amount(
 /// * **amount_a** is an object of type [`u128`].
 ///
 /// * **amount_b** is an object of type [`u128`].
-pub fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
-    let amount_a_times_coins =
-        checked_u8_mul(&U256::from(amount_a), N_COINS)?.checked_add(U256::one())?;
-    let amount_b_times_coins =
-        checked_u8_mul(&U256::from(amount_b), N_COINS)?.checked_add(U256::one())?;
-    let sum_x = amount_a.checked_add(amount_b)?; // sum(x_i), a.k.a S
+pub fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Result<u128, ContractError> {
+    let sum_x = amount_a
+        .checked_add(amount_b)
+        .ok_or(ContractError::MathOverflow {})?; // sum(x_i), a.k.a S
     if sum_x == 0 {
-        Some(0)
-    } else {
-        let mut d_previous: U256;
-        let mut d: U256 = sum_x.into();
-
-        // Newton's method to approximate D
-        for _ in 0..ITERATIONS {
-            let mut d_product = d;
-            d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_a_times_coins)?;
-            d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_b_times_coins)?;
-            d_previous = d;
-            // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
-            d = calculate_step(&d, leverage, sum_x, &d_product)?;
-            // Equality with the precision of 1
-            if d == d_previous {
-                break;
-            }
+        return Ok(0);
+    }
+
+    let amount_a_times_coins = checked_u8_mul(&U256::from(amount_a), N_COINS)
+        .and_then(|amount| amount.checked_add(U256::one()))
+        .ok_or(ContractError::MathOverflow {})?;
+    let amount_b_times_coins = checked_u8_mul(&U256::from(amount_b), N_COINS)
+        .and_then(|amount| amount.checked_add(U256::one()))
+        .ok_or(ContractError::MathOverflow {})?;
+
+    let mut d_previous: U256 = U256::zero();
+    let mut d: U256 = sum_x.into();
+
+    // Newton's method to approximate D
+    for _ in 0..ITERATIONS {
+        let d_product = compute_d_product(&d, &amount_a_times_coins, &amount_b_times_coins)
+            .ok_or(ContractError::MathOverflow {})?;
+        d_previous = d;
+        // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
+        d = calculate_step(&d, leverage, sum_x, &d_product)
+            .ok_or(ContractError::MathOverflow {})?;
+        // Equality with the precision of 1
+        if d == d_previous {
+            break;
         }
-        u128::try_from(d).ok()
     }
+
+    if !has_converged(&d, &d_previous) {
+        return Err(ContractError::NonConvergence {});
+    }
+    u128::try_from(d).map_err(|_| ContractError::MathOverflow {})
+}
+
+/// ## Description
+/// Computes D**(n+1) / (n**n * prod(x_i)) from the amounts of each coin multiplied by n.
+fn compute_d_product(
+    d: &U256,
+    amount_a_times_coins: &U256,
+    amount_b_times_coins: &U256,
+) -> Option<U256> {
+    d.checked_mul(*d)?
+        .checked_div(*amount_a_times_coins)?
+        .checked_mul(*d)?
+        .checked_div(*amount_b_times_coins)
 }
 
 /// ## Description
@@ -144,37 +179,126 @@ fn calculate_step(initial_d: &U256, leverage: u64, sum_x: u128, d_product: &U256
 /// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
 ///
 /// y**2 + b*y = c
-fn compute_new_balance(leverage: u64, new_source_amount: u128, d_val: u128) -> Option<u128> {
+pub fn compute_new_balance(
+    leverage: u64,
+    new_source_amount: u128,
+    d_val: u128,
+) -> Result<u128, ContractError> {
     // Upscale to U256
     let leverage: U256 = leverage.into();
     let new_source_amount: U256 = new_source_amount.into();
     let d_val: U256 = d_val.into();
 
+    let (b, c) = compute_new_balance_coefficients(&leverage, &new_source_amount, &d_val)
+        .ok_or(ContractError::MathOverflow {})?;
+
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y_prev: U256 = U256::zero();
+    let mut y = d_val;
+    for _ in 0..ITERATIONS {
+        y_prev = y;
+        y = compute_new_balance_step(&y, &b, &c, &d_val).ok_or(ContractError::MathOverflow {})?;
+        if y == y_prev {
+            break;
+        }
+    }
+
+    if !has_converged(&y, &y_prev) {
+        return Err(ContractError::NonConvergence {});
+    }
+    u128::try_from(y).map_err(|_| ContractError::MathOverflow {})
+}
+
+/// ## Description
+/// Returns the coefficients b and c of the equation solved by `compute_new_balance`.
+fn compute_new_balance_coefficients(
+    leverage: &U256,
+    new_source_amount: &U256,
+    d_val: &U256,
+) -> Option<(U256, U256)> {
     // sum' = prod' = x
     // c =  D ** (n + 1) / (n ** (2 * n) * prod' * A)
-    let c = checked_u8_power(&d_val, N_COINS.checked_add(1)?)?
+    let c = checked_u8_power(d_val, N_COINS.checked_add(1)?)?
         .checked_mul(U256::from(AMP_PRECISION))?
-        .checked_div(checked_u8_mul(&new_source_amount, N_COINS_SQUARED)?.checked_mul(leverage)?)?;
+        .checked_div(checked_u8_mul(new_source_amount, N_COINS_SQUARED)?.checked_mul(*leverage)?)?;
 
     // b = sum' - (A*n**n - 1) * D / (A * n**n)
     let b = new_source_amount.checked_add(
         d_val
             .checked_mul(U256::from(AMP_PRECISION))?
-            .checked_div(leverage)?,
+            .checked_div(*leverage)?,
     )?;
 
-    // Solve for y by approximating: y**2 + b*y = c
-    let mut y_prev: U256;
-    let mut y = d_val;
-    for _ in 0..ITERATIONS {
-        y_prev = y;
-        y = (checked_u8_power(&y, 2)?.checked_add(c)?)
-            .checked_div(checked_u8_mul(&y, 2)?.checked_add(b)?.checked_sub(d_val)?)?;
-        if y == y_prev {
-            break;
-        }
-    }
-    u128::try_from(y).ok()
+    Some((b, c))
+}
+
+/// ## Description
+/// A Newton step of `compute_new_balance`: y = (y**2 + c) / (2 * y + b - D)
+fn compute_new_balance_step(y: &U256, b: &U256, c: &U256, d_val: &U256) -> Option<U256> {
+    (checked_u8_power(y, 2)?.checked_add(*c)?)
+        .checked_div(checked_u8_mul(y, 2)?.checked_add(*b)?.checked_sub(*d_val)?)
+}
+
+/// ## Description
+/// Returns whether the last two estimates of Newton's method are at most one unit apart.
+/// The integer rounding can make the estimates alternate around the root instead of settling.
+fn has_converged(current: &U256, previous: &U256) -> bool {
+    let difference = if current > previous {
+        *current - *previous
+    } else {
+        *previous - *current
+    };
+    difference <= U256::one()
+}
+
+/// ## Description
+/// Computes the marginal price of the offer tokens in ask tokens (-dy/dx) from the derivative of the invariant.
+///
+/// * **Equation**
+///
+/// -dy/dx = (leverage + D**(n+1) / (n**n * x**2 * y)) / (leverage + D**(n+1) / (n**n * x * y**2))
+/// ## Params
+/// * **leverage** is an object of type [`u64`].
+///
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens (x) in the pool.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens (y) in the pool.
+pub fn compute_spot_price(
+    leverage: u64,
+    offer_pool: u128,
+    ask_pool: u128,
+) -> Result<Decimal, ContractError> {
+    let d = U256::from(compute_d(leverage, offer_pool, ask_pool)?);
+    let x = U256::from(offer_pool);
+    let y = U256::from(ask_pool);
+    let one = U256::from(DECIMAL_FRACTIONAL);
+
+    // the invariant is computed with the leverage in place of A * n**n
+    let ann = U256::from(leverage)
+        .checked_mul(one)
+        .ok_or(ContractError::MathOverflow {})?
+        / AMP_PRECISION;
+
+    // D**(n+1) / (n**n * a**2 * b), scaled by `one`
+    let partial = |a: U256, b: U256| -> Option<U256> {
+        d.checked_mul(d)?
+            .checked_div(a)?
+            .checked_mul(d)?
+            .checked_div(a)?
+            .checked_mul(one)?
+            .checked_div(checked_u8_mul(&b, N_COINS_SQUARED)?)
+    };
+
+    let numerator = partial(x, y)
+        .and_then(|partial| ann.checked_add(partial))
+        .and_then(|numerator| u128::try_from(numerator).ok())
+        .ok_or(ContractError::MathOverflow {})?;
+    let denominator = partial(y, x)
+        .and_then(|partial| ann.checked_add(partial))
+        .and_then(|denominator| u128::try_from(denominator).ok())
+        .ok_or(ContractError::MathOverflow {})?;
+
+    Decimal::checked_from_ratio(numerator, denominator).map_err(|_| ContractError::MathOverflow {})
 }
 
 /// ## Description
@@ -0,0 +1,54 @@
+use cosmwasm_std::{Addr, DepsMut, StdResult, Uint128};
+use cw_storage_plus::Item;
+use novaswap::pair::PoolStatus;
+use novaswap::pairinfo::PairInfo;
+use novaswap::querier::query_token_precision;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Config, CONFIG};
+use crate::utils::DEFAULT_FEE_BPS;
+
+// config stored by the 1.0.0 pairs, which charged a global fee of 30 bps.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV100 {
+    pub pair_info: PairInfo,
+    pub factory_addr: Addr,
+    pub block_time_last: u64,
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+    pub init_amp: u64,
+    pub init_amp_time: u64,
+    pub next_amp: u64,
+    pub next_amp_time: u64,
+}
+
+pub const CONFIG_V100: Item<ConfigV100> = Item::new("config");
+
+// Upgrades the config stored by a 1.0.0 pair, the fee of the pair is kept at the former global fee
+// and the decimals of the assets are queried once to be cached.
+pub fn migrate_config_from_v100(deps: DepsMut) -> StdResult<()> {
+    let config = CONFIG_V100.load(deps.storage)?;
+    let precisions = [
+        query_token_precision(&deps.querier, config.pair_info.asset_infos[0].clone())?,
+        query_token_precision(&deps.querier, config.pair_info.asset_infos[1].clone())?,
+    ];
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            pair_info: config.pair_info,
+            precisions,
+            factory_addr: config.factory_addr,
+            block_time_last: config.block_time_last,
+            price0_cumulative_last: config.price0_cumulative_last,
+            price1_cumulative_last: config.price1_cumulative_last,
+            init_amp: config.init_amp,
+            init_amp_time: config.init_amp_time,
+            next_amp: config.next_amp,
+            next_amp_time: config.next_amp_time,
+            fee_bps: DEFAULT_FEE_BPS,
+            status: PoolStatus::Active,
+        },
+    )
+}
@@ -1,12 +1,16 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::{cell::Cell, collections::HashMap, marker::PhantomData};
 
 use cosmwasm_std::{
     from_binary, from_slice,
     testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
-    to_binary, Coin, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError,
-    SystemResult, Uint128, WasmQuery,
+    to_binary, Addr, Coin, Decimal, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest,
+    SystemError, SystemResult, Uint128, WasmQuery,
 };
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use novaswap::factory::{
+    ConfigResponse as FactoryConfigResponse, FeeInfoResponse, QueryMsg as FactoryQueryMsg,
+};
+use novaswap::pair::{RateProviderQueryMsg, RateProviderResponse};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
 /// This uses the Astroport CustomQuerier.
@@ -27,6 +31,10 @@ pub fn mock_dependencies(
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
     token_querier: TokenQuerier,
+    factory_querier: FactoryQuerier,
+    rate_querier: RateQuerier,
+    // the number of cw20 TokenInfo queries answered so far
+    token_info_queries: Cell<u64>,
 }
 
 impl Querier for WasmMockQuerier {
@@ -59,6 +67,18 @@ impl TokenQuerier {
     }
 }
 
+#[derive(Clone, Default)]
+pub struct FactoryQuerier {
+    fee_address: Option<String>,
+    maker_fee_bps: u16,
+}
+
+#[derive(Clone, Default)]
+pub struct RateQuerier {
+    // asset -> (rate, updated_at)
+    rates: HashMap<String, (Decimal, u64)>,
+}
+
 pub(crate) fn balances_to_map(
     balances: &[(&String, &[(&String, &Uint128)])],
 ) -> HashMap<String, HashMap<String, Uint128>> {
@@ -77,9 +97,64 @@ pub(crate) fn balances_to_map(
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == "factory" =>
+            {
+                match from_binary(msg).unwrap() {
+                    FactoryQueryMsg::Config {} => SystemResult::Ok(
+                        to_binary(&FactoryConfigResponse {
+                            owner: Addr::unchecked("owner0000"),
+                            pair_configs: vec![],
+                            token_code_id: 10u64,
+                            fee_address: self
+                                .factory_querier
+                                .fee_address
+                                .as_ref()
+                                .map(Addr::unchecked),
+                        })
+                        .into(),
+                    ),
+                    FactoryQueryMsg::FeeInfo { .. } => SystemResult::Ok(
+                        to_binary(&FeeInfoResponse {
+                            fee_address: self
+                                .factory_querier
+                                .fee_address
+                                .as_ref()
+                                .map(Addr::unchecked),
+                            maker_fee_rate: Decimal::from_ratio(
+                                self.factory_querier.maker_fee_bps,
+                                10000u128,
+                            ),
+                        })
+                        .into(),
+                    ),
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == "rate_provider" =>
+            {
+                match from_binary(msg).unwrap() {
+                    RateProviderQueryMsg::Rate { asset_info } => {
+                        match self.rate_querier.rates.get(&asset_info.to_string()) {
+                            Some((rate, updated_at)) => SystemResult::Ok(
+                                to_binary(&RateProviderResponse {
+                                    rate: *rate,
+                                    updated_at: *updated_at,
+                                })
+                                .into(),
+                            ),
+                            None => SystemResult::Err(SystemError::Unknown {}),
+                        }
+                    }
+                }
+            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match from_binary(msg).unwrap() {
                     Cw20QueryMsg::TokenInfo {} => {
+                        self.token_info_queries
+                            .set(self.token_info_queries.get() + 1);
+
                         let balances: &HashMap<String, Uint128> =
                             match self.token_querier.balances.get(contract_addr) {
                                 Some(balances) => balances,
@@ -138,14 +213,39 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
+            factory_querier: FactoryQuerier::default(),
+            rate_querier: RateQuerier::default(),
+            token_info_queries: Cell::new(0),
         }
     }
 
+    pub fn token_info_queries(&self) -> u64 {
+        self.token_info_queries.get()
+    }
+
     // Configure the mint whitelist mock querier
     pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
         self.token_querier = TokenQuerier::new(balances);
     }
 
+    // Configure the fee split returned by the factory
+    pub fn with_fee_info(&mut self, fee_address: Option<&str>, maker_fee_bps: u16) {
+        self.factory_querier = FactoryQuerier {
+            fee_address: fee_address.map(String::from),
+            maker_fee_bps,
+        };
+    }
+
+    // Configure the rates returned by the rate provider
+    pub fn with_rates(&mut self, rates: &[(&str, Decimal, u64)]) {
+        self.rate_querier = RateQuerier {
+            rates: rates
+                .iter()
+                .map(|(asset, rate, updated_at)| (asset.to_string(), (*rate, *updated_at)))
+                .collect(),
+        };
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, &[Coin])]) {
         for (addr, balance) in balances {
             self.base.update_balance(addr.to_string(), balance.to_vec());
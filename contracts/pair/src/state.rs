@@ -1,5 +1,7 @@
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Item, Map};
+use novaswap::asset::Asset;
+use novaswap::pair::{Observation, PoolStatus};
 use novaswap::pairinfo::PairInfo;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -7,6 +9,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub pair_info: PairInfo,
+    // decimals of the assets, in the order of `pair_info.asset_infos`.
+    pub precisions: [u8; 2],
     pub factory_addr: Addr,
 
     // used for calculatring TAWP.
@@ -19,6 +23,48 @@ pub struct Config {
     pub init_amp_time: u64,
     pub next_amp: u64,
     pub next_amp_time: u64,
+
+    // swap fee charged by this pair, in basis points.
+    pub fee_bps: u16,
+
+    // operations allowed by the pool, pools stored before the status default to active.
+    #[serde(default)]
+    pub status: PoolStatus,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+
+// redemption rates of the assets, not saved for pairs trading 1:1.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateConfig {
+    // the rates are queried from this contract when set, otherwise they are set by the owner.
+    pub rate_provider: Option<Addr>,
+    pub rates: [Decimal; 2],
+    pub rates_updated_at: u64,
+    pub max_rate_age: Option<u64>,
+}
+
+pub const RATES: Item<RateConfig> = Item::new("rates");
+
+// the reserves of the pair, in the order of `pair_info.asset_infos`. Tokens sent to the pair
+// outside of its messages are not part of the reserves, they can be skimmed or synced.
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
+
+// ring buffer of the cumulative prices, keyed by `count % MAX_OBSERVATIONS`.
+pub const OBSERVATIONS: Map<u64, Observation> = Map::new("observations");
+// the number of observations written so far.
+pub const OBSERVATIONS_COUNT: Item<u64> = Item::new("observations_count");
+
+// a flash loan in progress, saved while its assets are lent out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    // the balances of the pair before the loan, in the order of `pair_info.asset_infos`.
+    pub balances: [Uint128; 2],
+    // the fees owed on top of the balances, they join the reserves once repaid.
+    pub fees: [Uint128; 2],
+}
+
+pub const FLASH_LOAN: Item<FlashLoan> = Item::new("flash_loan");
+
+// the reserves a flash swap must be paid to, saved while its offer is unpaid.
+pub const FLASH_SWAP: Item<[Asset; 2]> = Item::new("flash_swap");
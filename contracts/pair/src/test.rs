@@ -1,35 +1,45 @@
 use std::ops::Mul;
+use std::str::FromStr;
 
 use crate::{
-    contract::{execute, instantiate, query_pair_info, query_pool, query_share, query_simulation},
+    contract::{
+        execute, instantiate, migrate, query_config, query_observe, query_pair_info, query_pool,
+        query_rates_in_use, query_reverse_simulation, query_share, query_simulate_withdraw,
+        query_simulate_withdraw_one_coin, query_simulation, query_spot_price, query_twap_price,
+        query_virtual_price, reply,
+    },
     error::ContractError,
-    math::AMP_PRECISION,
-    mock_querier::mock_dependencies,
-    state::Config,
+    math::{calc_ask_amount, calc_offer_amount, compute_d, AMP_PRECISION, MAX_AMP},
+    migration::{ConfigV100, CONFIG_V100},
+    mock_querier::{mock_dependencies, WasmMockQuerier},
+    state::{Config, CONFIG, FLASH_LOAN, FLASH_SWAP, RESERVES},
     test_helpers::{
-        mock_env_with_block_time, store_liquidity_token, swap_token, CASES, TEST_SWAP_DECIMALS,
-        TOKEN_DECIMALS,
+        mock_env_with_block_time, store_liquidity_token, store_reserves, swap_token, CASES,
+        TEST_SWAP_DECIMALS, TOKEN_DECIMALS,
     },
-    utils::{accumulate_prices, assert_max_spread},
+    utils::{accumulate_prices, assert_max_spread, observe, store_observation, MAX_OBSERVATIONS},
 };
 use cosmwasm_std::{
-    attr,
-    testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
-    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, ReplyOn, StdError, SubMsg, Uint128,
-    WasmMsg,
+    attr, from_binary,
+    testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, OwnedDeps, Reply, ReplyOn, Response,
+    StdError, SubMsg, SubMsgResponse, SubMsgResult, Timestamp, Uint128, WasmMsg,
 };
-use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
-use novaswap::token::InstantiateMsg as TokenInstantiateMsg;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Logo, MinterResponse};
+use cw_utils::Expiration;
+use novaswap::token::{InstantiateMarketingInfo, InstantiateMsg as TokenInstantiateMsg};
 use novaswap::{
     asset::{Asset, AssetInfo},
     pair::{
-        Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, SimulationResponse,
-        StablePoolParams, TWAP_PRECISION,
+        Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, Observation, PoolResponse, PoolStatus,
+        RateParams, RateSource, SimulationResponse, StablePoolConfig, StablePoolParams,
+        StablePoolUpdateParams, TWAP_PRECISION,
     },
     pairinfo::{PairInfo, PairType},
     U256,
 };
-use crate::math::MINIMUM_AMP;
+use proptest::prelude::*;
 
 #[test]
 fn proper_initialization() {
@@ -51,7 +61,20 @@ fn proper_initialization() {
         ],
         token_code_id: 1u64,
         factory_addr: String::from("factory0000"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 50u64 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 50u64,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: Some(InstantiateMarketingInfo {
+                    project: Some("Novaswap".to_string()),
+                    description: Some("SNT-UUSD stable pair LP token".to_string()),
+                    marketing: None,
+                    logo: Some(Logo::Url("https://novaswap.io/lp.png".to_string())),
+                }),
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
@@ -65,13 +88,19 @@ fn proper_initialization() {
                 code_id: 1u64,
                 msg: to_binary(&TokenInstantiateMsg {
                     name: "SNT-UUSD-LP".to_string(),
-                    symbol: "uLP".to_string(),
+                    symbol: "SNT-UUS-SLP".to_string(),
                     decimals: 6,
                     initial_balances: vec![],
                     mint: Some(MinterResponse {
                         minter: String::from(MOCK_CONTRACT_ADDR),
                         cap: None,
                     }),
+                    marketing: Some(InstantiateMarketingInfo {
+                        project: Some("Novaswap".to_string()),
+                        description: Some("SNT-UUSD stable pair LP token".to_string()),
+                        marketing: None,
+                        logo: Some(Logo::Url("https://novaswap.io/lp.png".to_string())),
+                    }),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -158,11 +187,370 @@ fn initialization_should_fail() {
         ],
         token_code_id: 1u64,
         factory_addr: String::from("factory0000"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 0u64 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 0u64,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
 
-    let err = instantiate(deps.as_mut(), env, info, msg).unwrap_err();
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
     assert_eq!(err, ContractError::IncorrectAmp {});
+
+    // Check Incorrect fee
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+        ],
+        token_code_id: 1u64,
+        factory_addr: String::from("factory0000"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100u64,
+                fee_bps: Some(1001),
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let err = instantiate(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(err, ContractError::IncorrectFee {});
+}
+
+/// 페어 수수료 설정 및 변경
+#[test]
+fn update_fee() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: Some(4),
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    let params: StablePoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(params.fee_bps, 4);
+
+    // only the factory owner can update the fee
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFee { fee_bps: 1 }).unwrap(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    let params: StablePoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(params.fee_bps, 1);
+
+    // fee cannot exceed the maximum
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFee { fee_bps: 1001 }).unwrap(),
+    };
+    let info = mock_info("owner0000", &[]);
+    let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(err, ContractError::IncorrectFee {});
+}
+
+/// 풀 상태에 따라 스왑과 유동성 공급을 막고, 유동성 회수는 항상 허용한다.
+#[test]
+fn update_pool_status() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.status, PoolStatus::Active);
+
+    // only the factory owner can pause the pool
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateStatus {
+            status: PoolStatus::SwapsPaused,
+        })
+        .unwrap(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.status, PoolStatus::SwapsPaused);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(10u128),
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        forward_msg: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10u128),
+        }],
+    );
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::SwapsPaused {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(10u128),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::SwapsPaused {});
+
+    // the factory updates the status of its pairs, but no other parameters
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFee { fee_bps: 1 }).unwrap(),
+    };
+    let info = mock_info("factory", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateStatus {
+            status: PoolStatus::WithdrawOnly,
+        })
+        .unwrap(),
+    };
+    let info = mock_info("factory", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(10u128),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(10u128),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10u128),
+        }],
+    );
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::WithdrawOnly {});
+
+    // withdrawals remain available
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(100u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(res.messages.len(), 3);
+}
+
+/// 1.0.0 페어의 설정을 마이그레이션하고, 다른 컨트랙트나 하위 버전으로의 마이그레이션은 막는다.
+#[test]
+fn migrate_from_v100() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(500_000000))],
+    )]);
+
+    let pair_info = PairInfo {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+        liquidity_token: Addr::unchecked("liquidity0000"),
+        pair_type: PairType::Stable {},
+    };
+    CONFIG_V100
+        .save(
+            deps.as_mut().storage,
+            &ConfigV100 {
+                pair_info: pair_info.clone(),
+                factory_addr: Addr::unchecked("factory"),
+                block_time_last: 1000,
+                price0_cumulative_last: Uint128::new(1_000000),
+                price1_cumulative_last: Uint128::new(2_000000),
+                init_amp: 100 * AMP_PRECISION,
+                init_amp_time: 500,
+                next_amp: 200 * AMP_PRECISION,
+                next_amp_time: 2000,
+            },
+        )
+        .unwrap();
+
+    // other contracts and newer versions cannot be migrated
+    set_contract_version(deps.as_mut().storage, "novaswap-factory", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-stable", "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-stable", "1.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", "1.0.0"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+
+    // the fee is kept at the former global fee and the decimals of the assets are cached
+    assert_eq!(
+        CONFIG.load(deps.as_ref().storage).unwrap(),
+        Config {
+            pair_info,
+            precisions: [6, 6],
+            factory_addr: Addr::unchecked("factory"),
+            block_time_last: 1000,
+            price0_cumulative_last: Uint128::new(1_000000),
+            price1_cumulative_last: Uint128::new(2_000000),
+            init_amp: 100 * AMP_PRECISION,
+            init_amp_time: 500,
+            next_amp: 200 * AMP_PRECISION,
+            next_amp_time: 2000,
+            fee_bps: 30,
+            status: PoolStatus::Active,
+        }
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+    // the reserves start from the balances of the pair
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::zero(), Uint128::new(500_000000)]
+    );
+
+    // migrating again keeps the state
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(CONFIG.load(deps.as_ref().storage).unwrap().fee_bps, 30);
 }
 
 /// 1. 정상적으로 유동성 풀에 공급했는지 확인한다.
@@ -197,7 +585,15 @@ fn test_provide_liquidity() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
@@ -222,7 +618,10 @@ fn test_provide_liquidity() {
                 amount: Uint128::from(100u128).mul(Uint128::from(TOKEN_DECIMALS)),
             },
         ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
         receiver: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -234,7 +633,7 @@ fn test_provide_liquidity() {
         }],
     );
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let transfer_from_msg = res.messages.get(0).expect("no message");
+    let transfer_from_msg = res.messages.first().expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
 
     // should execute `TransferFrom` on cw20 contract.
@@ -305,6 +704,8 @@ fn test_provide_liquidity() {
         ),
     ]);
 
+    store_reserves(deps.as_mut(), [200 * TOKEN_DECIMALS, 200 * TOKEN_DECIMALS]);
+
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: [
             Asset {
@@ -320,7 +721,10 @@ fn test_provide_liquidity() {
                 amount: Uint128::from(200u128).mul(Uint128::from(TOKEN_DECIMALS)),
             },
         ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
         receiver: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -333,7 +737,7 @@ fn test_provide_liquidity() {
     );
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let transfer_from_msg = res.messages.get(0).expect("no message");
+    let transfer_from_msg = res.messages.first().expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
     assert_eq!(
         transfer_from_msg,
@@ -361,7 +765,7 @@ fn test_provide_liquidity() {
                 contract_addr: String::from("liquidity0000"),
                 msg: to_binary(&Cw20ExecuteMsg::Mint {
                     recipient: String::from("addr0000"),
-                    amount: Uint128::from(74_981956874579206461u128),
+                    amount: Uint128::from(74_916356268651516833u128),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -389,7 +793,10 @@ fn test_provide_liquidity() {
                 amount: Uint128::from(50_000000000000000000u128),
             },
         ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
         receiver: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -439,7 +846,15 @@ fn test_provide_liquidity_with_minimum_liquidity() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 1 }).unwrap()), // add minimum amp
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 1,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ), // add minimum amp
     };
 
     let env = mock_env();
@@ -474,6 +889,8 @@ fn test_provide_liquidity_with_minimum_liquidity() {
         ),
     ]);
 
+    store_reserves(deps.as_mut(), [100 * TOKEN_DECIMALS, 150 * TOKEN_DECIMALS]);
+
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: [
             Asset {
@@ -489,7 +906,10 @@ fn test_provide_liquidity_with_minimum_liquidity() {
                 amount: Uint128::from(36u128).mul(Uint128::from(TOKEN_DECIMALS)),
             },
         ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
         receiver: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -530,6 +950,8 @@ fn test_provide_liquidity_with_minimum_liquidity() {
         ),
     ]);
 
+    store_reserves(deps.as_mut(), [100 * TOKEN_DECIMALS, 150 * TOKEN_DECIMALS]);
+
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: [
             Asset {
@@ -545,7 +967,10 @@ fn test_provide_liquidity_with_minimum_liquidity() {
                 amount: Uint128::from(20u128).mul(Uint128::from(TOKEN_DECIMALS)),
             },
         ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
         receiver: None,
+        deadline: None,
     };
 
     let env = mock_env();
@@ -559,32 +984,31 @@ fn test_provide_liquidity_with_minimum_liquidity() {
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(res.attributes[0], attr("action", "provide_liquidity"));
-    assert_eq!(res.attributes[3], attr("assets", "54000000000000000000asset0000, 20000000000000000000uusd"));
-    assert_eq!(res.attributes[4], attr("deposits_evaluated", "20000000000000000000uusd, 30000000000000000000asset0000"));
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "assets",
+            "54000000000000000000asset0000, 20000000000000000000uusd"
+        )
+    );
+    assert_eq!(
+        res.attributes[4],
+        attr(
+            "deposits_evaluated",
+            "20000000000000000000uusd, 30000000000000000000asset0000"
+        )
+    );
 }
 
-/// 유동성 회수 테스트
-/// - 두 코인 & 토큰에 대해서 transfer 메시지가 발생했는가
-/// - LP 토큰의 Burn 메시지가 발생했는가
-/// - 출금한 유동성의 공급량 계산
-/// - 환불한 금액에 대한 이벤트 로그
+/// MINIMUM_AMP 에서 비율에 맞춰 깎인 예치금은 보낸 사람에게 돌려준다.
+/// 네이티브 토큰은 bank send 로 환불하고, cw20 은 깎인 양만큼만 TransferFrom 한다.
 #[test]
-fn withdraw_liquidity() {
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::new(100u128),
-    }]);
-
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("liquidity0000"),
-            &[(&String::from("addr0000"), &Uint128::new(100u128))],
-        ),
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
-        ),
-    ]);
+fn provide_liquidity_refunds_clipped_deposit() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
 
     let msg = InstantiateMsg {
         asset_infos: [
@@ -597,22 +1021,199 @@ fn withdraw_liquidity() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 1,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
-
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store the liquidity token
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
 
-    // Withdraw liquidity
-    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(100_000000))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(150_000000))],
+        ),
+    ]);
+
+    let provide_msg = |native_amount: u128, token_amount: u128| ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(native_amount),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(token_amount),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+
+    store_reserves(deps.as_mut(), [100_000000, 150_000000]);
+    // 100 uusd : 150 asset0000 풀에 30 uusd 와 60 asset0000 을 넣으면 asset0000 은 45 만 가져간다.
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(130_000000),
+        }],
+    )]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(30_000000),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(30_000000, 60_000000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: String::from("addr0000"),
+                recipient: String::from(MOCK_CONTRACT_ADDR),
+                amount: Uint128::new(45_000000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(res.messages.len(), 2);
+    assert!(res.attributes.contains(&attr(
+        "deposits_evaluated",
+        "30000000uusd, 45000000asset0000"
+    )));
+    assert!(res
+        .attributes
+        .contains(&attr("refund_assets", "0uusd, 15000000asset0000")));
+
+    store_reserves(deps.as_mut(), [100_000000, 150_000000]);
+    // 40 uusd 와 30 asset0000 을 넣으면 uusd 는 20 만 가져가고 나머지 20 은 돌려준다.
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(140_000000),
+        }],
+    )]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(40_000000),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(40_000000, 30_000000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(20_000000),
+            }],
+        })
+    );
+    assert_eq!(res.messages.len(), 3);
+    assert!(res
+        .attributes
+        .contains(&attr("refund_assets", "20000000uusd, 0asset0000")));
+}
+
+/// 유동성 회수 테스트
+/// - 두 코인 & 토큰에 대해서 transfer 메시지가 발생했는가
+/// - LP 토큰의 Burn 메시지가 발생했는가
+/// - 출금한 유동성의 공급량 계산
+/// - 환불한 금액에 대한 이벤트 로그
+#[test]
+fn withdraw_liquidity() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100, 100]);
+
+    // Withdraw liquidity
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -621,7 +1222,7 @@ fn withdraw_liquidity() {
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     let log_withdrawn_share = res.attributes.get(2).expect("no log");
     let log_refund_assets = res.attributes.get(3).expect("no log");
-    let msg_refund_0 = res.messages.get(0).expect("no message");
+    let msg_refund_0 = res.messages.first().expect("no message");
     let msg_refund_1 = res.messages.get(1).expect("no message");
     let msg_burn_liquidity = res.messages.get(2).expect("no message");
     assert_eq!(
@@ -685,27 +1286,22 @@ fn withdraw_liquidity() {
     );
 }
 
-/// native를 token으로 스왑 해보기
+/// 최소 회수량 및 수령인을 지정한 유동성 회수
 #[test]
-fn try_native_to_token() {
-    let total_share = Uint128::new(30000000000u128);
-    let asset_pool_amount = Uint128::new(20000000000u128);
-    let collateral_pool_amount = Uint128::new(30000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
-
+fn withdraw_liquidity_with_min_assets_out() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+        amount: Uint128::new(100u128),
     }]);
 
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
         ),
         (
             &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(50u128))],
         ),
     ]);
 
@@ -720,125 +1316,115 @@ fn try_native_to_token() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
 
-    let env = mock_env_with_block_time(100);
+    let env = mock_env();
     let info = mock_info("addr0000", &[]);
-
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store the liquidity token
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100, 50]);
 
-    // Normal swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
+    let res = query_simulate_withdraw(deps.as_ref(), Uint128::new(40u128)).unwrap();
+    let refund_assets = res.refund_assets;
+    assert_eq!(refund_assets[0].amount, Uint128::new(40u128));
+    assert_eq!(refund_assets[1].amount, Uint128::new(20u128));
+
+    let min_assets_out = |uusd_amount: u128, token_amount: u128| {
+        [
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(token_amount),
             },
-            amount: offer_amount,
-        },
-        belief_price: None,
-        max_spread: Some(Decimal::percent(50)),
-        to: None,
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(uusd_amount),
+            },
+        ]
     };
 
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: offer_amount,
-        }],
-    );
-
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
-
-    // Check simulation result
-    deps.querier.with_balance(&[(
-        &String::from(MOCK_CONTRACT_ADDR),
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: collateral_pool_amount, /* user deposit must be pre-applied */
-        }],
-    )]);
-
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        env,
-        Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: Some(min_assets_out(40, 21)),
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(40u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::WithdrawAmountTooSmall {
+            minimum: 21,
+            received: 20,
+            asset: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
             },
-            amount: offer_amount,
-        },
-    )
-    .unwrap();
+        }
+    );
 
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: Some(min_assets_out(40, 20)),
+            receiver: Some(String::from("addr0001")),
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(40u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "swap"),
-            attr("sender", "addr0000"),
-            attr("receiver", "addr0000"),
-            attr("offer_asset", "uusd"),
-            attr("ask_asset", "asset0000"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", simulation_res.return_amount.to_string()),
-            attr("spread_amount", simulation_res.spread_amount.to_string()),
-            attr(
-                "commission_amount",
-                simulation_res.commission_amount.to_string()
-            ),
-        ]
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(40u128),
+            }],
+        })
     );
-
     assert_eq!(
-        &SubMsg {
-            msg: WasmMsg::Execute {
-                contract_addr: String::from("asset0000"),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: String::from("addr0000"),
-                    amount: simulation_res.return_amount,
-                })
-                .unwrap(),
-                funds: vec![],
-            }
-            .into(),
-            id: 0,
-            gas_limit: None,
-            reply_on: ReplyOn::Never,
-        },
-        msg_transfer,
+        res.attributes[3],
+        attr("refund_assets", "40uusd, 20asset0000")
     );
+    assert_eq!(res.attributes[4], attr("receiver", "addr0001"));
 }
 
-/// token을 native로 스왑 해보기
+/// 한 종류의 코인으로 유동성 회수
 #[test]
-fn try_token_to_native() {
-    let total_share = Uint128::new(30000000000u128);
-    let asset_pool_amount = Uint128::new(20000000000u128);
-    let collateral_pool_amount = Uint128::new(30000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
-
+fn withdraw_liquidity_one_coin() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount,
+        amount: Uint128::new(100_000000u128),
     }]);
 
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            &[(&String::from("addr0000"), &Uint128::new(200_000000u128))],
         ),
         (
             &String::from("asset0000"),
             &[(
                 &String::from(MOCK_CONTRACT_ADDR),
-                &(asset_pool_amount + offer_amount),
+                &Uint128::new(100_000000u128),
             )],
         ),
     ]);
@@ -854,190 +1440,147 @@ fn try_token_to_native() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
 
-    let env = mock_env_with_block_time(100);
+    let env = mock_env();
     let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
 
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let withdraw_amount = Uint128::new(20_000000u128);
 
-    // Store the liquidity token
-    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    let simulation =
+        query_simulate_withdraw_one_coin(deps.as_ref(), env.clone(), withdraw_amount, uusd.clone())
+            .unwrap();
 
-    // Unauthorized access; can not execute swap directy for token swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
-            },
-            amount: offer_amount,
-        },
-        belief_price: None,
-        max_spread: None,
-        to: None,
-    };
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    assert_eq!(res, ContractError::Unauthorized {});
+    // 10% of the pool withdrawn in one coin is close to 20% of the coin balance,
+    // minus the slippage of the invariant and the imbalance fee.
+    assert!(simulation.return_amount < Uint128::new(20_000000u128));
+    assert!(simulation.return_amount > Uint128::new(19_900000u128));
+    assert!(!simulation.fee_amount.is_zero());
 
-    // Normal sell
+    // the imbalance fee is at most half of the swap fee on the withdrawn amount
+    assert!(
+        simulation.fee_amount
+            <= Uint128::new(20_000000u128) * Decimal::from_ratio(15u128, 10000u128)
+    );
+
+    // min_amount guard
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            belief_price: None,
-            max_spread: None,
-            to: None,
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneCoin {
+            asset_info: uusd.clone(),
+            min_amount: Some(Uint128::new(20_000000u128)),
         })
         .unwrap(),
+        amount: withdraw_amount,
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawAmountTooSmall {
+            minimum: 20_000000u128,
+            received: simulation.return_amount.u128(),
+            asset: uusd.clone(),
+        }
+    );
+
+    // only the liquidity token can call the hook
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneCoin {
+            asset_info: uusd.clone(),
+            min_amount: None,
+        })
+        .unwrap(),
+        amount: withdraw_amount,
     });
-    let env = mock_env_with_block_time(1000);
     let info = mock_info("asset0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
 
+    let info = mock_info("liquidity0000", &[]);
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
-
-    // Check simulation result
-    // Return asset token balance as normal
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
-        ),
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
-        ),
-    ]);
-
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        env,
-        Asset {
-            amount: offer_amount,
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
-            },
-        },
-    )
-    .unwrap();
-
     assert_eq!(
-        res.attributes,
+        res.messages,
         vec![
-            attr("action", "swap"),
-            attr("sender", "addr0000"),
-            attr("receiver", "addr0000"),
-            attr("offer_asset", "asset0000"),
-            attr("ask_asset", "uusd"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", simulation_res.return_amount.to_string()),
-            attr("spread_amount", simulation_res.spread_amount.to_string()),
-            attr(
-                "commission_amount",
-                simulation_res.commission_amount.to_string()
-            ),
-        ]
-    );
-
-    assert_eq!(
-        &SubMsg {
-            msg: CosmosMsg::Bank(BankMsg::Send {
+            SubMsg::new(BankMsg::Send {
                 to_address: String::from("addr0000"),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: simulation_res.return_amount,
+                    amount: simulation.return_amount,
                 }],
             }),
-            id: 0,
-            gas_limit: None,
-            reply_on: ReplyOn::Never,
-        },
-        msg_transfer,
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: withdraw_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_liquidity_one_coin"),
+            attr("sender", "addr0000"),
+            attr("withdrawn_share", withdraw_amount.to_string()),
+            attr("refund_asset", format!("{}uusd", simulation.return_amount)),
+            attr("fee_amount", simulation.fee_amount.to_string()),
+        ]
     );
 
-    // Failed due to non asset token contract being used in a swap
+    // the asset must belong to the pair
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            belief_price: None,
-            max_spread: None,
-            to: None,
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneCoin {
+            asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            min_amount: None,
         })
         .unwrap(),
+        amount: withdraw_amount,
     });
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("liquidtity0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    assert_eq!(res, ContractError::Unauthorized {});
-}
-
-/// max spread가 넘으면 스왑되지 않는 로직 부분 테스트
-#[test]
-fn test_max_spread() {
-    // belief_price - 코인 A / B 사이의 내가 생각한 가격
-    // 토큰 B는, 1200개 토큰 A 정도로 생각한다는 의미
-
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(989999u128),
-        Uint128::zero(),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Uint128::from(1200000000u128),
-        Uint128::from(990000u128),
-        Uint128::zero(),
-    )
-    .unwrap();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(989999u128),
-        Uint128::from(10001u128),
-    )
-    .unwrap_err();
-
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
-        Uint128::zero(),
-        Uint128::from(990000u128),
-        Uint128::from(10000u128),
-    )
-    .unwrap();
+    let info = mock_info("liquidity0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap_err();
 }
 
-/// 페어 풀 정보 조회
+/// 정확한 수량을 지정하여 유동성 회수
 #[test]
-fn test_query_pool() {
-    let total_share_amount = Uint128::from(111u128);
-    let asset_0_amount = Uint128::from(222u128);
-    let asset_1_amount = Uint128::from(333u128);
+fn withdraw_liquidity_imbalance() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: asset_0_amount,
+        amount: Uint128::new(100_000000u128),
     }]);
 
     deps.querier.with_token_balances(&[
         (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(200_000000u128))],
         ),
         (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(100_000000u128),
+            )],
         ),
     ]);
 
@@ -1052,308 +1595,3624 @@ fn test_query_pool() {
         ],
         token_code_id: 10u64,
         factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
     };
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store the liquidity token
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
 
-    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
-
-    assert_eq!(
-        res.assets,
+    let assets = |uusd_amount: u128, token_amount: u128| {
         [
             Asset {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
-                amount: asset_0_amount
+                amount: Uint128::new(uusd_amount),
             },
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
-                amount: asset_1_amount
-            }
-        ]
-    );
-    assert_eq!(res.total_supply, total_share_amount);
-}
-
-/// share 정보 조회
-#[test]
-fn test_query_share() {
-    let total_share_amount = Uint128::from(500u128);
-    let asset_0_amount = Uint128::from(250u128);
-    let asset_1_amount = Uint128::from(1000u128);
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: asset_0_amount,
-    }]);
-
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
-        ),
-        (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
-        ),
-    ]);
-
-    let msg = InstantiateMsg {
-        asset_infos: [
-            AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
+                amount: Uint128::new(token_amount),
             },
-        ],
-        token_code_id: 10u64,
-        factory_addr: String::from("factory"),
-        init_params: Some(to_binary(&StablePoolParams { amp: 100 }).unwrap()),
+        ]
     };
 
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    // a proportional withdrawal burns the pro-rata share without fees
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: assets(10_000000, 10_000000),
+            max_burn_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(30_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(res.attributes[2], attr("withdrawn_share", "20000001"));
+    assert_eq!(res.attributes[3], attr("refund_share", "9999999"));
+    assert_eq!(res.attributes[5], attr("fee_amounts", "0uusd, 0asset0000"));
 
-    // Store the liquidity token
-    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    // a one-sided withdrawal pays the imbalance fee
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: assets(10_000000, 0),
+            max_burn_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(30_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
+    let burn_amount = Uint128::try_from(res.attributes[2].value.as_str()).unwrap();
+    assert!(burn_amount > Uint128::new(10_000000u128));
+    assert!(burn_amount < Uint128::new(10_100000u128));
 
-    assert_eq!(res[0].amount, Uint128::new(125));
-    assert_eq!(res[1].amount, Uint128::new(500));
-}
+    // burning the same share into one coin does not return more
+    let one_coin = query_simulate_withdraw_one_coin(
+        deps.as_ref(),
+        env.clone(),
+        burn_amount,
+        assets(0, 0)[0].info.clone(),
+    )
+    .unwrap();
+    assert!(one_coin.return_amount <= Uint128::new(10_000000u128));
 
-/// TWAP price 계산
-#[test]
-fn test_accumulate_prices() {
-    struct Case {
-        block_time: u64,
-        block_time_last: u64,
-        last0: u128,
-        last1: u128,
-        x_amount: u128,
-        y_amount: u128,
-    }
-
-    struct Result {
-        block_time_last: u64,
-        cumulative_price_x: u128,
-        cumulative_price_y: u128,
-        is_some: bool,
-    }
-
-    let price_precision = 10u128.pow(TWAP_PRECISION.into());
-
-    let test_cases: Vec<(Case, Result)> = vec![
-        (
-            Case {
-                block_time: 1000,
-                block_time_last: 0,
-                last0: 0,
-                last1: 0,
-                x_amount: 250_000000,
-                y_amount: 500_000000,
-            },
-            Result {
-                block_time_last: 1000,
-                cumulative_price_x: 1008,
-                cumulative_price_y: 991,
-                is_some: true,
-            },
-        ),
-        // Same block height, no changes
-        (
-            Case {
-                block_time: 1000,
-                block_time_last: 1000,
-                last0: price_precision,
-                last1: 2 * price_precision,
-                x_amount: 250_000000,
-                y_amount: 500_000000,
-            },
-            Result {
-                block_time_last: 1000,
-                cumulative_price_x: 1,
-                cumulative_price_y: 2,
-                is_some: false,
-            },
-        ),
-        (
-            Case {
-                block_time: 1500,
-                block_time_last: 1000,
-                last0: 500 * price_precision,
-                last1: 2000 * price_precision,
-                x_amount: 250_000000,
-                y_amount: 500_000000,
-            },
-            Result {
-                block_time_last: 1500,
-                cumulative_price_x: 1004,
-                cumulative_price_y: 2495,
-                is_some: true,
-            },
-        ),
-    ];
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(10_000000u128),
+                }],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: burn_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(30_000000u128) - burn_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
 
-    for test_case in test_cases {
-        let (case, result) = test_case;
+    // max_burn_amount guard
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: assets(10_000000, 0),
+            max_burn_amount: Some(Uint128::new(10_000000u128)),
+        })
+        .unwrap(),
+        amount: Uint128::new(30_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxBurnAmountAssertion {
+            maximum: 10_000000u128,
+            required: burn_amount.u128(),
+        }
+    );
 
-        let env = mock_env_with_block_time(case.block_time);
-        let config = accumulate_prices(
-            env.clone(),
-            &Config {
-                pair_info: PairInfo {
-                    asset_infos: [
-                        AssetInfo::NativeToken {
-                            denom: "uusd".to_string(),
-                        },
-                        AssetInfo::Token {
-                            contract_addr: Addr::unchecked("asset0000"),
-                        },
-                    ],
-                    contract_addr: Addr::unchecked("pair"),
-                    liquidity_token: Addr::unchecked("lp_token"),
-                    pair_type: PairType::Stable {},
-                },
-                factory_addr: Addr::unchecked("factory"),
-                block_time_last: case.block_time_last,
-                price0_cumulative_last: Uint128::new(case.last0),
-                price1_cumulative_last: Uint128::new(case.last1),
-                init_amp: 100 * AMP_PRECISION,
-                init_amp_time: env.block.time.seconds(),
-                next_amp: 100 * AMP_PRECISION,
-                next_amp_time: env.block.time.seconds(),
-            },
-            Uint128::new(case.x_amount),
-            6,
-            Uint128::new(case.y_amount),
-            6,
-        )
-        .unwrap();
+    // cannot burn more than sent
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: assets(10_000000, 0),
+            max_burn_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(5_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxBurnAmountAssertion {
+            maximum: 5_000000u128,
+            required: burn_amount.u128(),
+        }
+    );
 
-        assert_eq!(result.is_some, config.is_some());
+    // zero amounts
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: assets(0, 0),
+            max_burn_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(5_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
 
-        if let Some(config) = config {
-            assert_eq!(config.2, result.block_time_last);
-            assert_eq!(
-                config.0 / Uint128::from(price_precision),
-                Uint128::new(result.cumulative_price_x)
-            );
-            assert_eq!(
-                config.1 / Uint128::from(price_precision),
-                Uint128::new(result.cumulative_price_y)
-            );
-        }
-    }
+    // the same asset cannot be given twice
+    let uusd = assets(10_000000, 0)[0].clone();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityImbalance {
+            assets: [uusd.clone(), uusd],
+            max_burn_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(30_000000u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
 }
 
+/// 한쪽으로 치우친 유동성 공급 후 비례 인출하는 것이 스왑보다 저렴하지 않은지 확인
 #[test]
-fn swap_simulation() {
-    for (index, tt) in CASES.iter().enumerate() {
-        let mut deps = mock_dependencies(&[]);
-        let total_share = Uint128::new(
-            (U256::from(
-                Uint128::new(tt.token_a)
-                    .mul(Uint128::new(TEST_SWAP_DECIMALS))
-                    .u128(),
-            ) * U256::from(
-                Uint128::new(tt.token_b)
-                    .mul(Uint128::new(TEST_SWAP_DECIMALS))
-                    .u128(),
-            ))
-            .integer_sqrt()
-            .as_u128(),
-        );
+fn provide_liquidity_imbalance_fee() {
+    let provide_and_withdraw = |uusd_amount: u128, token_amount: u128| {
+        let mut deps = mock_dependencies(&[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000u128 + uusd_amount),
+        }]);
 
         deps.querier.with_token_balances(&[
             (
                 &String::from("liquidity0000"),
-                &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+                &[(&String::from("addr0000"), &Uint128::new(200_000000u128))],
             ),
             (
                 &String::from("asset0000"),
                 &[(
                     &String::from(MOCK_CONTRACT_ADDR),
-                    &Uint128::new(tt.token_a).mul(Uint128::new(TEST_SWAP_DECIMALS)),
-                )],
-            ),
-            (
-                &String::from("asset0001"),
-                &[(
-                    &String::from(MOCK_CONTRACT_ADDR),
-                    &Uint128::new(tt.token_b).mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                    &Uint128::new(100_000000u128),
                 )],
             ),
         ]);
 
         let msg = InstantiateMsg {
             asset_infos: [
-                AssetInfo::Token {
-                    contract_addr: Addr::unchecked("asset0000"),
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
                 },
                 AssetInfo::Token {
-                    contract_addr: Addr::unchecked("asset0001"),
+                    contract_addr: Addr::unchecked("asset0000"),
                 },
             ],
             token_code_id: 10u64,
             factory_addr: String::from("factory"),
-            init_params: Some(to_binary(&StablePoolParams { amp: 50 }).unwrap()),
+            init_params: Some(
+                to_binary(&StablePoolParams {
+                    amp: 100,
+                    fee_bps: None,
+                    rates: None,
+                    lp_token_marketing: None,
+                })
+                .unwrap(),
+            ),
         };
 
-        let env = mock_env_with_block_time(100);
+        let env = mock_env();
         let info = mock_info("addr0000", &[]);
-
-        // We can just call .unwrap() to assert this was a success
-        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-        // Store the liquidity token
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
         store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+        store_reserves(deps.as_mut(), [100_000000, 100_000000]);
 
-        println!();
-        println!("-- swap test case #{} --", index);
-        let pool_res = query_pool(deps.as_ref()).unwrap();
-        println!(
-            "pool liquidity, asset0: {}, asset1: {}",
-            pool_res.assets[0].amount, pool_res.assets[1].amount
+        let msg = ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(uusd_amount),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(token_amount),
+                },
+            ],
+            slippage_tolerance: None,
+            min_lp_to_receive: None,
+            receiver: None,
+            deadline: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(uusd_amount),
+            }],
         );
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let share: u128 = res
+            .attributes
+            .iter()
+            .find(|a| a.key == "share")
+            .unwrap()
+            .value
+            .parse()
+            .unwrap();
+        let fee_amounts = res
+            .attributes
+            .iter()
+            .find(|a| a.key == "fee_amounts")
+            .unwrap()
+            .value
+            .clone();
 
+        // apply the deposit and withdraw the minted share proportionally
         deps.querier.with_token_balances(&[
             (
                 &String::from("liquidity0000"),
-                &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+                &[(
+                    &String::from("addr0000"),
+                    &Uint128::new(200_000000u128 + share),
+                )],
             ),
             (
                 &String::from("asset0000"),
                 &[(
                     &String::from(MOCK_CONTRACT_ADDR),
-                    &Uint128::new(tt.token_a + tt.swap_amount)
-                        .mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                    &Uint128::new(100_000000u128 + token_amount),
                 )],
             ),
+        ]);
+        let withdrawn = query_share(deps.as_ref(), Uint128::new(share)).unwrap();
+        let uusd_out = withdrawn[0].amount.u128();
+        let token_out = withdrawn[1].amount.u128();
+
+        // the round trip is a swap of the net input, compare it against `swap`
+        // on the untouched pool.
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000000u128),
+            }],
+        )]);
+        deps.querier.with_token_balances(&[
+            (
+                &String::from("liquidity0000"),
+                &[(&String::from("addr0000"), &Uint128::new(200_000000u128))],
+            ),
             (
-                &String::from("asset0001"),
+                &String::from("asset0000"),
                 &[(
                     &String::from(MOCK_CONTRACT_ADDR),
-                    &Uint128::new(tt.token_b).mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                    &Uint128::new(100_000000u128),
                 )],
             ),
         ]);
+        store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+        let (offer_asset, offer_amount, received) = if uusd_amount > uusd_out {
+            (
+                withdrawn[0].info.clone(),
+                uusd_amount - uusd_out,
+                token_out.saturating_sub(token_amount),
+            )
+        } else {
+            (
+                withdrawn[1].info.clone(),
+                token_amount - token_out,
+                uusd_out.saturating_sub(uusd_amount),
+            )
+        };
+        let swap_return = query_simulation(
+            deps.as_ref(),
+            mock_env(),
+            Asset {
+                info: offer_asset,
+                amount: Uint128::new(offer_amount),
+            },
+        )
+        .unwrap()
+        .return_amount
+        .u128();
 
-        let result = swap_token(deps.as_mut(), Uint128::from(tt.swap_amount));
-        println!("expected amount: {}", tt.token_b - tt.after_token_b);
-        println!("{}", result);
+        (received, swap_return, fee_amounts)
+    };
+
+    // a balanced deposit pays no imbalance fee
+    let (_, _, fee_amounts) = provide_and_withdraw(10_000000, 10_000000);
+    assert_eq!(fee_amounts, "0uusd, 0asset0000");
+
+    let cases: [(u128, u128); 6] = [
+        (10_000000, 0),
+        (0, 10_000000),
+        (50_000000, 0),
+        (30_000000, 10_000000),
+        (10_000000, 30_000000),
+        (100_000000, 1_000000),
+    ];
+    for (uusd_amount, token_amount) in cases {
+        let (received, swap_return, fee_amounts) = provide_and_withdraw(uusd_amount, token_amount);
+        assert_ne!(fee_amounts, "0uusd, 0asset0000");
+        assert!(received <= swap_return);
+    }
+}
+
+/// 유동성 공급 시 slippage_tolerance, min_lp_to_receive 확인
+#[test]
+fn provide_liquidity_slippage() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(150_000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(200_000000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(100_000000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+
+    // one-sided deposit of 50 uusd
+    let msg = |slippage_tolerance: Option<Decimal>, min_lp_to_receive: Option<Uint128>| {
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(50_000000u128),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::zero(),
+                },
+            ],
+            slippage_tolerance,
+            min_lp_to_receive,
+            receiver: None,
+            deadline: None,
+        }
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(50_000000u128),
+        }],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        msg(Some(Decimal::percent(60)), None),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AllowedSlippageAssertion {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        msg(Some(Decimal::permille(1)), None),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::MaxSlippageAssertion {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        msg(None, Some(Uint128::new(50_000000u128))),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::MinLpAmountAssertion {
+            minimum: 50_000000,
+            received: 49_854748,
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        msg(Some(Decimal::percent(1)), Some(Uint128::new(49_000000u128))),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[5], attr("share", "49854748"));
+}
+
+/// native를 token으로 스왑 해보기
+#[test]
+fn try_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    // Normal swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        forward_msg: None,
+        deadline: None,
+    };
+
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg_transfer = res.messages.first().expect("no message");
+
+    // Check simulation result
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0000"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", simulation_res.return_amount.to_string()),
+            attr("spread_amount", simulation_res.spread_amount.to_string()),
+            attr(
+                "commission_amount",
+                simulation_res.commission_amount.to_string()
+            ),
+            attr(
+                "maker_fee_amount",
+                simulation_res.maker_fee_amount.to_string()
+            ),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            msg: WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: simulation_res.return_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+        msg_transfer,
+    );
+}
+
+/// token을 native로 스왑 해보기
+#[test]
+fn try_token_to_native() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + offer_amount),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    // Unauthorized access; can not execute swap directy for token swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        forward_msg: None,
+        deadline: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // Normal sell
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg_transfer = res.messages.first().expect("no message");
+
+    // Check simulation result
+    // Return asset token balance as normal
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
+        ),
+    ]);
+
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            amount: offer_amount,
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "asset0000"),
+            attr("ask_asset", "uusd"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", simulation_res.return_amount.to_string()),
+            attr("spread_amount", simulation_res.spread_amount.to_string()),
+            attr(
+                "commission_amount",
+                simulation_res.commission_amount.to_string()
+            ),
+            attr(
+                "maker_fee_amount",
+                simulation_res.maker_fee_amount.to_string()
+            ),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: simulation_res.return_amount,
+                }],
+            }),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+        msg_transfer,
+    );
+
+    // Failed due to non asset token contract being used in a swap
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("liquidtity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+}
+
+/// 토큰 소수점 자릿수는 생성할 때 캐시되어, 스왑과 시뮬레이션은 TokenInfo 를 쿼리하지 않는다.
+#[test]
+fn swap_with_cached_precisions() {
+    let offer_amount = Uint128::new(1500_000000);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30000_000000) + offer_amount,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(30000_000000),
+            )],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20000_000000),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(100),
+        mock_info("addr0000", &[]),
+        msg,
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [30000_000000, 20000_000000]);
+
+    // the decimals are queried once, at instantiation
+    assert_eq!(
+        CONFIG.load(deps.as_ref().storage).unwrap().precisions,
+        [6, 6]
+    );
+    let token_info_queries = deps.querier.token_info_queries();
+
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: offer_amount,
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+    query_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+    assert_eq!(deps.querier.token_info_queries(), token_info_queries);
+}
+
+/// 정확한 수량을 받는 스왑은 필요한 만큼만 받고 나머지는 돌려준다.
+#[test]
+fn swap_exact_out() {
+    let total_share = Uint128::new(30000_000000);
+    let collateral_pool_amount = Uint128::new(30000_000000);
+    let asset_pool_amount = Uint128::new(20000_000000);
+    let max_offer_amount = Uint128::new(1500_000000);
+    let ask_amount = Uint128::new(1000_000000);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + max_offer_amount,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(100),
+        mock_info("addr0000", &[]),
+        msg,
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    let swap_exact_out_msg = |max_offer_amount: Uint128| ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: ask_amount,
+        },
+        max_offer_amount,
+        to: None,
+        deadline: None,
+    };
+    let uusd = |amount: Uint128| {
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount,
+        }]
+    };
+
+    // the sent funds must match the maximum offer amount
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("addr0000", &uusd(Uint128::new(1000_000000))),
+        swap_exact_out_msg(max_offer_amount),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        ))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("addr0000", &uusd(max_offer_amount)),
+        swap_exact_out_msg(max_offer_amount),
+    )
+    .unwrap();
+    let offer_amount = Uint128::from_str(
+        &res.attributes
+            .iter()
+            .find(|attr| attr.key == "offer_amount")
+            .unwrap()
+            .value,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: uusd(max_offer_amount - offer_amount),
+            }),
+        ]
+    );
+
+    // the offer amount is the least amount the simulation returns the ask amount for
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &uusd(collateral_pool_amount),
+    )]);
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+    let simulate = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, amount| {
+        query_simulation(
+            deps.as_ref(),
+            mock_env_with_block_time(1000),
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount,
+            },
+        )
+        .unwrap()
+        .return_amount
+    };
+    assert!(simulate(&deps, offer_amount) >= ask_amount);
+    assert!(simulate(&deps, offer_amount - Uint128::new(1)) < ask_amount);
+
+    // swaps needing more than the maximum offer amount fail
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &uusd(collateral_pool_amount + Uint128::new(900_000000)),
+    )]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("addr0000", &uusd(Uint128::new(900_000000))),
+        swap_exact_out_msg(Uint128::new(900_000000)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxOfferAmountAssertion {
+            maximum: 900_000000,
+            required: offer_amount.u128(),
+        }
+    );
+
+    // cw20 offers are refunded with a transfer
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &uusd(collateral_pool_amount),
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + max_offer_amount),
+            )],
+        ),
+    ]);
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: max_offer_amount,
+            msg: to_binary(&Cw20HookMsg::SwapExactOut {
+                ask_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: ask_amount,
+                },
+                to: Some(String::from("merchant0000")),
+                deadline: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    let offer_amount = Uint128::from_str(
+        &res.attributes
+            .iter()
+            .find(|attr| attr.key == "offer_amount")
+            .unwrap()
+            .value,
+    )
+    .unwrap();
+    assert!(offer_amount < max_offer_amount);
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("merchant0000"),
+                amount: uusd(ask_amount),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: max_offer_amount - offer_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // only the pair tokens can be offered
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("asset0001", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: max_offer_amount,
+            msg: to_binary(&Cw20HookMsg::SwapExactOut {
+                ask_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: ask_amount,
+                },
+                to: None,
+                deadline: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+/// 기한이 지난 스왑, 유동성 공급, 출금 메시지는 실패한다.
+#[test]
+fn expired_deadline() {
+    let mut deps = rate_scaled_pair(RateSource::Owner {
+        rates: [Decimal::one(), Decimal::percent(110)],
+    });
+    let env = mock_env_with_block_time(1000);
+
+    let swap_msg = |deadline| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(10_000000),
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        forward_msg: None,
+        deadline,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10_000000),
+        }],
+    );
+
+    // the deadline passes at the given time or height
+    for deadline in [
+        Expiration::AtTime(Timestamp::from_seconds(1000)),
+        Expiration::AtHeight(env.block.height),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            swap_msg(Some(deadline)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::zero(),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    amount: Uint128::new(10_000000),
+                },
+            ],
+            slippage_tolerance: None,
+            min_lp_to_receive: None,
+            receiver: None,
+            deadline: Some(Expiration::AtTime(Timestamp::from_seconds(999))),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                forward_msg: None,
+                deadline: Some(Expiration::AtTime(Timestamp::from_seconds(999))),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("liquidity0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_out: None,
+                receiver: None,
+                deadline: Some(Expiration::AtHeight(env.block.height)),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::SwapExactOut {
+            ask_asset: Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(5_000000),
+            },
+            max_offer_amount: Uint128::new(10_000000),
+            to: None,
+            deadline: Some(Expiration::AtTime(Timestamp::from_seconds(999))),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::SwapExactOut {
+                ask_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::new(5_000000),
+                },
+                to: None,
+                deadline: Some(Expiration::AtHeight(env.block.height)),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    // messages within the deadline execute
+    execute(
+        deps.as_mut(),
+        env,
+        info,
+        swap_msg(Some(Expiration::AtTime(Timestamp::from_seconds(1001)))),
+    )
+    .unwrap();
+}
+
+/// 플래시 론은 수수료와 함께 상환되어야 하고, 대출 중에는 페어를 사용할 수 없다.
+#[test]
+fn flash_loan() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30000_000000),
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(30000_000000),
+            )],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20000_000000),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [30000_000000, 20000_000000]);
+
+    let uusd = |amount: u128| Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::new(amount),
+    };
+    let asset0000 = |amount: u128| Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: Uint128::new(amount),
+    };
+    let flash_loan_msg = |assets: Vec<Asset>| ExecuteMsg::FlashLoan {
+        assets,
+        callback_contract: String::from("borrower0000"),
+        msg: to_binary(&"arbitrage").unwrap(),
+    };
+
+    // assets must belong to the pair and be lent once
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        flash_loan_msg(vec![uusd(1000_000000), uusd(1000_000000)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        flash_loan_msg(vec![Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            amount: Uint128::new(1000_000000),
+        }]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        flash_loan_msg(vec![uusd(1000_000000), asset0000(500_000000)]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("borrower0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(1000_000000),
+                }],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("borrower0000"),
+                    amount: Uint128::new(500_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from("borrower0000"),
+                    msg: to_binary(&"arbitrage").unwrap(),
+                    funds: vec![],
+                },
+                2,
+            ),
+        ]
+    );
+    // the fees are charged at the swap fee of 30 bps
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "fees",
+            format!("{}, {}", uusd(3_000000), asset0000(1_500000))
+        )
+    );
+
+    // the pair is locked until the loan is repaid
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("borrower0000"),
+            amount: Uint128::new(100_000000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                forward_msg: None,
+                deadline: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FlashLoanInProgress {});
+
+    // returning the loan without the fee fails
+    let callback_reply = Reply {
+        id: 2,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let err = reply(deps.as_mut(), mock_env(), callback_reply.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FlashLoanNotRepaid {
+            expected: 30003_000000,
+            balance: 30000_000000,
+            asset: uusd(0).info,
+        }
+    );
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(30003_000000),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(30000_000000),
+            )],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20001_500000),
+            )],
+        ),
+    ]);
+    reply(deps.as_mut(), mock_env(), callback_reply).unwrap();
+    assert_eq!(FLASH_LOAN.may_load(deps.as_ref().storage).unwrap(), None);
+    // the fees are added to the reserves
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(30003_000000), Uint128::new(20001_500000)]
+    );
+}
+
+/// 플래시 론은 준비금이 아니라 대출 전 잔고에 수수료를 더한 만큼 상환되어야 한다.
+#[test]
+fn flash_loan_repaid_to_balances_before_the_loan() {
+    // 100 uusd were sent to the pair outside of its messages
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30100_000000),
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(30000_000000),
+            )],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20000_000000),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [30000_000000, 20000_000000]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::FlashLoan {
+            assets: vec![Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(1000_000000),
+            }],
+            callback_contract: String::from("borrower0000"),
+            msg: to_binary(&"arbitrage").unwrap(),
+        },
+    )
+    .unwrap();
+
+    // the borrower kept the surplus and repaid the reserves with the fee
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(30003_000000),
+        }],
+    )]);
+    let callback_reply = Reply {
+        id: 2,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let err = reply(deps.as_mut(), mock_env(), callback_reply.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FlashLoanNotRepaid {
+            expected: 30103_000000,
+            balance: 30003_000000,
+            asset: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        }
+    );
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(30103_000000),
+        }],
+    )]);
+    reply(deps.as_mut(), mock_env(), callback_reply).unwrap();
+    // only the fee joins the reserves, the surplus can still be skimmed
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(30003_000000), Uint128::new(20000_000000)]
+    );
+}
+
+/// 스왑 결과를 메시지와 함께 컨트랙트로 보낸다.
+#[test]
+fn swap_and_call() {
+    let mut deps = rate_scaled_pair(RateSource::Owner {
+        rates: [Decimal::one(), Decimal::percent(110)],
+    });
+    let env = mock_env_with_block_time(1000);
+    let vault_msg = to_binary(&"deposit").unwrap();
+
+    let return_amount = |res: &Response| {
+        Uint128::from_str(
+            &res.attributes
+                .iter()
+                .find(|attr| attr.key == "return_amount")
+                .unwrap()
+                .value,
+        )
+        .unwrap()
+    };
+
+    // cw20 outputs are delivered with a cw20 send
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(10_000000),
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::new(10_000000),
+            },
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: Some(String::from("vault0000")),
+            forward_msg: Some(vault_msg.clone()),
+            deadline: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: String::from("vault0000"),
+                amount: return_amount(&res),
+                msg: vault_msg.clone(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // native outputs are attached as funds to the message
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: Some(Decimal::percent(50)),
+                to: Some(String::from("vault0000")),
+                forward_msg: Some(vault_msg.clone()),
+                deadline: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("vault0000"),
+            msg: vault_msg,
+            funds: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: return_amount(&res),
+            }],
+        })
+    );
+}
+
+/// 플래시 스왑은 콜백이 끝나기 전에 수수료를 포함한 대가를 지불해야 한다.
+#[test]
+fn flash_swap() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30000_000000),
+    }]);
+    let with_pools = |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+                      uusd_amount: u128,
+                      asset_amount: u128| {
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(uusd_amount),
+            }],
+        )]);
+        deps.querier.with_token_balances(&[
+            (
+                &String::from("liquidity0000"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(30000_000000),
+                )],
+            ),
+            (
+                &String::from("asset0000"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(asset_amount),
+                )],
+            ),
+        ]);
+    };
+    with_pools(&mut deps, 30000_000000, 20000_000000);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [30000_000000, 20000_000000]);
+    deps.querier.with_fee_info(Some("collector0000"), 5000);
+
+    let ask_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: Uint128::new(100_000000),
+    };
+    let offer_amount = query_reverse_simulation(deps.as_ref(), mock_env(), ask_asset.clone())
+        .unwrap()
+        .offer_amount;
+    let simulation_res = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+    let maker_fee_amount = simulation_res.maker_fee_amount;
+    assert!(!maker_fee_amount.is_zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("arbitrageur0000", &[]),
+        ExecuteMsg::FlashSwap {
+            ask_asset: ask_asset.clone(),
+            callback: to_binary(&"arbitrage").unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("arbitrageur0000"),
+                    amount: Uint128::new(100_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("collector0000"),
+                    amount: maker_fee_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from("arbitrageur0000"),
+                    msg: to_binary(&"arbitrage").unwrap(),
+                    funds: vec![],
+                },
+                3,
+            ),
+        ]
+    );
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "offer_amount")
+            .unwrap()
+            .value,
+        offer_amount.to_string()
+    );
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "commission_amount")
+            .unwrap()
+            .value,
+        simulation_res.commission_amount.to_string()
+    );
+
+    // the pair is locked until the offer is paid
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("arbitrageur0000", &[]),
+        ExecuteMsg::FlashSwap {
+            ask_asset,
+            callback: to_binary(&"arbitrage").unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FlashLoanInProgress {});
+
+    let callback_reply = Reply {
+        id: 3,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let asset_balance = 19900_000000 - maker_fee_amount.u128();
+
+    // nothing paid
+    with_pools(&mut deps, 30000_000000, asset_balance);
+    let err = reply(deps.as_mut(), mock_env(), callback_reply.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FlashSwapNotPaid {
+            expected: 30000_000000 + offer_amount.u128(),
+            balance: 30000_000000,
+            asset: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        }
+    );
+
+    // paying one less than the offer
+    with_pools(
+        &mut deps,
+        30000_000000 + offer_amount.u128() - 1,
+        asset_balance,
+    );
+    let err = reply(deps.as_mut(), mock_env(), callback_reply.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::FlashSwapNotPaid { .. }));
+
+    // paying the offer of a plain swap, the reserves follow the amounts owed
+    with_pools(&mut deps, 30000_000000 + offer_amount.u128(), asset_balance);
+    reply(deps.as_mut(), mock_env(), callback_reply).unwrap();
+    assert_eq!(FLASH_SWAP.may_load(deps.as_ref().storage).unwrap(), None);
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            Uint128::new(30000_000000) + offer_amount,
+            Uint128::new(asset_balance)
+        ]
+    );
+}
+
+/// 스왑 수수료 중 프로토콜 몫을 fee address로 보내기
+#[test]
+fn swap_with_maker_fee() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+    deps.querier.with_fee_info(Some("collector0000"), 5000);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        forward_msg: None,
+        deadline: None,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+    store_reserves(
+        deps.as_mut(),
+        [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+    );
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+
+    // half of the commission goes to the collector
+    assert_eq!(
+        simulation_res.maker_fee_amount,
+        simulation_res.commission_amount * Decimal::percent(50)
+    );
+    assert!(!simulation_res.maker_fee_amount.is_zero());
+    assert_eq!(
+        res.attributes.last().unwrap(),
+        &attr(
+            "maker_fee_amount",
+            simulation_res.maker_fee_amount.to_string()
+        )
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("collector0000"),
+                amount: simulation_res.maker_fee_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+/// 페어에 설정된 수수료로 스왑 시뮬레이션
+#[test]
+fn simulation_with_pair_fee() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    for fee_bps in [1u16, 4, 30] {
+        let mut deps = mock_dependencies(&[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }]);
+
+        deps.querier.with_token_balances(&[
+            (
+                &String::from("liquidity0000"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            ),
+            (
+                &String::from("asset0000"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+            ),
+        ]);
+
+        let msg = InstantiateMsg {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+            ],
+            token_code_id: 10u64,
+            factory_addr: String::from("factory"),
+            init_params: Some(
+                to_binary(&StablePoolParams {
+                    amp: 100,
+                    fee_bps: Some(fee_bps),
+                    rates: None,
+                    lp_token_marketing: None,
+                })
+                .unwrap(),
+            ),
+        };
+
+        let env = mock_env_with_block_time(100);
+        let info = mock_info("addr0000", &[]);
+        instantiate(deps.as_mut(), env, info, msg).unwrap();
+        store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+        store_reserves(
+            deps.as_mut(),
+            [collateral_pool_amount.u128(), asset_pool_amount.u128()],
+        );
+
+        let env = mock_env_with_block_time(1000);
+        let res = query_simulation(
+            deps.as_ref(),
+            env.clone(),
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+        )
+        .unwrap();
+
+        let fee_rate = Decimal::from_ratio(fee_bps, 10000u128);
+        assert_eq!(
+            res.commission_amount,
+            (res.return_amount + res.commission_amount) * fee_rate
+        );
+
+        let res = query_reverse_simulation(
+            deps.as_ref(),
+            env,
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: offer_amount,
+            },
+        )
+        .unwrap();
+
+        let before_commission = offer_amount * (Decimal::one() / (Decimal::one() - fee_rate));
+        assert_eq!(res.commission_amount, before_commission * fee_rate);
+    }
+}
+
+/// max spread가 넘으면 스왑되지 않는 로직 부분 테스트
+#[test]
+fn test_max_spread() {
+    // belief_price - 코인 A / B 사이의 내가 생각한 가격
+    // 토큰 B는, 1200개 토큰 A 정도로 생각한다는 의미
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(989999u128),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Uint128::from(1200000000u128),
+        Uint128::from(990000u128),
+        Uint128::zero(),
+    )
+    .unwrap();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(989999u128),
+        Uint128::from(10001u128),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
+        Uint128::zero(),
+        Uint128::from(990000u128),
+        Uint128::from(10000u128),
+    )
+    .unwrap();
+}
+
+/// 페어 풀 정보 조회
+#[test]
+fn test_query_pool() {
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222u128);
+    let asset_1_amount = Uint128::from(333u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [222, 333]);
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+
+    assert_eq!(
+        res.assets,
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: asset_0_amount
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: asset_1_amount
+            }
+        ]
+    );
+    assert_eq!(res.total_supply, total_share_amount);
+}
+
+/// share 정보 조회
+#[test]
+fn test_query_share() {
+    let total_share_amount = Uint128::from(500u128);
+    let asset_0_amount = Uint128::from(250u128);
+    let asset_1_amount = Uint128::from(1000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [250, 1000]);
+
+    let res = query_share(deps.as_ref(), Uint128::new(250)).unwrap();
+
+    assert_eq!(res[0].amount, Uint128::new(125));
+    assert_eq!(res[1].amount, Uint128::new(500));
+}
+
+/// LP 토큰 가치와 현재 가격 조회
+#[test]
+fn test_query_virtual_price_and_spot_price() {
+    let total_share_amount = Uint128::from(1000_000000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1000_000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(1000_000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [1000_000000, 1000_000000]);
+
+    // balanced pool, D is the sum of the balances
+    let res = query_virtual_price(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.virtual_price, Decimal::from_ratio(2u128, 1u128));
+
+    let res = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.ask_asset_info,
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        }
+    );
+    assert_eq!(res.price, Decimal::one());
+
+    // imbalanced pool, the abundant asset is cheaper
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1500_000000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(500_000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    store_reserves(deps.as_mut(), [1500_000000, 500_000000]);
+    let uusd_price = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+    )
+    .unwrap()
+    .price;
+    let asset_price = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    )
+    .unwrap()
+    .price;
+    assert!(uusd_price < Decimal::one());
+    assert!(asset_price > Decimal::one());
+    assert!(uusd_price * asset_price > Decimal::from_str("0.999999999").unwrap());
+    assert!(uusd_price * asset_price < Decimal::from_str("1.000000001").unwrap());
+
+    // a small swap is filled close to the spot price
+    let res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(1_000000u128),
+        },
+    )
+    .unwrap();
+    let filled = res.return_amount + res.commission_amount;
+    assert!(filled <= Uint128::from(1_000000u128) * uusd_price);
+    assert!(filled + Uint128::from(100u128) > Uint128::from(1_000000u128) * uusd_price);
+
+    let res = query_spot_price(
+        deps.as_ref(),
+        env,
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0001"),
+        },
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs"
+        ))
+    );
+}
+
+/// TWAP price 계산
+#[test]
+fn test_accumulate_prices() {
+    struct Case {
+        block_time: u64,
+        block_time_last: u64,
+        last0: u128,
+        last1: u128,
+        x_amount: u128,
+        y_amount: u128,
+    }
+
+    struct Result {
+        block_time_last: u64,
+        cumulative_price_x: u128,
+        cumulative_price_y: u128,
+        is_some: bool,
+    }
+
+    let price_precision = 10u128.pow(TWAP_PRECISION.into());
+
+    let test_cases: Vec<(Case, Result)> = vec![
+        (
+            Case {
+                block_time: 1000,
+                block_time_last: 0,
+                last0: 0,
+                last1: 0,
+                x_amount: 250_000000,
+                y_amount: 500_000000,
+            },
+            Result {
+                block_time_last: 1000,
+                cumulative_price_x: 1008,
+                cumulative_price_y: 991,
+                is_some: true,
+            },
+        ),
+        // Same block height, no changes
+        (
+            Case {
+                block_time: 1000,
+                block_time_last: 1000,
+                last0: price_precision,
+                last1: 2 * price_precision,
+                x_amount: 250_000000,
+                y_amount: 500_000000,
+            },
+            Result {
+                block_time_last: 1000,
+                cumulative_price_x: 1,
+                cumulative_price_y: 2,
+                is_some: false,
+            },
+        ),
+        (
+            Case {
+                block_time: 1500,
+                block_time_last: 1000,
+                last0: 500 * price_precision,
+                last1: 2000 * price_precision,
+                x_amount: 250_000000,
+                y_amount: 500_000000,
+            },
+            Result {
+                block_time_last: 1500,
+                cumulative_price_x: 1004,
+                cumulative_price_y: 2495,
+                is_some: true,
+            },
+        ),
+    ];
+
+    for test_case in test_cases {
+        let (case, result) = test_case;
+
+        let env = mock_env_with_block_time(case.block_time);
+        let config = accumulate_prices(
+            env.clone(),
+            &Config {
+                pair_info: PairInfo {
+                    asset_infos: [
+                        AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                    ],
+                    contract_addr: Addr::unchecked("pair"),
+                    liquidity_token: Addr::unchecked("lp_token"),
+                    pair_type: PairType::Stable {},
+                },
+                precisions: [6, 6],
+                factory_addr: Addr::unchecked("factory"),
+                block_time_last: case.block_time_last,
+                price0_cumulative_last: Uint128::new(case.last0),
+                price1_cumulative_last: Uint128::new(case.last1),
+                init_amp: 100 * AMP_PRECISION,
+                init_amp_time: env.block.time.seconds(),
+                next_amp: 100 * AMP_PRECISION,
+                next_amp_time: env.block.time.seconds(),
+                fee_bps: 30,
+                status: PoolStatus::Active,
+            },
+            Uint128::new(case.x_amount),
+            6,
+            Uint128::new(case.y_amount),
+            6,
+            [Decimal::one(); 2],
+        )
+        .unwrap();
+
+        assert_eq!(result.is_some, config.is_some());
+
+        if let Some(config) = config {
+            assert_eq!(config.2, result.block_time_last);
+            assert_eq!(
+                config.0 / Uint128::from(price_precision),
+                Uint128::new(result.cumulative_price_x)
+            );
+            assert_eq!(
+                config.1 / Uint128::from(price_precision),
+                Uint128::new(result.cumulative_price_y)
+            );
+        }
+    }
+}
+
+/// 가격 관측값 저장 및 보간
+#[test]
+fn test_observe() {
+    let mut deps = mock_dependencies(&[]);
+
+    for (timestamp, price0_cumulative, price1_cumulative) in [
+        (100u64, 0u128, 0u128),
+        (200, 200_000000, 50_000000),
+        (300, 300_000000, 100_000000),
+    ] {
+        store_observation(
+            deps.as_mut().storage,
+            timestamp,
+            Uint128::new(price0_cumulative),
+            Uint128::new(price1_cumulative),
+        )
+        .unwrap();
+    }
+
+    let latest = Observation {
+        timestamp: 400,
+        price0_cumulative: Uint128::new(400_000000),
+        price1_cumulative: Uint128::new(150_000000),
+    };
+
+    let cases = [
+        (150u64, 100_000000u128, 25_000000u128),
+        (200, 200_000000, 50_000000),
+        (350, 350_000000, 125_000000),
+        (400, 400_000000, 150_000000),
+    ];
+    for (timestamp, price0_cumulative, price1_cumulative) in cases {
+        assert_eq!(
+            observe(deps.as_ref().storage, &latest, timestamp).unwrap(),
+            Observation {
+                timestamp,
+                price0_cumulative: Uint128::new(price0_cumulative),
+                price1_cumulative: Uint128::new(price1_cumulative),
+            }
+        );
+    }
+
+    assert_eq!(
+        observe(deps.as_ref().storage, &latest, 99),
+        Err(StdError::generic_err(
+            "The requested time is older than the oldest observation"
+        ))
+    );
+
+    // the oldest observations are overwritten once the buffer is full
+    for i in 0..MAX_OBSERVATIONS {
+        store_observation(
+            deps.as_mut().storage,
+            1000 + i,
+            Uint128::new(u128::from(1000 + i)),
+            Uint128::zero(),
+        )
+        .unwrap();
+    }
+
+    let latest = Observation {
+        timestamp: 1000 + MAX_OBSERVATIONS,
+        price0_cumulative: Uint128::new(u128::from(1000 + MAX_OBSERVATIONS)),
+        price1_cumulative: Uint128::zero(),
+    };
+    assert!(observe(deps.as_ref().storage, &latest, 999).is_err());
+    assert_eq!(
+        observe(deps.as_ref().storage, &latest, 1500)
+            .unwrap()
+            .price0_cumulative,
+        Uint128::new(1500)
+    );
+}
+
+/// 기간 평균 가격 조회
+#[test]
+fn test_query_twap_price() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1000_000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(1000_000000u128),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::from(1000_000000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(100);
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [1000_000000, 1000_000000]);
+
+    // the prices accumulated by the previous swaps
+    for (timestamp, price0_cumulative, price1_cumulative) in [
+        (100u64, 0u128, 0u128),
+        (200, 200_000000, 50_000000),
+        (300, 300_000000, 100_000000),
+    ] {
+        store_observation(
+            deps.as_mut().storage,
+            timestamp,
+            Uint128::new(price0_cumulative),
+            Uint128::new(price1_cumulative),
+        )
+        .unwrap();
+    }
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.block_time_last = 300;
+    config.price0_cumulative_last = Uint128::new(300_000000);
+    config.price1_cumulative_last = Uint128::new(100_000000);
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+    let env = mock_env_with_block_time(300);
+    let res = query_observe(deps.as_ref(), env.clone(), vec![0, 150]).unwrap();
+    assert_eq!(
+        res.observations,
+        vec![
+            Observation {
+                timestamp: 300,
+                price0_cumulative: Uint128::new(300_000000),
+                price1_cumulative: Uint128::new(100_000000),
+            },
+            Observation {
+                timestamp: 150,
+                price0_cumulative: Uint128::new(100_000000),
+                price1_cumulative: Uint128::new(25_000000),
+            },
+        ]
+    );
+
+    let res = query_twap_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        200,
+    )
+    .unwrap();
+    assert_eq!(
+        res.ask_asset_info,
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        }
+    );
+    assert_eq!(res.price, Decimal::from_str("1.5").unwrap());
+
+    let res = query_twap_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        100,
+    )
+    .unwrap();
+    assert_eq!(res.price, Decimal::from_str("0.5").unwrap());
+
+    // the balanced pool has traded at 1:1 since the last observation
+    let res = query_twap_price(
+        deps.as_ref(),
+        mock_env_with_block_time(400),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        100,
+    )
+    .unwrap();
+    assert!(res.price > Decimal::from_str("0.999").unwrap());
+    assert!(res.price <= Decimal::one());
+
+    let res = query_twap_price(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        0,
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("The window must not be empty"))
+    );
+
+    let res = query_twap_price(
+        deps.as_ref(),
+        env,
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        300,
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "The requested time is older than the oldest observation"
+        ))
+    );
+}
+
+#[test]
+fn swap_simulation() {
+    for (index, tt) in CASES.iter().enumerate() {
+        let mut deps = mock_dependencies(&[]);
+        let total_share = Uint128::new(
+            (U256::from(
+                Uint128::new(tt.token_a)
+                    .mul(Uint128::new(TEST_SWAP_DECIMALS))
+                    .u128(),
+            ) * U256::from(
+                Uint128::new(tt.token_b)
+                    .mul(Uint128::new(TEST_SWAP_DECIMALS))
+                    .u128(),
+            ))
+            .integer_sqrt()
+            .as_u128(),
+        );
+
+        deps.querier.with_token_balances(&[
+            (
+                &String::from("liquidity0000"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            ),
+            (
+                &String::from("asset0000"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(tt.token_a).mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                )],
+            ),
+            (
+                &String::from("asset0001"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(tt.token_b).mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                )],
+            ),
+        ]);
+
+        let msg = InstantiateMsg {
+            asset_infos: [
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0001"),
+                },
+            ],
+            token_code_id: 10u64,
+            factory_addr: String::from("factory"),
+            init_params: Some(
+                to_binary(&StablePoolParams {
+                    amp: 50,
+                    fee_bps: None,
+                    rates: None,
+                    lp_token_marketing: None,
+                })
+                .unwrap(),
+            ),
+        };
+
+        let env = mock_env_with_block_time(100);
+        let info = mock_info("addr0000", &[]);
+
+        // We can just call .unwrap() to assert this was a success
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        // Store the liquidity token
+        store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+        store_reserves(
+            deps.as_mut(),
+            [
+                tt.token_a * TEST_SWAP_DECIMALS,
+                tt.token_b * TEST_SWAP_DECIMALS,
+            ],
+        );
+
+        println!();
+        println!("-- swap test case #{} --", index);
+        let pool_res = query_pool(deps.as_ref()).unwrap();
+        println!(
+            "pool liquidity, asset0: {}, asset1: {}",
+            pool_res.assets[0].amount, pool_res.assets[1].amount
+        );
+
+        deps.querier.with_token_balances(&[
+            (
+                &String::from("liquidity0000"),
+                &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+            ),
+            (
+                &String::from("asset0000"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(tt.token_a + tt.swap_amount)
+                        .mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                )],
+            ),
+            (
+                &String::from("asset0001"),
+                &[(
+                    &String::from(MOCK_CONTRACT_ADDR),
+                    &Uint128::new(tt.token_b).mul(Uint128::new(TEST_SWAP_DECIMALS)),
+                )],
+            ),
+        ]);
+
+        let result = swap_token(deps.as_mut(), Uint128::from(tt.swap_amount));
+        println!("expected amount: {}", tt.token_b - tt.after_token_b);
+        println!("{}", result);
+    }
+}
+
+// 1100 uusd 와 1000 asset0000 을 가지고, asset0000 의 상환 비율이 1.1 인 페어를 만든다.
+fn rate_scaled_pair(source: RateSource) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1100_000000),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1010_000000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(2100_000000),
+            )],
+        ),
+    ]);
+    deps.querier.with_rates(&[
+        ("uusd", Decimal::one(), 900),
+        ("asset0000", Decimal::percent(110), 950),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: Some(RateParams {
+                    source,
+                    max_rate_age: Some(3600),
+                }),
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [1100_000000, 1000_000000]);
+
+    deps
+}
+
+// asset0000 10개를 uusd 로 스왑하는 메시지
+fn swap_rate_scaled_token() -> ExecuteMsg {
+    ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(10_000000),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    })
+}
+
+/// 관리자가 설정한 상환 비율로 스왑하고, 비율이 오래되면 스왑을 막는다.
+#[test]
+fn swap_with_owner_rates() {
+    let mut deps = rate_scaled_pair(RateSource::Owner {
+        rates: [Decimal::one(), Decimal::percent(110)],
+    });
+
+    let rates = query_rates_in_use(deps.as_ref(), mock_env_with_block_time(1000)).unwrap();
+    assert_eq!(rates.rates, [Decimal::one(), Decimal::percent(110)]);
+    assert_eq!(rates.updated_at, 1000);
+    assert_eq!(rates.rate_provider, None);
+    assert_eq!(rates.max_rate_age, Some(3600));
+
+    // 10 asset0000 are worth 11 uusd, the pool is balanced at the rates
+    let offer_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: Uint128::new(10_000000),
+    };
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(2100_000000),
+            )],
+        ),
+    ]);
+    let simulation =
+        query_simulation(deps.as_ref(), mock_env_with_block_time(1000), offer_asset).unwrap();
+    assert_eq!(
+        simulation.return_amount + simulation.commission_amount + simulation.spread_amount,
+        Uint128::new(11_000000)
+    );
+    assert!(simulation.spread_amount < Uint128::new(2000));
+    assert_eq!(
+        simulation.commission_amount,
+        Uint128::new(11_000000 - simulation.spread_amount.u128()) * Decimal::permille(3)
+    );
+
+    let reverse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: simulation.return_amount,
+        },
+    )
+    .unwrap();
+    assert!(reverse.offer_amount.u128().abs_diff(10_000000) <= 1);
+
+    let mut deps = rate_scaled_pair(RateSource::Owner {
+        rates: [Decimal::one(), Decimal::percent(110)],
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("asset0000", &[]),
+        swap_rate_scaled_token(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: simulation.return_amount,
+            }],
+        })
+    );
+
+    // the rates go stale after an hour
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(4601),
+        mock_info("asset0000", &[]),
+        swap_rate_scaled_token(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StaleRates { updated_at: 1000 });
+
+    let err = query_simulation(
+        deps.as_ref(),
+        mock_env_with_block_time(4601),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(10_000000),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Redemption rates are stale, last updated at 1000")
+    );
+
+    // withdrawing proportionally does not depend on the rates
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(100_000000),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(4601),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // only the factory owner can update the rates
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateRates {
+            rates: [Decimal::one(), Decimal::percent(111)],
+        })
+        .unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(5000),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(5000),
+        mock_info("owner0000", &[]),
+        msg,
+    )
+    .unwrap();
+    let rates = query_rates_in_use(deps.as_ref(), mock_env_with_block_time(5000)).unwrap();
+    assert_eq!(rates.rates, [Decimal::one(), Decimal::percent(111)]);
+    assert_eq!(rates.updated_at, 5000);
+
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(5000),
+        mock_info("asset0000", &[]),
+        swap_rate_scaled_token(),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateRates {
+            rates: [Decimal::one(), Decimal::zero()],
+        })
+        .unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(5000),
+        mock_info("owner0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectRate {});
+}
+
+/// 상환 비율을 곱한 현재 가격이 넘치면 패닉 대신 에러를 반환한다.
+#[test]
+fn spot_price_with_overflowing_rates() {
+    let rate = Decimal::from_ratio(200_000000_000000_000000u128, 1u128);
+    let mut deps = rate_scaled_pair(RateSource::Owner {
+        rates: [rate, rate],
+    });
+    store_reserves(deps.as_mut(), [1000_000000, 1]);
+
+    let res = query_spot_price(
+        deps.as_ref(),
+        mock_env_with_block_time(1000),
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    );
+    assert_eq!(res, Err(ContractError::MathOverflow {}.into()));
+}
+
+/// 상환 비율을 제공하는 컨트랙트에서 비율을 조회하여 스왑한다.
+#[test]
+fn swap_with_rate_provider() {
+    let mut deps = rate_scaled_pair(RateSource::Provider {
+        contract_addr: String::from("rate_provider"),
+    });
+
+    // the oldest rate decides the staleness
+    let rates = query_rates_in_use(deps.as_ref(), mock_env_with_block_time(1000)).unwrap();
+    assert_eq!(rates.rates, [Decimal::one(), Decimal::percent(110)]);
+    assert_eq!(rates.updated_at, 900);
+    assert_eq!(rates.rate_provider, Some(Addr::unchecked("rate_provider")));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("asset0000", &[]),
+        swap_rate_scaled_token(),
+    )
+    .unwrap();
+    // 10 asset0000 are worth 11 uusd, minus the 0.3% fee
+    assert_eq!(res.attributes[6], attr("return_amount", "10965915"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(4501),
+        mock_info("asset0000", &[]),
+        swap_rate_scaled_token(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StaleRates { updated_at: 900 });
+
+    // the rates of a provider cannot be overridden
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateRates {
+            rates: [Decimal::one(), Decimal::one()],
+        })
+        .unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        mock_info("owner0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RatesNotUpdatable {});
+
+    // zero rates are rejected
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: Some(RateParams {
+                    source: RateSource::Owner {
+                        rates: [Decimal::zero(), Decimal::one()],
+                    },
+                    max_rate_age: None,
+                }),
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::IncorrectRate {});
+}
+
+/// 풀에 직접 보낸 토큰은 skim 으로 꺼내거나, 팩토리 owner 의 sync 로 리저브에 반영한다.
+#[test]
+fn skim_and_sync() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1000_000000),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store the liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    // 100 uusd 가 리저브에 반영되지 않은 채로 풀에 들어와 있다
+    store_reserves(deps.as_mut(), [900_000000, 1000_000000]);
+
+    let res = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::new(900_000000));
+
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000000),
+            }],
+        }))]
+    );
+    // skim 은 리저브를 바꾸지 않는다
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(900_000000), Uint128::new(1000_000000)]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr("reserves", "1000000000uusd, 1000000000asset0000"),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1000_000000), Uint128::new(1000_000000)]
+    );
+
+    // 리저브와 잔고가 같으면 skim 할 것이 없다
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert!(res.messages.is_empty());
+}
+
+/// 풀보다 많은 양을 사려고 하면 underflow 로 패닉하지 않고 InsufficientReserves 에러를 돌려준다.
+#[test]
+fn math_insufficient_reserves() {
+    let amp = 100 * AMP_PRECISION;
+    assert_eq!(
+        calc_offer_amount(1000_000000, 1000_000000, 1000_000001, amp),
+        Err(ContractError::InsufficientReserves {
+            requested: 1000_000001,
+            available: 1000_000000,
+        })
+    );
+    // 풀 전체는 살 수 없다
+    assert_eq!(
+        calc_offer_amount(1000_000000, 1000_000000, 1000_000000, amp),
+        Err(ContractError::InsufficientReserves {
+            requested: 1000_000000,
+            available: 1000_000000,
+        })
+    );
+    assert_eq!(
+        calc_ask_amount(u128::MAX, 1000_000000, 1, amp),
+        Err(ContractError::MathOverflow {})
+    );
+    assert_eq!(compute_d(2 * amp, 0, 0), Ok(0));
+
+    // 리버스 시뮬레이션 쿼리도 같은 에러를 돌려준다
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(1000_000000),
+        )],
+    )]);
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [1000_000000, 1000_000000]);
+
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(2000_000000),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            ContractError::InsufficientReserves {
+                requested: 2006_018054,
+                available: 1000_000000,
+            }
+            .to_string()
+        )
+    );
+}
+
+// 수학 함수가 돌려줄 수 있는 에러
+fn is_math_error(err: &ContractError) -> bool {
+    matches!(
+        err,
+        ContractError::NonConvergence {} | ContractError::MathOverflow {}
+    )
+}
+
+proptest! {
+    /// 극단적인 잔고와 amp 에서도 스왑 계산은 패닉하지 않고, 풀보다 많이 내주지 않는다.
+    #[test]
+    fn math_calc_ask_amount_never_panics(
+        offer_pool in 1..=u128::MAX >> 8,
+        ask_pool in 1..=u128::MAX >> 8,
+        offer_amount in 0..=u128::MAX >> 8,
+        amp in AMP_PRECISION..=MAX_AMP * AMP_PRECISION,
+    ) {
+        match calc_ask_amount(offer_pool, ask_pool, offer_amount, amp) {
+            Ok(ask_amount) => prop_assert!(ask_amount < ask_pool),
+            Err(err) => prop_assert!(is_math_error(&err), "{:?}", err),
+        }
+    }
+
+    /// 리버스 스왑 계산은 풀보다 많이 요구하면 InsufficientReserves 를, 그 외에는 계산 결과나 수학 에러를 돌려준다.
+    #[test]
+    fn math_calc_offer_amount_never_panics(
+        offer_pool in 1..=u128::MAX >> 8,
+        ask_pool in 1..=u128::MAX >> 8,
+        ask_amount in 0..=u128::MAX >> 8,
+        amp in AMP_PRECISION..=MAX_AMP * AMP_PRECISION,
+    ) {
+        match calc_offer_amount(offer_pool, ask_pool, ask_amount, amp) {
+            Ok(_) => prop_assert!(ask_amount < ask_pool),
+            Err(ContractError::InsufficientReserves { requested, available }) => {
+                prop_assert!(ask_amount >= ask_pool);
+                prop_assert_eq!((requested, available), (ask_amount, ask_pool));
+            }
+            Err(err) => prop_assert!(is_math_error(&err), "{:?}", err),
+        }
+    }
+
+    /// 인베리언트 D 는 잔고의 합을 넘지 않는다.
+    #[test]
+    fn math_compute_d_bounded_by_sum(
+        amount_a in 0..=u128::MAX >> 2,
+        amount_b in 0..=u128::MAX >> 2,
+        amp in AMP_PRECISION..=MAX_AMP * AMP_PRECISION,
+    ) {
+        match compute_d(2 * amp, amount_a, amount_b) {
+            Ok(d) => prop_assert!(d <= amount_a + amount_b),
+            Err(err) => prop_assert!(is_math_error(&err), "{:?}", err),
+        }
+    }
+
+    /// 균형에 가까운 풀에서는 스왑한 양을 다시 사는 데 필요한 양이 처음 낸 양과 거의 같다.
+    #[test]
+    fn math_swap_round_trip(
+        pool in 1_000000u128..=1_000_000_000_000_000_000,
+        skew in 1u128..=10,
+        offer_amount in 1_000000u128..=1_000_000_000_000,
+        amp in AMP_PRECISION..=MAX_AMP * AMP_PRECISION,
+    ) {
+        let offer_pool = pool;
+        let ask_pool = pool * skew;
+        let ask_amount = calc_ask_amount(offer_pool, ask_pool, offer_amount, amp).unwrap();
+        prop_assume!(ask_amount > 0);
+        let offer_required = calc_offer_amount(offer_pool, ask_pool, ask_amount, amp).unwrap();
+        prop_assert!(offer_required <= offer_amount + 2, "{} > {}", offer_required, offer_amount);
     }
 }
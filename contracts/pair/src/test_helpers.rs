@@ -10,6 +10,7 @@ use novaswap::pair::{Cw20HookMsg, ExecuteMsg};
 use prost::Message;
 
 use crate::contract::{execute, reply};
+use crate::state::RESERVES;
 
 pub const TOKEN_DECIMALS: u128 = 1_000000000000000000u128;
 
@@ -43,6 +44,16 @@ pub fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String)
     reply(deps, mock_env(), reply_msg).unwrap();
 }
 
+// Sets the reserves of the pair, as if they had been deposited through the pair.
+pub fn store_reserves(deps: DepsMut, reserves: [u128; 2]) {
+    RESERVES
+        .save(
+            deps.storage,
+            &[Uint128::new(reserves[0]), Uint128::new(reserves[1])],
+        )
+        .unwrap();
+}
+
 pub fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
@@ -61,6 +72,8 @@ pub fn swap_token(deps: DepsMut, swap_amount: Uint128) -> SwapResult {
             belief_price: None,
             max_spread: Some(Decimal::from_str("0.4").unwrap()),
             to: None,
+            forward_msg: None,
+            deadline: None,
         })
         .unwrap(),
     });
@@ -94,7 +107,7 @@ impl Display for SwapResult {
             .return_amount
             .checked_add(self.commission_amount)
             .unwrap();
-        return write!(
+        write!(
             f,
             "offer: {}, return: {}, spread: {}, commission: {}, actual: {}",
             self.offer_amount,
@@ -102,11 +115,12 @@ impl Display for SwapResult {
             self.spread_amount,
             self.commission_amount,
             actual_amount
-        );
+        )
     }
 }
 
 // swap simulation datas
+#[allow(dead_code)]
 pub struct SwapTestCase {
     pub token_a: u128,       // token amount of A in liquidity before swap operation
     pub token_b: u128,       // token amount of B in liquidity before swap operation
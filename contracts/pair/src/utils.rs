@@ -1,73 +1,201 @@
 use crate::error::ContractError;
 use crate::math::{
-    calc_ask_amount, calc_offer_amount, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE,
-    MIN_AMP_CHANGING_TIME,
+    calc_ask_amount, calc_offer_amount, compute_d, compute_new_balance, AMP_PRECISION, MAX_AMP,
+    MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME, N_COINS,
 };
-use crate::state::{Config, CONFIG};
+use crate::state::{Config, RateConfig, CONFIG, OBSERVATIONS, OBSERVATIONS_COUNT, RATES, RESERVES};
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, StdResult, Uint128, WasmMsg,
+    to_binary, Addr, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut, Env, Fraction, StdError,
+    StdResult, Storage, Uint128, Uint256, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
+use cw_utils::Expiration;
 use novaswap::asset::Asset;
-use novaswap::pair::{DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION};
-use novaswap::querier::query_supply;
+use novaswap::factory::FeeInfoResponse;
+use novaswap::pair::{
+    Observation, PoolStatus, RatesResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
+};
+use novaswap::querier::{query_rate, query_supply};
 use std::cmp::Ordering;
 use std::str::FromStr;
 
-const GLOBAL_FEE_NUMERATOR: u128 = 30;
-const GLOBAL_FEE_DENOMINATOR: u128 = 10000;
+pub const DEFAULT_FEE_BPS: u16 = 30;
+pub const MAX_FEE_BPS: u16 = 1000;
+pub const MAX_OBSERVATIONS: u64 = 1000;
+const FEE_DENOMINATOR: u128 = 10000;
+
+// Returns the fee rate of the pair
+pub fn compute_fee_rate(config: &Config) -> Decimal {
+    Decimal::from_ratio(Uint128::from(config.fee_bps), Uint128::new(FEE_DENOMINATOR))
+}
+
+// Returns the fee of a flash loan of `amount`, charged at the swap fee rate and rounded up.
+pub fn compute_flash_fee(config: &Config, amount: Uint128) -> StdResult<Uint128> {
+    let fee_denominator = Uint128::new(FEE_DENOMINATOR);
+    Ok(amount
+        .checked_mul(Uint128::from(config.fee_bps))?
+        .checked_add(fee_denominator - Uint128::new(1))?
+        / fee_denominator)
+}
+
+// Returns the fee rate charged on the imbalanced part of a liquidity operation.
+// fee * n / (4 * (n - 1)), which is the half of the swap fee for two coins.
+pub fn compute_imbalance_fee_rate(fee_rate: Decimal) -> Decimal {
+    let n_coins = u128::from(N_COINS);
+    fee_rate * Decimal::from_ratio(n_coins, 4 * (n_coins - 1))
+}
+
+// Returns the part of the commission which goes to the factory fee address.
+// Nothing is taken when the fee address is not set.
+pub fn compute_maker_fee(fee_info: &FeeInfoResponse, commission_amount: Uint128) -> Uint128 {
+    if fee_info.fee_address.is_some() {
+        commission_amount * fee_info.maker_fee_rate
+    } else {
+        Uint128::zero()
+    }
+}
+
+// Multiplies `amount` by `ratio` like `amount * ratio`, failing instead of panicking on overflow.
+pub fn checked_mul_decimal(amount: Uint128, ratio: Decimal) -> Result<Uint128, ContractError> {
+    amount
+        .checked_multiply_ratio(ratio.numerator(), ratio.denominator())
+        .map_err(|_| ContractError::MathOverflow {})
+}
 
-// Returns global fee rate
-pub fn compute_global_fee() -> Decimal {
-    Decimal::from_ratio(
-        Uint128::new(GLOBAL_FEE_NUMERATOR),
-        Uint128::new(GLOBAL_FEE_DENOMINATOR),
-    )
+// Returns `value * numerator / denominator` rounded up.
+pub fn multiply_ratio_ceil(
+    value: Uint128,
+    numerator: Uint128,
+    denominator: Uint128,
+) -> Result<Uint128, ContractError> {
+    if denominator.is_zero() {
+        return Err(ContractError::MathOverflow {});
+    }
+
+    let product = value.full_mul(numerator);
+    let denominator = Uint256::from(denominator);
+    let mut result = product / denominator;
+    if !(product % denominator).is_zero() {
+        result += Uint256::from(1u128);
+    }
+    Uint128::try_from(result).map_err(|_| ContractError::MathOverflow {})
 }
 
 // Returns an amount of offer assets for a specified amount of ask assets.
+// The offer amount is rounded up in favor of the pool, so swapping it returns at least `ask_amount`.
+#[allow(clippy::too_many_arguments)]
 pub fn compute_offer_amount(
     offer_pool: Uint128,
     offer_precision: u8,
+    offer_rate: Decimal,
     ask_pool: Uint128,
     ask_precision: u8,
+    ask_rate: Decimal,
     ask_amount: Uint128,
     commission_rate: Decimal,
     amp: u64,
-) -> StdResult<(Uint128, Uint128, Uint128)> {
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
     // ask => offer
 
     let greater_precision = offer_precision.max(ask_precision);
-    let offer_pool = adjust_precision(offer_pool, offer_precision, greater_precision)?;
-    let ask_pool = adjust_precision(ask_pool, ask_precision, greater_precision)?;
-    let ask_amount = adjust_precision(ask_amount, ask_precision, greater_precision)?;
-
+    let offer_pool = apply_rate(
+        adjust_precision(offer_pool, offer_precision, greater_precision)?,
+        offer_rate,
+    )?;
+    let ask_pool = apply_rate(
+        adjust_precision(ask_pool, ask_precision, greater_precision)?,
+        ask_rate,
+    )?;
+    let ask_amount = multiply_ratio_ceil(
+        adjust_precision(ask_amount, ask_precision, greater_precision)?,
+        ask_rate.atomics(),
+        Decimal::one().atomics(),
+    )?;
+
+    // The least amount which is still at least `ask_amount` after the commission,
+    // which is rounded down in `compute_swap`, is deducted
     let one_minus_commission = Decimal::one() - commission_rate;
-    let inv_one_minus_commission: Decimal = Decimal::one() / one_minus_commission;
-    let before_commission_deduction = ask_amount * inv_one_minus_commission;
-
-    let offer_amount = Uint128::new(
-        calc_offer_amount(
-            offer_pool.u128(),
-            ask_pool.u128(),
-            before_commission_deduction.u128(),
-            amp,
-        )
-        .unwrap(),
-    );
+    let before_commission_deduction = if ask_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        (ask_amount - Uint128::new(1))
+            .checked_multiply_ratio(Decimal::one().atomics(), one_minus_commission.atomics())
+            .map_err(|_| ContractError::MathOverflow {})?
+            .checked_add(Uint128::new(1))?
+    };
 
-    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. Any exchange rate < 1 could be considered the spread
+    let offer_amount = Uint128::new(calc_offer_amount(
+        offer_pool.u128(),
+        ask_pool.u128(),
+        before_commission_deduction.u128(),
+        amp,
+    )?);
+
+    // We assume the assets should stay at the ratio of their redemption rates, so the true exchange rate is 1
+    // between the valued amounts. Any exchange rate < 1 could be considered the spread
     let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
 
     let commission_amount = before_commission_deduction * commission_rate;
 
-    let offer_amount = adjust_precision(offer_amount, greater_precision, offer_precision)?;
-    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
-    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+    let offer_amount = multiply_ratio_ceil(
+        multiply_ratio_ceil(offer_amount, Decimal::one().atomics(), offer_rate.atomics())?,
+        Uint128::new(1),
+        Uint128::new(10_u128.pow((greater_precision - offer_precision) as u32)),
+    )?;
+    let spread_amount = adjust_precision(
+        remove_rate(spread_amount, ask_rate)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let commission_amount = adjust_precision(
+        remove_rate(commission_amount, ask_rate)?,
+        greater_precision,
+        ask_precision,
+    )?;
 
     Ok((offer_amount, spread_amount, commission_amount))
 }
 
+// Returns a [`ContractError`] when the deadline of the message has passed at the current block.
+pub fn assert_deadline(
+    block: &BlockInfo,
+    deadline: Option<Expiration>,
+) -> Result<(), ContractError> {
+    match deadline {
+        Some(deadline) if deadline.is_expired(block) => Err(ContractError::Expired {}),
+        _ => Ok(()),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow swaps.
+pub fn assert_swaps_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active => Ok(()),
+        PoolStatus::SwapsPaused => Err(ContractError::SwapsPaused {}),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow deposits.
+pub fn assert_deposits_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active | PoolStatus::SwapsPaused => Ok(()),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Updates the operations allowed by the pool.
+pub fn update_status(
+    mut config: Config,
+    deps: DepsMut,
+    status: PoolStatus,
+) -> Result<(), ContractError> {
+    config.status = status;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
 // Returns a [`ContractError`] on failure.
 // If `belief_price` and `max_spread` are both specified, we compute a new spread,
 // otherwise we just use the swap spread to check `max_spread`.
@@ -87,7 +215,10 @@ pub fn assert_max_spread(
     }
 
     if let Some(belief_price) = belief_price {
-        let expected_return = offer_amount * (Decimal::one() / belief_price);
+        let inverse_belief_price =
+            Decimal::checked_from_ratio(Decimal::one().numerator(), belief_price.numerator())
+                .map_err(|_| StdError::generic_err("Belief price must be greater than zero"))?;
+        let expected_return = checked_mul_decimal(offer_amount, inverse_belief_price)?;
         let spread_amount = expected_return
             .checked_sub(return_amount)
             .unwrap_or_else(|_| Uint128::zero());
@@ -97,13 +228,36 @@ pub fn assert_max_spread(
         {
             return Err(ContractError::MaxSpreadAssertion {});
         }
-    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+    } else if Decimal::from_ratio(spread_amount, return_amount.checked_add(spread_amount)?)
+        > max_spread
+    {
         return Err(ContractError::MaxSpreadAssertion {});
     }
 
     Ok(())
 }
 
+// Returns a [`ContractError`] on failure.
+// `expected_share` is the amount of LP tokens minted for the deposit valued at par,
+// `share` may fall short of it by at most `slippage_tolerance`.
+pub fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    share: Uint128,
+    expected_share: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(slippage_tolerance) = slippage_tolerance {
+        if slippage_tolerance > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+            return Err(ContractError::AllowedSlippageAssertion {});
+        }
+
+        if share < expected_share * (Decimal::one() - slippage_tolerance) {
+            return Err(ContractError::MaxSlippageAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
 // Start changing the AMP value. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
 pub fn start_changing_amp(
     mut config: Config,
@@ -144,6 +298,18 @@ pub fn start_changing_amp(
     Ok(())
 }
 
+// Update the swap fee of the pair. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn update_fee(mut config: Config, deps: DepsMut, fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
+    config.fee_bps = fee_bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
 // Stop changing the AMP value. Returns [`Ok`].
 pub fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<()> {
     let current_amp = compute_current_amp(&config, &env)?;
@@ -205,6 +371,88 @@ pub fn adjust_precision(
     })
 }
 
+// Returns the value of `amount` at the redemption `rate`.
+pub fn apply_rate(amount: Uint128, rate: Decimal) -> StdResult<Uint128> {
+    amount
+        .checked_multiply_ratio(rate.atomics(), Decimal::one().atomics())
+        .map_err(|_| StdError::generic_err("Failed to apply the redemption rate"))
+}
+
+// Returns the amount of an asset which is worth `value` at the redemption `rate`.
+pub fn remove_rate(value: Uint128, rate: Decimal) -> StdResult<Uint128> {
+    value
+        .checked_multiply_ratio(Decimal::one().atomics(), rate.atomics())
+        .map_err(|_| StdError::generic_err("Failed to remove the redemption rate"))
+}
+
+// Returns the redemption rates of the pair assets and the time the oldest one was updated.
+// The assets trade 1:1 when the pair has no rates.
+pub fn query_rates(deps: Deps, env: &Env, config: &Config) -> StdResult<RatesResponse> {
+    let asset_infos = config.pair_info.asset_infos.clone();
+    let rate_config = RATES.may_load(deps.storage)?;
+
+    let (rates, updated_at) = match &rate_config {
+        None => ([Decimal::one(); 2], env.block.time.seconds()),
+        Some(RateConfig {
+            rate_provider: Some(rate_provider),
+            ..
+        }) => {
+            let rate0 = query_rate(&deps.querier, rate_provider.clone(), asset_infos[0].clone())?;
+            let rate1 = query_rate(&deps.querier, rate_provider.clone(), asset_infos[1].clone())?;
+            (
+                [rate0.rate, rate1.rate],
+                rate0.updated_at.min(rate1.updated_at),
+            )
+        }
+        Some(rate_config) => (rate_config.rates, rate_config.rates_updated_at),
+    };
+
+    Ok(RatesResponse {
+        asset_infos,
+        rates,
+        updated_at,
+        rate_provider: rate_config.as_ref().and_then(|c| c.rate_provider.clone()),
+        max_rate_age: rate_config.and_then(|c| c.max_rate_age),
+    })
+}
+
+// Returns the redemption rates used for pricing, or a [`ContractError`] when they are stale.
+pub fn load_rates(deps: Deps, env: &Env, config: &Config) -> Result<[Decimal; 2], ContractError> {
+    let rates = query_rates(deps, env, config)?;
+
+    if rates.rates.iter().any(|rate| rate.is_zero()) {
+        return Err(ContractError::IncorrectRate {});
+    }
+
+    if let Some(max_rate_age) = rates.max_rate_age {
+        if env.block.time.seconds() > rates.updated_at.saturating_add(max_rate_age) {
+            return Err(ContractError::StaleRates {
+                updated_at: rates.updated_at,
+            });
+        }
+    }
+
+    Ok(rates.rates)
+}
+
+// Update the redemption rates set by the owner. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn update_rates(deps: DepsMut, env: Env, rates: [Decimal; 2]) -> Result<(), ContractError> {
+    let mut rate_config = match RATES.may_load(deps.storage)? {
+        Some(rate_config) if rate_config.rate_provider.is_none() => rate_config,
+        _ => return Err(ContractError::RatesNotUpdatable {}),
+    };
+
+    if rates.iter().any(|rate| rate.is_zero()) {
+        return Err(ContractError::IncorrectRate {});
+    }
+
+    rate_config.rates = rates;
+    rate_config.rates_updated_at = env.block.time.seconds();
+    RATES.save(deps.storage, &rate_config)?;
+
+    Ok(())
+}
+
 // Mint LP tokens for a beneficiary
 pub fn mint_liquidity_token_message(
     config: &Config,
@@ -230,15 +478,23 @@ pub fn accumulate_prices(
     x_precision: u8,
     y: Uint128,
     y_precision: u8,
-) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+    rates: [Decimal; 2],
+) -> Result<Option<(Uint128, Uint128, u64)>, ContractError> {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
         return Ok(None);
     }
 
     let greater_precision = x_precision.max(y_precision).max(TWAP_PRECISION);
-    let x = adjust_precision(x, x_precision, greater_precision)?;
-    let y = adjust_precision(y, y_precision, greater_precision)?;
+    let x = apply_rate(
+        adjust_precision(x, x_precision, greater_precision)?,
+        rates[0],
+    )?;
+    let y = apply_rate(
+        adjust_precision(y, y_precision, greater_precision)?,
+        rates[1],
+    )?;
+    let one = adjust_precision(Uint128::new(1), 0, greater_precision)?;
 
     let time_elapsed = Uint128::from(block_time - config.block_time_last);
 
@@ -248,28 +504,28 @@ pub fn accumulate_prices(
     if !x.is_zero() && !y.is_zero() {
         let current_amp = compute_current_amp(config, &env)?;
         pcl0 = config.price0_cumulative_last.wrapping_add(adjust_precision(
-            time_elapsed.checked_mul(Uint128::new(
-                calc_ask_amount(
+            time_elapsed.checked_mul(remove_rate(
+                Uint128::new(calc_ask_amount(
                     x.u128(),
                     y.u128(),
-                    adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
+                    apply_rate(one, rates[0])?.u128(),
                     current_amp,
-                )
-                .unwrap(),
-            ))?,
+                )?),
+                rates[1],
+            )?)?,
             greater_precision,
             TWAP_PRECISION,
         )?);
         pcl1 = config.price1_cumulative_last.wrapping_add(adjust_precision(
-            time_elapsed.checked_mul(Uint128::new(
-                calc_ask_amount(
+            time_elapsed.checked_mul(remove_rate(
+                Uint128::new(calc_ask_amount(
                     y.u128(),
                     x.u128(),
-                    adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
+                    apply_rate(one, rates[1])?.u128(),
                     current_amp,
-                )
-                .unwrap(),
-            ))?,
+                )?),
+                rates[0],
+            )?)?,
             greater_precision,
             TWAP_PRECISION,
         )?)
@@ -278,6 +534,88 @@ pub fn accumulate_prices(
     Ok(Some((pcl0, pcl1, block_time)))
 }
 
+// Stores the cumulative prices of the block in the observation buffer, overwriting the oldest
+// observation when the buffer is full.
+pub fn store_observation(
+    storage: &mut dyn Storage,
+    timestamp: u64,
+    price0_cumulative: Uint128,
+    price1_cumulative: Uint128,
+) -> StdResult<()> {
+    let count = OBSERVATIONS_COUNT.may_load(storage)?.unwrap_or_default();
+    OBSERVATIONS.save(
+        storage,
+        count % MAX_OBSERVATIONS,
+        &Observation {
+            timestamp,
+            price0_cumulative,
+            price1_cumulative,
+        },
+    )?;
+    OBSERVATIONS_COUNT.save(storage, &(count + 1))
+}
+
+// Returns the cumulative prices at `timestamp`, interpolated between the stored observations.
+// `latest` are the cumulative prices accumulated up to the current block.
+pub fn observe(
+    storage: &dyn Storage,
+    latest: &Observation,
+    timestamp: u64,
+) -> StdResult<Observation> {
+    if timestamp >= latest.timestamp {
+        return Ok(latest.clone());
+    }
+
+    let count = OBSERVATIONS_COUNT.may_load(storage)?.unwrap_or_default();
+    let len = count.min(MAX_OBSERVATIONS);
+    let oldest = count - len;
+    let load = |i: u64| OBSERVATIONS.load(storage, (oldest + i) % MAX_OBSERVATIONS);
+
+    if len == 0 || load(0)?.timestamp > timestamp {
+        return Err(StdError::generic_err(
+            "The requested time is older than the oldest observation",
+        ));
+    }
+
+    // binary search for the last observation at or before `timestamp`
+    let (mut low, mut high) = (0, len - 1);
+    while low < high {
+        let mid = high - (high - low) / 2;
+        if load(mid)?.timestamp <= timestamp {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    let before = load(low)?;
+    let after = if low + 1 < len {
+        load(low + 1)?
+    } else {
+        latest.clone()
+    };
+
+    if before.timestamp == timestamp || after.timestamp == before.timestamp {
+        return Ok(Observation {
+            timestamp,
+            ..before
+        });
+    }
+
+    // prices are constant between two observations, so cumulative prices grow linearly
+    let elapsed = timestamp - before.timestamp;
+    let interval = after.timestamp - before.timestamp;
+    let interpolate = |before: Uint128, after: Uint128| {
+        before.wrapping_add(after.wrapping_sub(before).multiply_ratio(elapsed, interval))
+    };
+
+    Ok(Observation {
+        timestamp,
+        price0_cumulative: interpolate(before.price0_cumulative, after.price0_cumulative),
+        price1_cumulative: interpolate(before.price1_cumulative, after.price1_cumulative),
+    })
+}
+
 pub fn get_share_in_assets(
     pools: &[Asset; 2],
     amount: Uint128,
@@ -301,45 +639,266 @@ pub fn get_share_in_assets(
 }
 
 pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
-    let contract_addr = config.pair_info.contract_addr.clone();
-    let pools: [Asset; 2] = config.pair_info.query_pools(&deps.querier, contract_addr)?;
+    let pools = load_reserves(deps.storage, &config)?;
     let total_supply: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
 
     Ok((pools, total_supply))
 }
 
+// Returns the tracked reserves of the pair, balances above them are not counted.
+pub fn load_reserves(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
+
 /// compute swap then returns return_amount, spread_amount and commission_amount.
+#[allow(clippy::too_many_arguments)]
 pub fn compute_swap(
     offer_pool: Uint128,
     offer_precision: u8,
+    offer_rate: Decimal,
     ask_pool: Uint128,
     ask_precision: u8,
+    ask_rate: Decimal,
     offer_amount: Uint128,
     commission_rate: Decimal,
     amp: u64,
-) -> StdResult<(Uint128, Uint128, Uint128)> {
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
     // offer => ask
 
     let greater_precision = offer_precision.max(ask_precision);
-    let offer_pool = adjust_precision(offer_pool, offer_precision, greater_precision)?;
-    let ask_pool = adjust_precision(ask_pool, ask_precision, greater_precision)?;
-    let offer_amount = adjust_precision(offer_amount, offer_precision, greater_precision)?;
-
-    let return_amount = Uint128::new(
-        calc_ask_amount(offer_pool.u128(), ask_pool.u128(), offer_amount.u128(), amp).unwrap(),
-    );
-
-    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. So any exchange rate <1 could be considered the spread
+    let offer_pool = apply_rate(
+        adjust_precision(offer_pool, offer_precision, greater_precision)?,
+        offer_rate,
+    )?;
+    let ask_pool = apply_rate(
+        adjust_precision(ask_pool, ask_precision, greater_precision)?,
+        ask_rate,
+    )?;
+    let offer_amount = apply_rate(
+        adjust_precision(offer_amount, offer_precision, greater_precision)?,
+        offer_rate,
+    )?;
+
+    let return_amount = Uint128::new(calc_ask_amount(
+        offer_pool.u128(),
+        ask_pool.u128(),
+        offer_amount.u128(),
+        amp,
+    )?);
+
+    // We assume the assets should stay at the ratio of their redemption rates, so the true exchange rate is 1
+    // between the valued amounts. So any exchange rate <1 could be considered the spread
     let spread_amount = offer_amount.saturating_sub(return_amount);
 
     let commission_amount: Uint128 = return_amount * commission_rate;
 
     // The commission will be absorbed by the pool
-    let return_amount: Uint128 = return_amount.checked_sub(commission_amount).unwrap();
-
-    let return_amount = adjust_precision(return_amount, greater_precision, ask_precision)?;
-    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
-    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount)?;
+
+    let return_amount = adjust_precision(
+        remove_rate(return_amount, ask_rate)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let spread_amount = adjust_precision(
+        remove_rate(spread_amount, ask_rate)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let commission_amount = adjust_precision(
+        remove_rate(commission_amount, ask_rate)?,
+        greater_precision,
+        ask_precision,
+    )?;
 
     Ok((return_amount, spread_amount, commission_amount))
 }
+
+/// compute the amount received for burning `share` LP tokens into one coin,
+/// then returns return_amount and fee_amount.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_withdraw_one_coin(
+    withdraw_pool: Uint128,
+    withdraw_precision: u8,
+    withdraw_rate: Decimal,
+    other_pool: Uint128,
+    other_precision: u8,
+    other_rate: Decimal,
+    share: Uint128,
+    total_share: Uint128,
+    fee_rate: Decimal,
+    amp: u64,
+) -> Result<(Uint128, Uint128), ContractError> {
+    if share > total_share || total_share.is_zero() {
+        return Err(StdError::generic_err("Share exceeds the total supply").into());
+    }
+
+    let greater_precision = withdraw_precision.max(other_precision);
+    let withdraw_pool = apply_rate(
+        adjust_precision(withdraw_pool, withdraw_precision, greater_precision)?,
+        withdraw_rate,
+    )?;
+    let other_pool = apply_rate(
+        adjust_precision(other_pool, other_precision, greater_precision)?,
+        other_rate,
+    )?;
+
+    let leverage = amp
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
+    let d_before = Uint128::new(compute_d(
+        leverage,
+        withdraw_pool.u128(),
+        other_pool.u128(),
+    )?);
+    let d_after = d_before.checked_sub(d_before.multiply_ratio(share, total_share))?;
+
+    // balance of the withdrawn coin after burning the share, without fees
+    let new_withdraw_pool = Uint128::new(compute_new_balance(
+        leverage,
+        other_pool.u128(),
+        d_after.u128(),
+    )?);
+
+    // charge the imbalance fee on how far each balance moves from a proportional withdrawal
+    let imbalance_fee_rate = compute_imbalance_fee_rate(fee_rate);
+    let withdraw_expected = withdraw_pool
+        .multiply_ratio(d_after, d_before)
+        .saturating_sub(new_withdraw_pool);
+    let other_expected = other_pool.checked_sub(other_pool.multiply_ratio(d_after, d_before))?;
+    let withdraw_pool_reduced =
+        withdraw_pool.checked_sub(withdraw_expected * imbalance_fee_rate)?;
+    let other_pool_reduced = other_pool.checked_sub(other_expected * imbalance_fee_rate)?;
+
+    let new_withdraw_pool_reduced = Uint128::new(compute_new_balance(
+        leverage,
+        other_pool_reduced.u128(),
+        d_after.u128(),
+    )?);
+
+    // subtract one to round down in favor of the pool
+    let return_amount = withdraw_pool_reduced
+        .saturating_sub(new_withdraw_pool_reduced)
+        .saturating_sub(Uint128::new(1));
+    let return_amount_without_fee = withdraw_pool.saturating_sub(new_withdraw_pool);
+    let fee_amount = return_amount_without_fee.saturating_sub(return_amount);
+
+    let return_amount = adjust_precision(
+        remove_rate(return_amount, withdraw_rate)?,
+        greater_precision,
+        withdraw_precision,
+    )?;
+    let fee_amount = adjust_precision(
+        remove_rate(fee_amount, withdraw_rate)?,
+        greater_precision,
+        withdraw_precision,
+    )?;
+
+    Ok((return_amount, fee_amount))
+}
+
+// Returns the imbalance fee for each balance, charged on the difference between
+// the new balance and the balance it would have after a proportional change of D.
+pub fn compute_imbalance_fees(
+    old_balances: [Uint128; 2],
+    new_balances: [Uint128; 2],
+    d_before: Uint128,
+    d_after: Uint128,
+    imbalance_fee_rate: Decimal,
+) -> [Uint128; 2] {
+    let mut fees = [Uint128::zero(); 2];
+    for (i, fee) in fees.iter_mut().enumerate() {
+        let ideal_balance = old_balances[i].multiply_ratio(d_after, d_before);
+        let difference = if ideal_balance > new_balances[i] {
+            ideal_balance - new_balances[i]
+        } else {
+            new_balances[i] - ideal_balance
+        };
+        *fee = difference * imbalance_fee_rate;
+    }
+    fees
+}
+
+/// compute the amount of LP tokens to burn for withdrawing exact `amounts` from the pool,
+/// then returns burn_amount and the imbalance fee charged on each asset.
+pub fn compute_withdraw_imbalance(
+    pools: [Uint128; 2],
+    precisions: [u8; 2],
+    rates: [Decimal; 2],
+    amounts: [Uint128; 2],
+    total_share: Uint128,
+    fee_rate: Decimal,
+    amp: u64,
+) -> Result<(Uint128, [Uint128; 2]), ContractError> {
+    if total_share.is_zero() {
+        return Err(StdError::generic_err("The pool is empty").into());
+    }
+
+    let greater_precision = precisions[0].max(precisions[1]);
+    let mut old_balances = [Uint128::zero(); 2];
+    let mut new_balances = [Uint128::zero(); 2];
+    for i in 0..2 {
+        old_balances[i] = apply_rate(
+            adjust_precision(pools[i], precisions[i], greater_precision)?,
+            rates[i],
+        )?;
+        new_balances[i] = old_balances[i].checked_sub(apply_rate(
+            adjust_precision(amounts[i], precisions[i], greater_precision)?,
+            rates[i],
+        )?)?;
+    }
+
+    let leverage = amp
+        .checked_mul(u64::from(N_COINS))
+        .ok_or(ContractError::MathOverflow {})?;
+    let compute_d_of = |balances: &[Uint128; 2]| {
+        compute_d(leverage, balances[0].u128(), balances[1].u128()).map(Uint128::new)
+    };
+
+    let d_before = compute_d_of(&old_balances)?;
+    let d_after = compute_d_of(&new_balances)?;
+
+    let fees = compute_imbalance_fees(
+        old_balances,
+        new_balances,
+        d_before,
+        d_after,
+        compute_imbalance_fee_rate(fee_rate),
+    );
+    for i in 0..2 {
+        new_balances[i] = new_balances[i].checked_sub(fees[i])?;
+    }
+    let d_after_fee = compute_d_of(&new_balances)?;
+
+    // add one to round up in favor of the pool
+    let burn_amount = total_share
+        .multiply_ratio(d_before.checked_sub(d_after_fee)?, d_before)
+        .checked_add(Uint128::new(1))?;
+
+    Ok((
+        burn_amount,
+        [
+            adjust_precision(
+                remove_rate(fees[0], rates[0])?,
+                greater_precision,
+                precisions[0],
+            )?,
+            adjust_precision(
+                remove_rate(fees[1], rates[1])?,
+                greater_precision,
+                precisions[1],
+            )?,
+        ],
+    ))
+}
@@ -0,0 +1,101 @@
+use crate::math::{MAX_AMP, MAX_MA_HALF_TIME};
+use crate::utils::MAX_FEE_BPS;
+use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError};
+use novaswap::asset::AssetInfo;
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes concentrated liquidity pair contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Insufficient amount of liquidity")]
+    LiquidityAmountTooSmall {},
+
+    #[error("Withdrawn amount is less than the minimum, minimum: {minimum}, received: {received}, asset: {asset}")]
+    WithdrawAmountTooSmall {
+        minimum: u128,
+        received: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Operation exceeds max splippage tolerance")]
+    MaxSlippageAssertion {},
+
+    #[error("Provided slippage tolerance exceeds allowed limit")]
+    AllowedSlippageAssertion {},
+
+    #[error("Minted LP amount is less than the minimum, minimum: {minimum}, received: {received}")]
+    MinLpAmountAssertion { minimum: u128, received: u128 },
+
+    #[error("Native token balance mismatch between the argument and the transferred")]
+    AssetMismatch {},
+
+    #[error(
+        "Amp coefficient must be greater than 0 and less than or equal to {}",
+        MAX_AMP
+    )]
+    IncorrectAmp {},
+
+    #[error("Fee must be less than or equal to {} bps", MAX_FEE_BPS)]
+    IncorrectFee {},
+
+    #[error("Price scale must be greater than zero")]
+    IncorrectPriceScale {},
+
+    #[error(
+        "Adjustment step and allowed extra profit must be less than 1, MA half time must be between 1 and {} seconds",
+        MAX_MA_HALF_TIME
+    )]
+    IncorrectRepegParams {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("It is not possible to provide liquidity with one token for an empty pool")]
+    InvalidProvideLPsWithSingleToken {},
+
+    #[error("Swaps are paused in this pool")]
+    SwapsPaused {},
+
+    #[error("The pool only allows withdrawals")]
+    WithdrawOnly {},
+
+    #[error("The transaction deadline has passed")]
+    Expired {},
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
+
+impl From<ConversionOverflowError> for ContractError {
+    fn from(o: ConversionOverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
@@ -0,0 +1,306 @@
+/// Stableswap Algorithm around an internal price scale
+/// reference: https://miguelmota.com/blog/understanding-stableswap-curve/
+/// reference: https://classic.curve.fi/files/crypto-pools-paper.pdf
+use cosmwasm_std::{Decimal, Uint128};
+use novaswap::U256;
+use std::convert::TryFrom;
+
+const N_COINS_SQUARED: u8 = 4;
+const ITERATIONS: u8 = 32;
+
+pub const N_COINS: u8 = 2;
+pub const MAX_AMP: u64 = 1_000_000;
+pub const AMP_PRECISION: u64 = 100;
+pub const MAX_MA_HALF_TIME: u64 = 7 * 86400;
+
+// Precision of the moving average weight, terms below it are dropped.
+const HALFPOW_TOLERANCE: u128 = 10_000_000_000;
+const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
+
+/// ## Description
+/// Calculates the ask amount (the amount of tokens swapped to).
+/// ## Params
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens currently in a pool, valued at the price scale.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens currently in a pool, valued at the price scale.
+///
+/// * **offer_amount** is an object of type [`u128`]. This is the amount of offer tokens to swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_ask_amount(
+    offer_pool: u128,
+    ask_pool: u128,
+    offer_amount: u128,
+    amp: u64,
+) -> Option<u128> {
+    let leverage = amp.checked_mul(u64::from(N_COINS))?;
+    let new_offer_pool = offer_pool.checked_add(offer_amount)?;
+
+    let d = compute_d(leverage, offer_pool, ask_pool)?;
+
+    let new_ask_pool = compute_new_balance(leverage, new_offer_pool, d)?;
+
+    let amount_swapped = ask_pool.checked_sub(new_ask_pool)?;
+    Some(amount_swapped)
+}
+
+/// ## Description
+/// Calculates the amount to be swapped (the offer amount).
+/// ## Params
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens currently in a pool, valued at the price scale.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens currently in a pool, valued at the price scale.
+///
+/// * **ask_amount** is an object of type [`u128`]. This is the amount of offer tokens to swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_offer_amount(
+    offer_pool: u128,
+    ask_pool: u128,
+    ask_amount: u128,
+    amp: u64,
+) -> Option<u128> {
+    let leverage = amp.checked_mul(u64::from(N_COINS))?;
+    let new_ask_pool = ask_pool.checked_sub(ask_amount)?;
+
+    let d = compute_d(leverage, offer_pool, ask_pool)?;
+
+    let new_offer_pool = compute_new_balance(leverage, new_ask_pool, d)?;
+
+    let amount_swapped = new_offer_pool.checked_sub(offer_pool)?;
+    Some(amount_swapped)
+}
+
+/// ## Description
+/// Computes the stableswap invariant (D).
+///
+/// * **Equation**
+///
+/// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
+///
+/// ## Params
+/// * **leverage** is an object of type [`u128`].
+///
+/// * **amount_a** is an object of type [`u128`].
+///
+/// * **amount_b** is an object of type [`u128`].
+pub fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
+    let amount_a_times_coins =
+        checked_u8_mul(&U256::from(amount_a), N_COINS)?.checked_add(U256::one())?;
+    let amount_b_times_coins =
+        checked_u8_mul(&U256::from(amount_b), N_COINS)?.checked_add(U256::one())?;
+    let sum_x = amount_a.checked_add(amount_b)?; // sum(x_i), a.k.a S
+    if sum_x == 0 {
+        Some(0)
+    } else {
+        let mut d_previous: U256;
+        let mut d: U256 = sum_x.into();
+
+        // Newton's method to approximate D
+        for _ in 0..ITERATIONS {
+            let mut d_product = d;
+            d_product = d_product
+                .checked_mul(d)?
+                .checked_div(amount_a_times_coins)?;
+            d_product = d_product
+                .checked_mul(d)?
+                .checked_div(amount_b_times_coins)?;
+            d_previous = d;
+            // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
+            d = calculate_step(&d, leverage, sum_x, &d_product)?;
+            // Equality with the precision of 1
+            if d == d_previous {
+                break;
+            }
+        }
+        u128::try_from(d).ok()
+    }
+}
+
+/// ## Description
+/// Helper function used to calculate the D invariant as a last step in the `compute_d` public function.
+///
+/// * **Equation**:
+///
+/// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) * initial_d + (n_coins + 1) * d_product)
+fn calculate_step(initial_d: &U256, leverage: u64, sum_x: u128, d_product: &U256) -> Option<U256> {
+    let leverage_mul = U256::from(leverage).checked_mul(sum_x.into())? / AMP_PRECISION;
+    let d_p_mul = checked_u8_mul(d_product, N_COINS)?;
+
+    let l_val = leverage_mul.checked_add(d_p_mul)?.checked_mul(*initial_d)?;
+
+    let leverage_sub =
+        initial_d.checked_mul((leverage.checked_sub(AMP_PRECISION)?).into())? / AMP_PRECISION;
+    let n_coins_sum = checked_u8_mul(d_product, N_COINS.checked_add(1)?)?;
+
+    let r_val = leverage_sub.checked_add(n_coins_sum)?;
+
+    l_val.checked_div(r_val)
+}
+
+/// ## Description
+/// Compute the swap amount `y` in proportion to `x`.
+///
+/// * **Solve for y**
+///
+/// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+///
+/// y**2 + b*y = c
+pub fn compute_new_balance(leverage: u64, new_source_amount: u128, d_val: u128) -> Option<u128> {
+    // Upscale to U256
+    let leverage: U256 = leverage.into();
+    let new_source_amount: U256 = new_source_amount.into();
+    let d_val: U256 = d_val.into();
+
+    // sum' = prod' = x
+    // c =  D ** (n + 1) / (n ** (2 * n) * prod' * A)
+    let c = checked_u8_power(&d_val, N_COINS.checked_add(1)?)?
+        .checked_mul(U256::from(AMP_PRECISION))?
+        .checked_div(checked_u8_mul(&new_source_amount, N_COINS_SQUARED)?.checked_mul(leverage)?)?;
+
+    // b = sum' - (A*n**n - 1) * D / (A * n**n)
+    let b = new_source_amount.checked_add(
+        d_val
+            .checked_mul(U256::from(AMP_PRECISION))?
+            .checked_div(leverage)?,
+    )?;
+
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y_prev: U256;
+    let mut y = d_val;
+    for _ in 0..ITERATIONS {
+        y_prev = y;
+        y = (checked_u8_power(&y, 2)?.checked_add(c)?)
+            .checked_div(checked_u8_mul(&y, 2)?.checked_add(b)?.checked_sub(d_val)?)?;
+        if y == y_prev {
+            break;
+        }
+    }
+    u128::try_from(y).ok()
+}
+
+/// ## Description
+/// Computes the marginal price of the offer tokens in ask tokens (-dy/dx) from the derivative of the invariant.
+///
+/// * **Equation**
+///
+/// -dy/dx = (leverage + D**(n+1) / (n**n * x**2 * y)) / (leverage + D**(n+1) / (n**n * x * y**2))
+/// ## Params
+/// * **leverage** is an object of type [`u64`].
+///
+/// * **offer_pool** is an object of type [`u128`]. This is the amount of offer tokens (x) in the pool, valued at the price scale.
+///
+/// * **ask_pool** is an object of type [`u128`]. This is the amount of ask tokens (y) in the pool, valued at the price scale.
+pub fn compute_spot_price(leverage: u64, offer_pool: u128, ask_pool: u128) -> Option<Decimal> {
+    let d = U256::from(compute_d(leverage, offer_pool, ask_pool)?);
+    let x = U256::from(offer_pool);
+    let y = U256::from(ask_pool);
+    let one = U256::from(DECIMAL_FRACTIONAL);
+
+    // the invariant is computed with the leverage in place of A * n**n
+    let ann = U256::from(leverage).checked_mul(one)? / AMP_PRECISION;
+
+    // D**(n+1) / (n**n * a**2 * b), scaled by `one`
+    let partial = |a: U256, b: U256| -> Option<U256> {
+        d.checked_mul(d)?
+            .checked_div(a)?
+            .checked_mul(d)?
+            .checked_div(a)?
+            .checked_mul(one)?
+            .checked_div(checked_u8_mul(&b, N_COINS_SQUARED)?)
+    };
+
+    let numerator = ann.checked_add(partial(x, y)?)?;
+    let denominator = ann.checked_add(partial(y, x)?)?;
+
+    Decimal::checked_from_ratio(
+        u128::try_from(numerator).ok()?,
+        u128::try_from(denominator).ok()?,
+    )
+    .ok()
+}
+
+/// ## Description
+/// Returns self to the power of b.
+fn checked_u8_power(a: &U256, b: u8) -> Option<U256> {
+    let mut result = *a;
+    for _ in 1..b {
+        result = result.checked_mul(*a)?;
+    }
+    Some(result)
+}
+
+/// ## Description
+/// Returns self multiplied by b.
+fn checked_u8_mul(a: &U256, b: u8) -> Option<U256> {
+    let mut result = *a;
+    for _ in 1..b {
+        result = result.checked_add(*a)?;
+    }
+    Some(result)
+}
+
+/// ## Description
+/// Returns 0.5 to the power of `power`, which is the weight of the old price oracle
+/// after `power` half times.
+///
+/// * **Equation**
+///
+/// 0.5**power = 0.5**int(power) * (1 - 0.5)**frac(power), the second factor is expanded as a binomial series.
+pub fn halfpow(power: Decimal) -> Decimal {
+    let power = power.atomics().u128();
+    let intpow = power / DECIMAL_FRACTIONAL;
+    let otherpow = power - intpow * DECIMAL_FRACTIONAL;
+    if intpow > 59 {
+        return Decimal::zero();
+    }
+
+    let result = DECIMAL_FRACTIONAL / (1u128 << intpow);
+    if otherpow == 0 {
+        return Decimal::from_atomics(result, 18).unwrap_or_default();
+    }
+
+    let x = DECIMAL_FRACTIONAL / 2;
+    let mut term = DECIMAL_FRACTIONAL;
+    let mut sum = DECIMAL_FRACTIONAL;
+    let mut neg = false;
+
+    for i in 1..256u128 {
+        let k = i * DECIMAL_FRACTIONAL;
+        let mut c = k - DECIMAL_FRACTIONAL;
+        if otherpow > c {
+            c = otherpow - c;
+            neg = !neg;
+        } else {
+            c -= otherpow;
+        }
+        term = term * (c * x / DECIMAL_FRACTIONAL) / k;
+        if neg {
+            sum -= term;
+        } else {
+            sum += term;
+        }
+        if term < HALFPOW_TOLERANCE {
+            break;
+        }
+    }
+
+    Decimal::from_atomics(result * sum / DECIMAL_FRACTIONAL, 18).unwrap_or_default()
+}
+
+/// ## Description
+/// Computes the value of the pool balanced at `price_scale` (xcp), that is the geometric mean
+/// of the balances holding D / 2 of each asset.
+///
+/// * **Equation**
+///
+/// xcp = sqrt(D / 2 * D / (2 * price_scale))
+pub fn compute_xcp(d: Uint128, price_scale: Decimal) -> Option<Uint128> {
+    let half_d = U256::from(d.u128()) / 2;
+    let half_d_in_second_asset = half_d
+        .checked_mul(U256::from(DECIMAL_FRACTIONAL))?
+        .checked_div(U256::from(price_scale.atomics().u128()))?;
+
+    let xcp = half_d.checked_mul(half_d_in_second_asset)?.integer_sqrt();
+    u128::try_from(xcp).ok().map(Uint128::new)
+}
@@ -0,0 +1,55 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::Item;
+use novaswap::pair::PoolStatus;
+use novaswap::pairinfo::PairInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub pair_info: PairInfo,
+    pub factory_addr: Addr,
+
+    // used for calculatring TAWP.
+    pub block_time_last: u64,
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+
+    // pool amplification, multiplied by AMP_PRECISION.
+    pub amp: u64,
+
+    // swap fee charged by this pair, in basis points.
+    pub fee_bps: u16,
+
+    // used for repegging the price scale.
+    pub allowed_extra_profit: Decimal,
+    pub adjustment_step: Decimal,
+    pub ma_half_time: u64,
+
+    // operations allowed by the pool, pools stored before the status default to active.
+    #[serde(default)]
+    pub status: PoolStatus,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// the reserves of the pair, in the order of `pair_info.asset_infos`. Tokens sent to the pair
+// outside of its messages are not part of the reserves, they can be skimmed or synced.
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
+
+// price state of the pool, the prices are the prices of the second asset in the first asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceState {
+    // the balances of the second asset are multiplied by the price scale before entering the invariant.
+    pub price_scale: Decimal,
+    pub price_oracle: Decimal,
+    pub last_price: Decimal,
+    pub last_price_time: u64,
+
+    // used for gating repegs by the profit of liquidity providers.
+    pub xcp_profit: Decimal,
+    pub virtual_price: Decimal,
+    pub not_adjusted: bool,
+}
+
+pub const PRICE_STATE: Item<PriceState> = Item::new("price_state");
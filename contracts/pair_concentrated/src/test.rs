@@ -0,0 +1,687 @@
+use crate::{
+    contract::{
+        execute, instantiate, migrate, query_config, query_pair_info, query_pool,
+        query_reverse_simulation, query_simulation, reply,
+    },
+    error::ContractError,
+    math::{
+        calc_ask_amount, calc_offer_amount, compute_d, compute_xcp, halfpow, AMP_PRECISION, N_COINS,
+    },
+    mock_querier::{mock_dependencies, WasmMockQuerier},
+    state::{PriceState, CONFIG, PRICE_STATE, RESERVES},
+    utils::{compute_provide_fee_rate, update_price_oracle},
+};
+use cosmwasm_std::{
+    attr, from_binary,
+    testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, OwnedDeps, Reply, SubMsg,
+    SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use novaswap::{
+    asset::{Asset, AssetInfo},
+    pair::{
+        ConcentratedPoolConfig, ConcentratedPoolParams, ConcentratedPoolUpdateParams, Cw20HookMsg,
+        ExecuteMsg, InstantiateMsg, MigrateMsg,
+    },
+    pairinfo::PairType,
+};
+use prost::Message;
+use std::str::FromStr;
+
+#[derive(Clone, PartialEq, Message)]
+struct MsgInstantiateContractResponse {
+    #[prost(string, tag = "1")]
+    pub contract_address: ::prost::alloc::string::String,
+    #[prost(bytes, tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+    };
+
+    let mut encoded_instantiate_reply = Vec::<u8>::with_capacity(data.encoded_len());
+    data.encode(&mut encoded_instantiate_reply).unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(encoded_instantiate_reply.into()),
+        }),
+    };
+
+    reply(deps, mock_env(), reply_msg).unwrap();
+}
+
+// 페어를 거쳐 예치된 것처럼 reserve 를 저장한다.
+fn store_reserves(deps: DepsMut, reserves: [u128; 2]) {
+    RESERVES
+        .save(
+            deps.storage,
+            &[Uint128::new(reserves[0]), Uint128::new(reserves[1])],
+        )
+        .unwrap();
+}
+
+fn pair_asset_infos() -> [AssetInfo; 2] {
+    [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    ]
+}
+
+// asset0000 하나의 가격이 2 uusd 인 풀의 파라미터
+fn pool_params() -> ConcentratedPoolParams {
+    ConcentratedPoolParams {
+        amp: 10,
+        fee_bps: None,
+        initial_price_scale: Decimal::from_str("2").unwrap(),
+        allowed_extra_profit: Decimal::from_str("0.000002").unwrap(),
+        adjustment_step: Decimal::from_str("0.000146").unwrap(),
+        ma_half_time: 600,
+        lp_token_marketing: None,
+    }
+}
+
+fn instantiate_msg(init_params: Option<ConcentratedPoolParams>) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos: pair_asset_infos(),
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: init_params.map(|params| to_binary(&params).unwrap()),
+    }
+}
+
+// uusd 를 `native_amount`, asset0000 을 `token_amount` 만큼 가지고 있는 페어를 만든다.
+fn pair_with_liquidity(
+    native_amount: u128,
+    token_amount: u128,
+    total_share: u128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(native_amount),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(token_amount),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(total_share))],
+        ),
+    ]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(Some(pool_params())),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [native_amount, token_amount]);
+
+    deps
+}
+
+fn provide_msg(native_amount: u128, token_amount: u128) -> ExecuteMsg {
+    ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: pair_asset_infos()[0].clone(),
+                amount: Uint128::new(native_amount),
+            },
+            Asset {
+                info: pair_asset_infos()[1].clone(),
+                amount: Uint128::new(token_amount),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    }
+}
+
+fn swap_token_msg(amount: u128) -> ExecuteMsg {
+    ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(amount),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    })
+}
+
+fn pool_config(deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) -> ConcentratedPoolConfig {
+    from_binary(
+        &query_config(deps.as_ref(), mock_env())
+            .unwrap()
+            .params
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+/// 초기 가격으로 페어를 생성하고, 잘못된 파라미터는 거부한다.
+#[test]
+fn proper_initialization() {
+    let deps = pair_with_liquidity(0, 0, 0);
+
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Concentrated {});
+
+    let config = pool_config(&deps);
+    assert_eq!(config.amp, Decimal::from_ratio(10u128, 1u128));
+    assert_eq!(config.fee_bps, 30);
+    assert_eq!(config.price_scale, Decimal::from_str("2").unwrap());
+    assert_eq!(config.price_oracle, Decimal::from_str("2").unwrap());
+    assert_eq!(config.xcp_profit, Decimal::one());
+
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InitParamsNotFound {});
+
+    let mut params = pool_params();
+    params.amp = 0;
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(Some(params)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectAmp {});
+
+    let mut params = pool_params();
+    params.initial_price_scale = Decimal::zero();
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(Some(params)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectPriceScale {});
+
+    let mut params = pool_params();
+    params.ma_half_time = 0;
+    let err = instantiate(deps.as_mut(), env, info, instantiate_msg(Some(params))).unwrap_err();
+    assert_eq!(err, ContractError::IncorrectRepegParams {});
+}
+
+/// 첫 유동성 공급자는 가격 기준으로 균형 잡힌 풀의 가치(xcp)만큼 LP 를 받고,
+/// 이후 가격 기준에서 벗어난 공급에는 수수료가 붙는다.
+#[test]
+fn provide_liquidity() {
+    // 200 uusd and 100 asset0000 are balanced at the price scale of 2
+    let mut deps = pair_with_liquidity(200_000000, 0, 0);
+    store_reserves(deps.as_mut(), [0, 0]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(200_000000),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(200_000000, 100_000000),
+    )
+    .unwrap();
+
+    // sqrt(200 * 100)
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0000"),
+                amount: Uint128::new(141_421356),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // a balanced deposit mints a proportional share without fees
+    let mut deps = pair_with_liquidity(400_000000, 100_000000, 141_421356);
+    store_reserves(deps.as_mut(), [200_000000, 100_000000]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(200_000000),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(200_000000, 100_000000),
+    )
+    .unwrap();
+    let share = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "share")
+        .unwrap();
+    assert_eq!(share.value, "141421356");
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(400_000000), Uint128::new(200_000000)]
+    );
+
+    // a single sided deposit pays the fee and respects the slippage tolerance
+    let mut deps = pair_with_liquidity(400_000000, 100_000000, 141_421356);
+    store_reserves(deps.as_mut(), [200_000000, 100_000000]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(200_000000),
+        }],
+    );
+    let mut msg = provide_msg(200_000000, 0);
+    if let ExecuteMsg::ProvideLiquidity {
+        slippage_tolerance, ..
+    } = &mut msg
+    {
+        *slippage_tolerance = Some(Decimal::percent(1));
+    }
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::MaxSlippageAssertion {});
+
+    assert_eq!(
+        compute_provide_fee_rate(
+            Decimal::permille(3),
+            [Uint128::new(200_000000), Uint128::zero()]
+        ),
+        Decimal::permille(3) * Decimal::percent(50)
+    );
+    assert_eq!(
+        compute_provide_fee_rate(
+            Decimal::permille(3),
+            [Uint128::new(200_000000), Uint128::new(200_000000)]
+        ),
+        Decimal::zero()
+    );
+}
+
+/// 가격 기준 근처에서는 asset0000 하나가 약 2 uusd 로 교환되고, 시뮬레이션과 결과가 같다.
+#[test]
+fn swap_around_price_scale() {
+    // the pool holds the offered 1 asset0000 already
+    let mut deps = pair_with_liquidity(2000_000000, 1001_000000, 1414_213562);
+    store_reserves(deps.as_mut(), [2000_000000, 1000_000000]);
+    deps.querier.with_fee_info(Some("fee_address"), 5000);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        swap_token_msg(1_000000),
+    )
+    .unwrap();
+    let return_amount: u128 = res.attributes[6].value.parse().unwrap();
+    let commission_amount: u128 = res.attributes[8].value.parse().unwrap();
+    let maker_fee_amount: u128 = res.attributes[9].value.parse().unwrap();
+    assert_eq!(maker_fee_amount, commission_amount / 2);
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            Uint128::new(2000_000000 - return_amount - maker_fee_amount),
+            Uint128::new(1001_000000)
+        ]
+    );
+
+    // 1 asset0000 is worth 2 uusd at the price scale, minus the 0.3% fee
+    assert!((1_990000..2_000000).contains(&(return_amount + commission_amount)));
+    assert_eq!(
+        commission_amount,
+        (return_amount + commission_amount) * 3 / 1000
+    );
+
+    // the traded price is recorded for the oracle
+    let config = pool_config(&deps);
+    assert!(config.last_price > Decimal::from_str("1.99").unwrap());
+    assert!(config.last_price < Decimal::from_str("2.01").unwrap());
+
+    let deps = pair_with_liquidity(2000_000000, 1000_000000, 1414_213562);
+    let simulation = query_simulation(
+        deps.as_ref(),
+        Asset {
+            info: pair_asset_infos()[1].clone(),
+            amount: Uint128::new(1_000000),
+        },
+    )
+    .unwrap();
+    assert_eq!(simulation.return_amount.u128(), return_amount);
+
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: pair_asset_infos()[0].clone(),
+            amount: simulation.return_amount,
+        },
+    )
+    .unwrap();
+    assert!((999000..=1_001000).contains(&reverse_simulation.offer_amount.u128()));
+}
+
+// 가상 가격이 1.01 인 풀에서 오라클이 2.2 를 가리키도록 상태를 설정한다.
+fn pair_with_profit(xcp_profit: &str) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    // xcp is 141.421356, the supply makes the virtual price 1.01
+    let mut deps = pair_with_liquidity(200_000000, 100_000000 + 10000, 140_021144);
+    store_reserves(deps.as_mut(), [200_000000, 100_000000]);
+    PRICE_STATE
+        .save(
+            deps.as_mut().storage,
+            &PriceState {
+                price_scale: Decimal::from_str("2").unwrap(),
+                price_oracle: Decimal::from_str("2.2").unwrap(),
+                last_price: Decimal::from_str("2.2").unwrap(),
+                last_price_time: mock_env().block.time.seconds(),
+                xcp_profit: Decimal::from_str(xcp_profit).unwrap(),
+                virtual_price: Decimal::from_str("1.01").unwrap(),
+                not_adjusted: false,
+            },
+        )
+        .unwrap();
+
+    deps
+}
+
+/// 수익이 충분하면 가격 기준을 오라클 쪽으로 옮기고, 수익의 절반 이상을 쓰게 되면 옮기지 않는다.
+#[test]
+fn repeg_is_gated_by_profit() {
+    let mut deps = pair_with_profit("1.01");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        swap_token_msg(10000),
+    )
+    .unwrap();
+
+    // norm = 0.1, the step is norm / 5, so the price scale moves to 2.04
+    let config = pool_config(&deps);
+    assert!(config.price_scale > Decimal::from_str("2.039").unwrap());
+    assert!(config.price_scale < Decimal::from_str("2.041").unwrap());
+    assert!(config.virtual_price > Decimal::one());
+
+    // the profit already spent on earlier repegs blocks this one
+    let mut deps = pair_with_profit("1.05");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        swap_token_msg(10000),
+    )
+    .unwrap();
+
+    let config = pool_config(&deps);
+    assert_eq!(config.price_scale, Decimal::from_str("2").unwrap());
+}
+
+/// 오라클은 ma_half_time 이 지날 때마다 마지막 거래 가격과의 차이를 절반으로 줄인다.
+#[test]
+fn price_oracle_moving_average() {
+    let deps = pair_with_liquidity(0, 0, 0);
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let mut state = PRICE_STATE.load(deps.as_ref().storage).unwrap();
+    state.last_price = Decimal::from_str("3").unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(config.ma_half_time);
+    update_price_oracle(
+        &config,
+        &mut state,
+        &env,
+        Some(Decimal::from_str("10").unwrap()),
+    );
+
+    // (3 + 2) / 2, the new price is capped at twice the price scale
+    assert_eq!(state.price_oracle, Decimal::from_str("2.5").unwrap());
+    assert_eq!(state.last_price, Decimal::from_str("4").unwrap());
+
+    // the oracle is not moved twice in the same block
+    update_price_oracle(&config, &mut state, &env, None);
+    assert_eq!(state.price_oracle, Decimal::from_str("2.5").unwrap());
+}
+
+/// 관리자만 수수료와 리페그 파라미터를 바꿀 수 있다.
+#[test]
+fn update_config() {
+    let mut deps = pair_with_liquidity(0, 0, 0);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&ConcentratedPoolUpdateParams::UpdateRepegParams {
+            allowed_extra_profit: None,
+            adjustment_step: Some(Decimal::percent(1)),
+            ma_half_time: Some(3600),
+        })
+        .unwrap(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let config = pool_config(&deps);
+    assert_eq!(config.adjustment_step, Decimal::percent(1));
+    assert_eq!(config.ma_half_time, 3600);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&ConcentratedPoolUpdateParams::UpdateFee { fee_bps: 1001 }).unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectFee {});
+}
+
+/// 풀에 직접 보낸 토큰은 skim 으로 꺼내거나, 팩토리 owner 의 sync 로 리저브에 반영한다.
+#[test]
+fn skim_and_sync() {
+    let mut deps = pair_with_liquidity(2000_000000, 1000_000000, 1414_213562);
+    // 100 uusd 가 리저브에 반영되지 않은 채로 풀에 들어와 있다
+    store_reserves(deps.as_mut(), [1900_000000, 1000_000000]);
+
+    let res = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::new(1900_000000));
+
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000000),
+            }],
+        }))]
+    );
+    // skim 은 리저브를 바꾸지 않는다
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1900_000000), Uint128::new(1000_000000)]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr("reserves", "2000000000uusd, 1000000000asset0000"),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(2000_000000), Uint128::new(1000_000000)]
+    );
+
+    // 리저브와 잔고가 같으면 skim 할 것이 없다
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert!(res.messages.is_empty());
+}
+
+/// 리저브를 저장하기 전에 생성된 페어는 마이그레이션할 때 잔고로 리저브를 채운다.
+#[test]
+fn migrate_seeds_reserves() {
+    let mut deps = pair_with_liquidity(2000_000000, 1000_000000, 1414_213562);
+    RESERVES.remove(deps.as_mut().storage);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(2000_000000), Uint128::new(1000_000000)]
+    );
+
+    // 이미 저장된 리저브는 그대로 둔다
+    store_reserves(deps.as_mut(), [1900_000000, 1000_000000]);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1900_000000), Uint128::new(1000_000000)]
+    );
+}
+
+/// 0.5 의 거듭제곱과 가격 기준으로 평가한 불변량 계산 확인
+#[test]
+fn concentrated_math() {
+    assert_eq!(halfpow(Decimal::zero()), Decimal::one());
+    assert_eq!(halfpow(Decimal::one()), Decimal::percent(50));
+    assert_eq!(halfpow(Decimal::from_ratio(60u128, 1u128)), Decimal::zero());
+
+    // 0.5 ** 0.5 = 0.70710678..
+    let sqrt_half = halfpow(Decimal::percent(50));
+    assert!(sqrt_half > Decimal::from_str("0.7071067").unwrap());
+    assert!(sqrt_half < Decimal::from_str("0.7071068").unwrap());
+
+    // 0.5 ** 2.5 = 0.17677669..
+    let value = halfpow(Decimal::from_str("2.5").unwrap());
+    assert!(value > Decimal::from_str("0.1767766").unwrap());
+    assert!(value < Decimal::from_str("0.1767767").unwrap());
+
+    // 200 uusd and 100 asset0000 valued at the price scale of 2 are balanced
+    let leverage = 10 * AMP_PRECISION * u64::from(N_COINS);
+    let d = compute_d(leverage, 200_000000, 200_000000).unwrap();
+    assert_eq!(d, 400_000000);
+    assert_eq!(
+        compute_xcp(Uint128::new(d), Decimal::from_str("2").unwrap()).unwrap(),
+        Uint128::new(141_421356)
+    );
+
+    // the invariant is flatter than the constant product around the price scale
+    let imbalanced_d = compute_d(leverage, 300_000000, 100_000000).unwrap();
+    assert!(imbalanced_d < 400_000000);
+    assert!(imbalanced_d > 2 * 173_205080);
+}
+
+/// 풀보다 많은 양을 사려고 하거나 계산이 넘치면 패닉하지 않고 None 을 돌려준다.
+#[test]
+fn concentrated_math_insufficient_pool() {
+    let amp = 10 * AMP_PRECISION;
+    assert_eq!(
+        calc_offer_amount(200_000000, 200_000000, 200_000000, amp),
+        None
+    );
+    assert_eq!(
+        calc_offer_amount(200_000000, 200_000000, 300_000000, amp),
+        None
+    );
+    assert!(calc_offer_amount(200_000000, 200_000000, 100_000000, amp).is_some());
+
+    assert_eq!(calc_ask_amount(u128::MAX, 200_000000, 1, amp), None);
+    assert_eq!(calc_ask_amount(200_000000, 200_000000, 1, u64::MAX), None);
+}
+
+/// 다른 컨트랙트나 하위 버전으로의 마이그레이션은 막고, 마이그레이션하면 버전을 올린다.
+#[test]
+fn migrate_contract_version() {
+    let mut deps = pair_with_liquidity(2000_000000, 1000_000000, 1414_213562);
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-xyk", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-concentrated", "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-concentrated", "0.9.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", "0.9.0"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
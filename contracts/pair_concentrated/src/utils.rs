@@ -0,0 +1,625 @@
+use crate::error::ContractError;
+use crate::math::{
+    calc_ask_amount, calc_offer_amount, compute_d, compute_xcp, halfpow, MAX_MA_HALF_TIME, N_COINS,
+};
+use crate::state::{Config, PriceState, CONFIG, RESERVES};
+use cosmwasm_std::{
+    to_binary, Addr, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut, Env, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_utils::Expiration;
+use novaswap::asset::Asset;
+use novaswap::factory::FeeInfoResponse;
+use novaswap::pair::{PoolStatus, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION};
+use novaswap::querier::query_supply;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+pub const DEFAULT_FEE_BPS: u16 = 30;
+pub const MAX_FEE_BPS: u16 = 1000;
+const FEE_DENOMINATOR: u128 = 10000;
+
+// Returns the fee rate of the pair
+pub fn compute_fee_rate(config: &Config) -> Decimal {
+    Decimal::from_ratio(Uint128::from(config.fee_bps), Uint128::new(FEE_DENOMINATOR))
+}
+
+// Returns the part of the commission which goes to the factory fee address.
+// Nothing is taken when the fee address is not set.
+pub fn compute_maker_fee(fee_info: &FeeInfoResponse, commission_amount: Uint128) -> Uint128 {
+    if fee_info.fee_address.is_some() {
+        commission_amount * fee_info.maker_fee_rate
+    } else {
+        Uint128::zero()
+    }
+}
+
+// Returns the share of LP tokens charged on a deposit, growing with how far the deposit
+// is from the price scale.
+// fee * n / (4 * (n - 1)) * sum(|x_i - avg|) / sum(x_i)
+pub fn compute_provide_fee_rate(fee_rate: Decimal, deposits: [Uint128; 2]) -> Decimal {
+    let n_coins = u128::from(N_COINS);
+    let sum = deposits[0] + deposits[1];
+    if sum.is_zero() {
+        return Decimal::zero();
+    }
+
+    let avg = sum / Uint128::from(n_coins);
+    let diff = deposits
+        .iter()
+        .fold(Uint128::zero(), |acc, x| acc + (x.max(&avg) - x.min(&avg)));
+
+    fee_rate * Decimal::from_ratio(n_coins, 4 * (n_coins - 1)) * Decimal::from_ratio(diff, sum)
+}
+
+// Returns the amplification multiplied by the number of coins, as used by the invariant.
+pub fn compute_leverage(amp: u64) -> StdResult<u64> {
+    amp.checked_mul(u64::from(N_COINS))
+        .ok_or_else(|| StdError::generic_err("Amplification overflow"))
+}
+
+// Returns the assets valued at the price scale in the greater precision of the pair assets.
+pub fn compute_xp(
+    amounts: [Uint128; 2],
+    precisions: [u8; 2],
+    price_scale: Decimal,
+) -> StdResult<[Uint128; 2]> {
+    let greater_precision = precisions[0].max(precisions[1]);
+
+    Ok([
+        adjust_precision(amounts[0], precisions[0], greater_precision)?,
+        apply_price(
+            adjust_precision(amounts[1], precisions[1], greater_precision)?,
+            price_scale,
+        )?,
+    ])
+}
+
+// Returns an amount of offer assets for a specified amount of ask assets.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_offer_amount(
+    offer_pool: Uint128,
+    offer_precision: u8,
+    offer_price: Decimal,
+    ask_pool: Uint128,
+    ask_precision: u8,
+    ask_price: Decimal,
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // ask => offer
+
+    let greater_precision = offer_precision.max(ask_precision);
+    let offer_pool = apply_price(
+        adjust_precision(offer_pool, offer_precision, greater_precision)?,
+        offer_price,
+    )?;
+    let ask_pool = apply_price(
+        adjust_precision(ask_pool, ask_precision, greater_precision)?,
+        ask_price,
+    )?;
+    let ask_amount = apply_price(
+        adjust_precision(ask_amount, ask_precision, greater_precision)?,
+        ask_price,
+    )?;
+
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let inv_one_minus_commission: Decimal = Decimal::one() / one_minus_commission;
+    let before_commission_deduction = ask_amount * inv_one_minus_commission;
+
+    if before_commission_deduction >= ask_pool {
+        return Err(StdError::generic_err(
+            "Not enough liquidity in the ask pool",
+        ));
+    }
+
+    let offer_amount = Uint128::new(
+        calc_offer_amount(
+            offer_pool.u128(),
+            ask_pool.u128(),
+            before_commission_deduction.u128(),
+            amp,
+        )
+        .ok_or_else(|| StdError::generic_err("Failed to compute the offer amount"))?,
+    );
+
+    // The assets are exchanged 1:1 at the price scale, any exchange rate < 1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+
+    let commission_amount = before_commission_deduction * commission_rate;
+
+    let offer_amount = adjust_precision(
+        remove_price(offer_amount, offer_price)?,
+        greater_precision,
+        offer_precision,
+    )?;
+    let spread_amount = adjust_precision(
+        remove_price(spread_amount, ask_price)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let commission_amount = adjust_precision(
+        remove_price(commission_amount, ask_price)?,
+        greater_precision,
+        ask_precision,
+    )?;
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
+/// compute swap then returns return_amount, spread_amount and commission_amount.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap(
+    offer_pool: Uint128,
+    offer_precision: u8,
+    offer_price: Decimal,
+    ask_pool: Uint128,
+    ask_precision: u8,
+    ask_price: Decimal,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // offer => ask
+
+    let greater_precision = offer_precision.max(ask_precision);
+    let offer_pool = apply_price(
+        adjust_precision(offer_pool, offer_precision, greater_precision)?,
+        offer_price,
+    )?;
+    let ask_pool = apply_price(
+        adjust_precision(ask_pool, ask_precision, greater_precision)?,
+        ask_price,
+    )?;
+    let offer_amount = apply_price(
+        adjust_precision(offer_amount, offer_precision, greater_precision)?,
+        offer_price,
+    )?;
+
+    let return_amount = Uint128::new(
+        calc_ask_amount(offer_pool.u128(), ask_pool.u128(), offer_amount.u128(), amp)
+            .ok_or_else(|| StdError::generic_err("Failed to compute the return amount"))?,
+    );
+
+    // The assets are exchanged 1:1 at the price scale, so any exchange rate <1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+
+    let commission_amount: Uint128 = return_amount * commission_rate;
+
+    // The commission will be absorbed by the pool
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount)?;
+
+    let return_amount = adjust_precision(
+        remove_price(return_amount, ask_price)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let spread_amount = adjust_precision(
+        remove_price(spread_amount, ask_price)?,
+        greater_precision,
+        ask_precision,
+    )?;
+    let commission_amount = adjust_precision(
+        remove_price(commission_amount, ask_price)?,
+        greater_precision,
+        ask_precision,
+    )?;
+
+    Ok((return_amount, spread_amount, commission_amount))
+}
+
+// Moves the price oracle towards the last traded price, weighted by the time since the last trade,
+// then records the price of this trade. The price of the current trade only enters the oracle
+// in a later block, so it can not be moved within a single transaction.
+pub fn update_price_oracle(
+    config: &Config,
+    state: &mut PriceState,
+    env: &Env,
+    last_price: Option<Decimal>,
+) {
+    let block_time = env.block.time.seconds();
+    if state.last_price_time < block_time {
+        // alpha = 0.5 ** (elapsed / ma_half_time)
+        let alpha = halfpow(Decimal::from_ratio(
+            block_time - state.last_price_time,
+            config.ma_half_time,
+        ));
+        state.price_oracle =
+            state.last_price * (Decimal::one() - alpha) + state.price_oracle * alpha;
+        state.last_price_time = block_time;
+    }
+
+    if let Some(last_price) = last_price {
+        // a single trade can not move the recorded price more than twice the price scale
+        state.last_price = last_price.min(state.price_scale + state.price_scale);
+    }
+}
+
+// Updates the virtual price and the profit of the pool, then repegs the price scale towards
+// the price oracle when it keeps at least half of the profit for the liquidity providers.
+// `xp` are the balances valued at the current price scale and `total_supply` is the amount of
+// LP tokens in the same precision.
+pub fn tweak_price(
+    config: &Config,
+    state: &mut PriceState,
+    xp: [Uint128; 2],
+    total_supply: Uint128,
+) -> StdResult<()> {
+    if total_supply.is_zero() {
+        return Ok(());
+    }
+
+    let leverage = compute_leverage(config.amp)?;
+    let d = compute_d(leverage, xp[0].u128(), xp[1].u128())
+        .ok_or_else(|| StdError::generic_err("Failed to compute the invariant"))?;
+    let virtual_price = compute_virtual_price(Uint128::new(d), state.price_scale, total_supply)?;
+
+    // the profit grows with the virtual price, the losses of repegging are paid from it.
+    if !state.virtual_price.is_zero() {
+        state.xcp_profit = state.xcp_profit * virtual_price / state.virtual_price;
+    }
+
+    // norm = |price_oracle / price_scale - 1|
+    let ratio = state.price_oracle / state.price_scale;
+    let norm = if ratio > Decimal::one() {
+        ratio - Decimal::one()
+    } else {
+        Decimal::one() - ratio
+    };
+    let adjustment_step = config
+        .adjustment_step
+        .max(norm * Decimal::from_ratio(1u128, 5u128));
+
+    // virtual_price - 1 > (xcp_profit - 1) / 2 + allowed_extra_profit
+    if !state.not_adjusted
+        && virtual_price > Decimal::one()
+        && state.xcp_profit >= Decimal::one()
+        && virtual_price - Decimal::one()
+            > (state.xcp_profit - Decimal::one()) * Decimal::percent(50)
+                + config.allowed_extra_profit
+    {
+        state.not_adjusted = true;
+    }
+
+    if state.not_adjusted && norm > adjustment_step && virtual_price > Decimal::one() {
+        let new_price_scale = (state.price_scale * (norm - adjustment_step)
+            + state.price_oracle * adjustment_step)
+            / norm;
+
+        let new_xp = [
+            xp[0],
+            xp[1].multiply_ratio(new_price_scale.atomics(), state.price_scale.atomics()),
+        ];
+        let new_d = compute_d(leverage, new_xp[0].u128(), new_xp[1].u128())
+            .ok_or_else(|| StdError::generic_err("Failed to compute the invariant"))?;
+        let new_virtual_price =
+            compute_virtual_price(Uint128::new(new_d), new_price_scale, total_supply)?;
+
+        // repeg only when the providers keep at least half of the profit
+        if new_virtual_price > Decimal::one()
+            && new_virtual_price + new_virtual_price - Decimal::one() > state.xcp_profit
+        {
+            state.price_scale = new_price_scale;
+            state.virtual_price = new_virtual_price;
+            return Ok(());
+        }
+
+        state.not_adjusted = false;
+    }
+
+    state.virtual_price = virtual_price;
+
+    Ok(())
+}
+
+// Returns the value of one LP token in the pool balanced at the price scale.
+pub fn compute_virtual_price(
+    d: Uint128,
+    price_scale: Decimal,
+    total_supply: Uint128,
+) -> StdResult<Decimal> {
+    let xcp = compute_xcp(d, price_scale)
+        .ok_or_else(|| StdError::generic_err("Failed to compute the virtual price"))?;
+
+    Decimal::checked_from_ratio(xcp, total_supply)
+        .map_err(|_| StdError::generic_err("Failed to compute the virtual price"))
+}
+
+// Returns a [`ContractError`] when the deadline of the message has passed at the current block.
+pub fn assert_deadline(
+    block: &BlockInfo,
+    deadline: Option<Expiration>,
+) -> Result<(), ContractError> {
+    match deadline {
+        Some(deadline) if deadline.is_expired(block) => Err(ContractError::Expired {}),
+        _ => Ok(()),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow swaps.
+pub fn assert_swaps_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active => Ok(()),
+        PoolStatus::SwapsPaused => Err(ContractError::SwapsPaused {}),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow deposits.
+pub fn assert_deposits_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active | PoolStatus::SwapsPaused => Ok(()),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Updates the operations allowed by the pool.
+pub fn update_status(
+    mut config: Config,
+    deps: DepsMut,
+    status: PoolStatus,
+) -> Result<(), ContractError> {
+    config.status = status;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] on failure.
+// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+// otherwise we just use the swap spread to check `max_spread`.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount * (Decimal::one() / belief_price);
+        let spread_amount = expected_return
+            .checked_sub(return_amount)
+            .unwrap_or_else(|_| Uint128::zero());
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] on failure.
+// `expected_share` is the amount of LP tokens minted for the deposit valued at the price scale,
+// `share` may fall short of it by at most `slippage_tolerance`.
+pub fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    share: Uint128,
+    expected_share: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(slippage_tolerance) = slippage_tolerance {
+        if slippage_tolerance > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+            return Err(ContractError::AllowedSlippageAssertion {});
+        }
+
+        if share < expected_share * (Decimal::one() - slippage_tolerance) {
+            return Err(ContractError::MaxSlippageAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] when the repeg parameters are out of range.
+pub fn validate_repeg_params(
+    allowed_extra_profit: Decimal,
+    adjustment_step: Decimal,
+    ma_half_time: u64,
+) -> Result<(), ContractError> {
+    if allowed_extra_profit >= Decimal::one()
+        || adjustment_step >= Decimal::one()
+        || ma_half_time == 0
+        || ma_half_time > MAX_MA_HALF_TIME
+    {
+        return Err(ContractError::IncorrectRepegParams {});
+    }
+
+    Ok(())
+}
+
+// Update the swap fee of the pair. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn update_fee(mut config: Config, deps: DepsMut, fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
+    config.fee_bps = fee_bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Update the repeg parameters of the pair. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn update_repeg_params(
+    mut config: Config,
+    deps: DepsMut,
+    allowed_extra_profit: Option<Decimal>,
+    adjustment_step: Option<Decimal>,
+    ma_half_time: Option<u64>,
+) -> Result<(), ContractError> {
+    config.allowed_extra_profit = allowed_extra_profit.unwrap_or(config.allowed_extra_profit);
+    config.adjustment_step = adjustment_step.unwrap_or(config.adjustment_step);
+    config.ma_half_time = ma_half_time.unwrap_or(config.ma_half_time);
+    validate_repeg_params(
+        config.allowed_extra_profit,
+        config.adjustment_step,
+        config.ma_half_time,
+    )?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// adjust given value from `current_precision` to `new_precision`
+pub fn adjust_precision(
+    value: Uint128,
+    current_precision: u8,
+    new_precision: u8,
+) -> StdResult<Uint128> {
+    Ok(match current_precision.cmp(&new_precision) {
+        Ordering::Equal => value,
+        Ordering::Less => value.checked_mul(Uint128::new(
+            // value = value * 10^(new - curr)
+            10_u128.pow((new_precision - current_precision) as u32),
+        ))?,
+        Ordering::Greater => value.checked_div(Uint128::new(
+            // value = value / 10^(curr - new)
+            10_u128.pow((current_precision - new_precision) as u32),
+        ))?,
+    })
+}
+
+// Returns the value of `amount` in the first asset at `price`.
+pub fn apply_price(amount: Uint128, price: Decimal) -> StdResult<Uint128> {
+    amount
+        .checked_multiply_ratio(price.atomics(), Decimal::one().atomics())
+        .map_err(|_| StdError::generic_err("Failed to apply the price scale"))
+}
+
+// Returns the amount of an asset which is worth `value` of the first asset at `price`.
+pub fn remove_price(value: Uint128, price: Decimal) -> StdResult<Uint128> {
+    value
+        .checked_multiply_ratio(Decimal::one().atomics(), price.atomics())
+        .map_err(|_| StdError::generic_err("Failed to remove the price scale"))
+}
+
+// Mint LP tokens for a beneficiary
+pub fn mint_liquidity_token_message(
+    config: &Config,
+    recipient: Addr,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    let lp_token = config.pair_info.liquidity_token.clone();
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: lp_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+// calculate accumulate prices
+pub fn accumulate_prices(
+    env: Env,
+    config: &Config,
+    x: Uint128,
+    x_precision: u8,
+    y: Uint128,
+    y_precision: u8,
+    price_scale: Decimal,
+) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+    let block_time = env.block.time.seconds();
+    if block_time <= config.block_time_last {
+        return Ok(None);
+    }
+
+    let greater_precision = x_precision.max(y_precision).max(TWAP_PRECISION);
+    let x = adjust_precision(x, x_precision, greater_precision)?;
+    let y = apply_price(
+        adjust_precision(y, y_precision, greater_precision)?,
+        price_scale,
+    )?;
+    let one = adjust_precision(Uint128::new(1), 0, greater_precision)?;
+
+    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+
+    let mut pcl0 = config.price0_cumulative_last;
+    let mut pcl1 = config.price1_cumulative_last;
+
+    if !x.is_zero() && !y.is_zero() {
+        let price0 = calc_ask_amount(x.u128(), y.u128(), one.u128(), config.amp)
+            .ok_or_else(|| StdError::generic_err("Failed to compute the price"))?;
+        let price1 = calc_ask_amount(
+            y.u128(),
+            x.u128(),
+            apply_price(one, price_scale)?.u128(),
+            config.amp,
+        )
+        .ok_or_else(|| StdError::generic_err("Failed to compute the price"))?;
+
+        pcl0 = config.price0_cumulative_last.wrapping_add(adjust_precision(
+            time_elapsed.checked_mul(remove_price(Uint128::new(price0), price_scale)?)?,
+            greater_precision,
+            TWAP_PRECISION,
+        )?);
+        pcl1 = config.price1_cumulative_last.wrapping_add(adjust_precision(
+            time_elapsed.checked_mul(Uint128::new(price1))?,
+            greater_precision,
+            TWAP_PRECISION,
+        )?);
+    };
+
+    Ok(Some((pcl0, pcl1, block_time)))
+}
+
+pub fn get_share_in_assets(
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+) -> [Asset; 2] {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    [
+        Asset {
+            info: pools[0].info.clone(),
+            amount: pools[0].amount * share_ratio,
+        },
+        Asset {
+            info: pools[1].info.clone(),
+            amount: pools[1].amount * share_ratio,
+        },
+    ]
+}
+
+pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
+    let pools = load_reserves(deps.storage, &config)?;
+    let total_supply: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
+
+    Ok((pools, total_supply))
+}
+
+// Returns the tracked reserves of the pair, balances above them are not counted.
+pub fn load_reserves(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
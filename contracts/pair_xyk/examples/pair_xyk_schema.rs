@@ -0,0 +1,61 @@
+extern crate cosmwasm_schema;
+
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema_with_title, remove_schemas, schema_for};
+
+use novaswap::pair::{
+    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    PoolResponse, QueryMsg, ReverseSimulationResponse, SimulateWithdrawResponse,
+    SimulationResponse, SpotPriceResponse, VirtualPriceResponse, XykPoolConfig, XykPoolParams,
+};
+use novaswap::pairinfo::PairInfo;
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema_with_title(&schema_for!(InstantiateMsg), &out_dir, "InstantiateMsg");
+    export_schema_with_title(&schema_for!(XykPoolParams), &out_dir, "XykPoolParams");
+    export_schema_with_title(&schema_for!(ExecuteMsg), &out_dir, "ExecuteMsg");
+    export_schema_with_title(&schema_for!(Cw20HookMsg), &out_dir, "Cw20HookMsg");
+    export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
+    export_schema_with_title(&schema_for!(PairInfo), &out_dir, "PairInfo");
+    export_schema_with_title(&schema_for!(PoolResponse), &out_dir, "PoolResponse");
+    export_schema_with_title(&schema_for!(ConfigResponse), &out_dir, "ConfigResponse");
+    export_schema_with_title(&schema_for!(XykPoolConfig), &out_dir, "XykPoolConfig");
+    export_schema_with_title(
+        &schema_for!(ReverseSimulationResponse),
+        &out_dir,
+        "ReverseSimulationResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SimulationResponse),
+        &out_dir,
+        "SimulationResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SimulateWithdrawResponse),
+        &out_dir,
+        "SimulateWithdrawResponse",
+    );
+    export_schema_with_title(&schema_for!(MigrateMsg), &out_dir, "MigrateMsg");
+    export_schema_with_title(
+        &schema_for!(CumulativePricesResponse),
+        &out_dir,
+        "CumulativePricesResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(VirtualPriceResponse),
+        &out_dir,
+        "VirtualPriceResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SpotPriceResponse),
+        &out_dir,
+        "SpotPriceResponse",
+    );
+}
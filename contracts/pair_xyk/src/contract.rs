@@ -0,0 +1,1026 @@
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use novaswap::asset::{format_lp_token_name, format_lp_token_symbol, Asset, AssetInfo};
+use novaswap::pair::{
+    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    PoolResponse, PoolStatus, PoolStatusUpdateParams, QueryMsg, ReverseSimulationResponse,
+    SimulateWithdrawResponse, SimulationResponse, SpotPriceResponse, VirtualPriceResponse,
+    XykPoolConfig, XykPoolParams,
+};
+
+use novaswap::pairinfo::{PairInfo, PairType};
+use novaswap::token::InstantiateMsg as TokenInstantiateMsg;
+use semver::Version;
+
+use crate::error::ContractError;
+use crate::state::{Config, CONFIG, RESERVES};
+
+use crate::utils::{
+    accumulate_prices, adjust_precision, assert_deadline, assert_deposits_allowed,
+    assert_max_spread, assert_slippage_tolerance, assert_swaps_allowed, compute_fee_rate,
+    compute_maker_fee, compute_offer_amount, compute_swap, get_share_in_assets, load_reserves,
+    mint_liquidity_token_message, pool_info, update_status, DEFAULT_FEE_BPS, MAX_FEE_BPS,
+    MINIMUM_LIQUIDITY_AMOUNT,
+};
+use novaswap::querier::{
+    query_factory_config, query_fee_info, query_supply, query_token_precision,
+};
+use novaswap::U256;
+
+const CONTRACT_NAME: &str = "novaswap-pair-xyk";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+
+// instantiate new novaswap constant product pair contract.
+// this will mint new token represents LP shares.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    msg.asset_infos[0].check(deps.api)?;
+    msg.asset_infos[1].check(deps.api)?;
+
+    if msg.asset_infos[0] == msg.asset_infos[1] {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    // init params are optional for constant product pairs.
+    let (fee_bps, lp_token_marketing) = match msg.init_params {
+        Some(init_params) => {
+            let params: XykPoolParams = from_binary(&init_params)?;
+            (params.fee_bps, params.lp_token_marketing)
+        }
+        None => (None, None),
+    };
+    let fee_bps = fee_bps.unwrap_or(DEFAULT_FEE_BPS);
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // set config
+    let config = Config {
+        pair_info: PairInfo {
+            contract_addr: env.contract.address.clone(),
+            liquidity_token: Addr::unchecked(""),
+            asset_infos: msg.asset_infos.clone(),
+            pair_type: PairType::Xyk {},
+        },
+        factory_addr: deps.api.addr_validate(msg.factory_addr.as_str())?,
+        block_time_last: 0,
+        price0_cumulative_last: Uint128::zero(),
+        price1_cumulative_last: Uint128::zero(),
+        fee_bps,
+        status: PoolStatus::Active,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &[Uint128::zero(); 2])?;
+
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
+    let token_symbol = format_lp_token_symbol(&msg.asset_infos, &deps.querier, "LP")?;
+    // the initial share is the geometric mean of the deposits, so its precision is the mean of
+    // the asset precisions.
+    let token_decimals = (query_token_precision(&deps.querier, msg.asset_infos[0].clone())?
+        + query_token_precision(&deps.querier, msg.asset_infos[1].clone())?)
+        / 2;
+
+    // Create LP Token
+    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+        msg: WasmMsg::Instantiate {
+            code_id: msg.token_code_id,
+            msg: to_binary(&TokenInstantiateMsg {
+                name: token_name,
+                symbol: token_symbol,
+                decimals: token_decimals,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+                marketing: lp_token_marketing,
+            })?,
+            funds: vec![],
+            admin: None,
+            label: String::from("Novaswap LP token"),
+        }
+        .into(),
+        id: INSTANTIATE_TOKEN_REPLY_ID,
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }];
+
+    Ok(Response::new().add_submessages(sub_msg))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if config.pair_info.liquidity_token != Addr::unchecked("") {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let res = cw_utils::parse_reply_instantiate_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    config.pair_info.liquidity_token = deps.api.addr_validate(res.contract_address.as_str())?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("liquidity_token_addr", config.pair_info.liquidity_token))
+}
+
+pub struct SwapParams {
+    offer_asset: Asset,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    forward_msg: Option<Binary>,
+}
+
+// Exposes all the execute functions.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, info, params),
+        ExecuteMsg::Swap {
+            offer_asset,
+            belief_price,
+            max_spread,
+            to,
+            forward_msg,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            let to_addr = if let Some(addr) = to {
+                Some(deps.api.addr_validate(addr.as_str())?)
+            } else {
+                None
+            };
+
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                SwapParams {
+                    offer_asset,
+                    belief_price,
+                    max_spread,
+                    to: to_addr,
+                    forward_msg,
+                },
+            )
+        }
+        ExecuteMsg::SwapExactOut { .. }
+        | ExecuteMsg::FlashLoan { .. }
+        | ExecuteMsg::FlashSwap { .. } => Err(ContractError::NonSupported {}),
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env, info),
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            min_lp_to_receive,
+            receiver,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                min_lp_to_receive,
+                receiver,
+            )
+        }
+    }
+}
+
+fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let contract_addr = info.sender.clone();
+
+    match from_binary(&msg.msg) {
+        Ok(Cw20HookMsg::Swap {
+            belief_price,
+            max_spread,
+            to,
+            forward_msg,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            let mut authorized = false;
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            for pool in config.pair_info.asset_infos {
+                if let AssetInfo::Token { contract_addr, .. } = &pool {
+                    if contract_addr == &info.sender {
+                        authorized = true;
+                    }
+                }
+            }
+
+            if !authorized {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let sender = deps.api.addr_validate(msg.sender.as_str())?;
+
+            swap(
+                deps,
+                env,
+                info,
+                sender,
+                SwapParams {
+                    offer_asset: Asset {
+                        info: AssetInfo::Token { contract_addr },
+                        amount: msg.amount,
+                    },
+                    belief_price,
+                    max_spread,
+                    to: to_addr,
+                    forward_msg,
+                },
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out,
+            receiver,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            withdraw_liquidity(
+                deps,
+                env,
+                info,
+                Addr::unchecked(msg.sender),
+                msg.amount,
+                min_assets_out,
+                receiver,
+            )
+        }
+        // a constant product pool is only withdrawn proportionally.
+        Ok(Cw20HookMsg::SwapExactOut { .. })
+        | Ok(Cw20HookMsg::WithdrawLiquidityOneCoin { .. })
+        | Ok(Cw20HookMsg::WithdrawLiquidityImbalance { .. }) => Err(ContractError::NonSupported {}),
+        Err(err) => Err(ContractError::Std(err)),
+    }
+}
+
+fn provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: [Asset; 2],
+    slippage_tolerance: Option<Decimal>,
+    min_lp_to_receive: Option<Uint128>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    assets[0].info.check(deps.api)?;
+    assets[1].info.check(deps.api)?;
+
+    // check the amount listed in messages are equal to actually received native coin.
+    for asset in assets.iter() {
+        asset.assert_sent_native_token_balance(&info)?;
+    }
+
+    // get pools asset from pair contract
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_deposits_allowed(&config)?;
+    let pools = load_reserves(deps.storage, &config)?;
+
+    // get the amount of coins the user want to deposit.
+    let mut deposits = [Uint128::zero(); 2];
+    for (i, pool) in pools.iter().enumerate() {
+        deposits[i] = assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or_else(|| StdError::generic_err("Wrong asset info is given"))?;
+    }
+
+    if deposits[0].is_zero() && deposits[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (i, pool) in pools.iter().enumerate() {
+        if deposits[i].is_zero() && pool.amount.is_zero() {
+            return Err(ContractError::InvalidProvideLPsWithSingleToken {});
+        }
+
+        if !deposits[i].is_zero() {
+            if let AssetInfo::Token { contract_addr } = &pool.info {
+                // Add TransferFrom message to messages.
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i],
+                    })?,
+                    funds: vec![],
+                }))
+            }
+        }
+    }
+
+    // total supply of liquidity token
+    let total_supply = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
+
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    let share = if total_supply.is_zero() {
+        // If I'm a first liquidity provider.
+        let share = Uint128::new(
+            (U256::from(deposits[0].u128()) * U256::from(deposits[1].u128()))
+                .integer_sqrt()
+                .as_u128(),
+        );
+
+        // the minimum liquidity is locked in the pair forever.
+        let share = share
+            .checked_sub(Uint128::new(MINIMUM_LIQUIDITY_AMOUNT))
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
+        if share.is_zero() {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        messages.push(mint_liquidity_token_message(
+            &config,
+            env.contract.address.clone(),
+            Uint128::new(MINIMUM_LIQUIDITY_AMOUNT),
+        )?);
+
+        share
+    } else {
+        // the deposit beyond the pool ratio is donated to the pool.
+        let share = std::cmp::min(
+            deposits[0].multiply_ratio(total_supply, pools[0].amount),
+            deposits[1].multiply_ratio(total_supply, pools[1].amount),
+        );
+
+        // Assert that slippage tolerance is respected
+        let expected_share = (deposits[0]
+            .multiply_ratio(total_supply, pools[0].amount)
+            .checked_add(deposits[1].multiply_ratio(total_supply, pools[1].amount))?)
+        .multiply_ratio(1u128, 2u128);
+        assert_slippage_tolerance(slippage_tolerance, share, expected_share)?;
+
+        share
+    };
+
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    if let Some(min_lp_to_receive) = min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpAmountAssertion {
+                minimum: min_lp_to_receive.u128(),
+                received: share.u128(),
+            });
+        }
+    }
+
+    // the deposits are added to the reserves.
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_add(deposits[0])?,
+            pools[1].amount.checked_add(deposits[1])?,
+        ],
+    )?;
+
+    // mint lp token
+    messages.push(mint_liquidity_token_message(
+        &config,
+        deps.api.addr_validate(receiver.as_str())?,
+        share,
+    )?);
+
+    // accumulate prices
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "provide_liquidity"),
+        attr("sender", info.sender.as_str()),
+        attr("receiver", receiver.as_str()),
+        attr("assets", format!("{}, {}", assets[0], assets[1])),
+        attr("share", share.to_string()),
+    ]))
+}
+
+fn withdraw_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    min_assets_out: Option<[Asset; 2]>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => sender.clone(),
+    };
+
+    let (pools, total_share) = pool_info(deps.as_ref(), config.clone())?;
+    let refund_assets: [Asset; 2] = get_share_in_assets(&pools, amount, total_share);
+
+    if let Some(min_assets_out) = min_assets_out {
+        for min_asset in min_assets_out.iter() {
+            let refund_asset = refund_assets
+                .iter()
+                .find(|a| a.info.equal(&min_asset.info))
+                .ok_or_else(|| StdError::generic_err("Wrong asset info is given"))?;
+
+            if refund_asset.amount < min_asset.amount {
+                return Err(ContractError::WithdrawAmountTooSmall {
+                    minimum: min_asset.amount.u128(),
+                    received: refund_asset.amount.u128(),
+                    asset: min_asset.info.clone(),
+                });
+            }
+        }
+    }
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    RESERVES.save(
+        deps.storage,
+        &[
+            pools[0].amount.checked_sub(refund_assets[0].amount)?,
+            pools[1].amount.checked_sub(refund_assets[1].amount)?,
+        ],
+    )?;
+
+    let messages: Vec<CosmosMsg> = vec![
+        refund_assets[0].clone().transfer_msg(receiver.clone())?,
+        refund_assets[1].clone().transfer_msg(receiver.clone())?,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.pair_info.liquidity_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+            funds: vec![],
+        }),
+    ];
+
+    let attributes = vec![
+        attr("action", "withdraw_liquidity"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr(
+            "refund_assets",
+            format!("{}, {}", refund_assets[0], refund_assets[1]),
+        ),
+        attr("receiver", receiver.as_str()),
+    ];
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+// Only the status of constant product pairs can be updated.
+fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+
+    // the factory updates the status of its pairs in bulk
+    if info.sender != factory_config.owner && info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match from_binary::<PoolStatusUpdateParams>(&params)? {
+        PoolStatusUpdateParams::UpdateStatus { status } => update_status(config, deps, status)?,
+    }
+
+    Ok(Response::default())
+}
+
+fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    params: SwapParams,
+) -> Result<Response, ContractError> {
+    params.offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    let pools = load_reserves(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+
+    if params.offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+    } else if params.offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let offer_amount = params.offer_asset.amount;
+    let ask_pool_info = ask_pool.info.clone();
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_amount,
+        compute_fee_rate(&config),
+    )?;
+
+    // Check the max spread limit
+    assert_max_spread(
+        params.belief_price,
+        params.max_spread,
+        offer_amount,
+        return_amount + commission_amount,
+        spread_amount,
+    )?;
+
+    let return_asset = Asset {
+        info: ask_pool_info.clone(),
+        amount: return_amount,
+    };
+
+    let receiver = params.to.unwrap_or_else(|| sender.clone());
+    let return_msg = match params.forward_msg {
+        Some(msg) => return_asset.send_msg(receiver.clone(), msg)?,
+        None => return_asset.transfer_msg(receiver.clone())?,
+    };
+    let mut messages = vec![return_msg];
+
+    // Send the protocol share of the commission to the fee address
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+    let maker_fee_amount = compute_maker_fee(&fee_info, commission_amount);
+    let mut sent_maker_fee_amount = Uint128::zero();
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            sent_maker_fee_amount = maker_fee_amount;
+            let maker_fee = Asset {
+                info: ask_pool_info.clone(),
+                amount: maker_fee_amount,
+            };
+            messages.push(maker_fee.transfer_msg(fee_address)?);
+        }
+    }
+
+    // the offer joins the reserves, the return and the maker fee leave them.
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    for (reserve, pool) in reserves.iter_mut().zip(pools.iter()) {
+        if pool.info.equal(&params.offer_asset.info) {
+            *reserve = reserve.checked_add(offer_amount)?;
+        } else {
+            *reserve = reserve.checked_sub(return_amount + sent_maker_fee_amount)?;
+        }
+    }
+    RESERVES.save(deps.storage, &reserves)?;
+
+    // Accumulate prices for the assets in the pool
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", params.offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool_info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+// Sends the balances of the pair above its reserves to `to`.
+fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let to = deps.api.addr_validate(&to)?;
+
+    let reserves = load_reserves(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<String> = vec![];
+    for (balance, reserve) in balances.iter().zip(reserves.iter()) {
+        let surplus = Asset {
+            info: balance.info.clone(),
+            amount: balance.amount.saturating_sub(reserve.amount),
+        };
+        if !surplus.amount.is_zero() {
+            skimmed.push(surplus.to_string());
+            messages.push(surplus.transfer_msg(to.clone())?);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to)
+        .add_attribute("assets", skimmed.join(", ")))
+}
+
+// Sets the reserves of the pair to its balances, the prices are accumulated with the former reserves.
+// Only the factory owner can sync, as the tokens sent to the pair are credited to the providers.
+fn sync(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reserves = load_reserves(deps.storage, &config)?;
+    let balances: [Asset; 2] = config
+        .pair_info
+        .query_pools(&deps.querier, env.contract.address.clone())?;
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+        env,
+        &config,
+        reserves[0].amount,
+        query_token_precision(&deps.querier, reserves[0].info.clone())?,
+        reserves[1].amount,
+        query_token_precision(&deps.querier, reserves[1].info.clone())?,
+    )? {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    RESERVES.save(deps.storage, &[balances[0].amount, balances[1].amount])?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", format!("{}, {}", balances[0], balances[1])))
+}
+
+// Exposes all query functions.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Pair {} => to_binary(&query_pair_info(deps)?),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
+        QueryMsg::Share { amount } => to_binary(&query_share(deps, amount)?),
+        QueryMsg::Simulation { offer_asset } => to_binary(&query_simulation(deps, offer_asset)?),
+        QueryMsg::ReverseSimulation { ask_asset } => {
+            to_binary(&query_reverse_simulation(deps, ask_asset)?)
+        }
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::SimulateWithdraw { lp_amount } => {
+            to_binary(&query_simulate_withdraw(deps, lp_amount)?)
+        }
+        QueryMsg::VirtualPrice {} => to_binary(&query_virtual_price(deps)?),
+        QueryMsg::SpotPrice { offer_asset_info } => {
+            to_binary(&query_spot_price(deps, offer_asset_info)?)
+        }
+        QueryMsg::SimulateWithdrawOneCoin { .. }
+        | QueryMsg::Rates {}
+        | QueryMsg::Observe { .. }
+        | QueryMsg::TwapPrice { .. } => Err(StdError::generic_err(
+            ContractError::NonSupported {}.to_string(),
+        )),
+    }
+}
+
+// Returns pair info
+pub fn query_pair_info(deps: Deps) -> StdResult<PairInfo> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(config.pair_info)
+}
+
+// Returns the amount of assets in the pair contract as well as the amount of LP.
+pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, total_supply) = pool_info(deps, config)?;
+
+    Ok(PoolResponse {
+        assets,
+        total_supply,
+    })
+}
+
+// Returns the amount of assets owned within the pool with the amount of LP tokens.
+pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<[Asset; 2]> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config)?;
+    let owned_assets = get_share_in_assets(&pools, amount, total_share);
+
+    Ok(owned_assets)
+}
+
+// Returns the assets refunded for withdrawing `lp_amount` LP tokens
+pub fn query_simulate_withdraw(
+    deps: Deps,
+    lp_amount: Uint128,
+) -> StdResult<SimulateWithdrawResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config)?;
+
+    Ok(SimulateWithdrawResponse {
+        refund_assets: get_share_in_assets(&pools, lp_amount, total_share),
+    })
+}
+
+// Returns information about a swap simulation
+pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools = load_reserves(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+    if offer_asset.info.equal(&pools[0].info) {
+        offer_pool = pools[0].clone();
+        ask_pool = pools[1].clone();
+    } else if offer_asset.info.equal(&pools[1].info) {
+        offer_pool = pools[1].clone();
+        ask_pool = pools[0].clone();
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs",
+        ));
+    }
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        compute_fee_rate(&config),
+    )?;
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pair_info.pair_type.clone(),
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        maker_fee_amount: compute_maker_fee(&fee_info, commission_amount),
+    })
+}
+
+// Returns information about a reverse swap simulation
+pub fn query_reverse_simulation(
+    deps: Deps,
+    ask_asset: Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools = load_reserves(deps.storage, &config)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+    if ask_asset.info.equal(&pools[0].info) {
+        ask_pool = pools[0].clone();
+        offer_pool = pools[1].clone();
+    } else if ask_asset.info.equal(&pools[1].info) {
+        ask_pool = pools[1].clone();
+        offer_pool = pools[0].clone();
+    } else {
+        return Err(StdError::generic_err(
+            "Given ask asset doesn't belong to pairs",
+        ));
+    }
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        compute_fee_rate(&config),
+    )?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount,
+        spread_amount,
+        commission_amount,
+    })
+}
+
+// Returns price oracle
+pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps, config.clone())?;
+
+    let mut price0_cumulative_last = config.price0_cumulative_last;
+    let mut price1_cumulative_last = config.price1_cumulative_last;
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, _)) = accumulate_prices(
+        env,
+        &config,
+        assets[0].amount,
+        query_token_precision(&deps.querier, assets[0].info.clone())?,
+        assets[1].amount,
+        query_token_precision(&deps.querier, assets[1].info.clone())?,
+    )? {
+        price0_cumulative_last = price0_cumulative_new;
+        price1_cumulative_last = price1_cumulative_new;
+    }
+
+    let resp = CumulativePricesResponse {
+        assets,
+        total_share,
+        price0_cumulative_last,
+        price1_cumulative_last,
+    };
+
+    Ok(resp)
+}
+
+// Returns the value of one LP token, which is sqrt(x * y) divided by the LP supply
+pub fn query_virtual_price(deps: Deps) -> StdResult<VirtualPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, config)?;
+
+    if total_share.is_zero() {
+        return Ok(VirtualPriceResponse {
+            virtual_price: Decimal::zero(),
+        });
+    }
+
+    let invariant = (U256::from(pools[0].amount.u128()) * U256::from(pools[1].amount.u128()))
+        .integer_sqrt()
+        .as_u128();
+
+    Ok(VirtualPriceResponse {
+        virtual_price: Decimal::from_ratio(invariant, total_share),
+    })
+}
+
+// Returns the marginal price of the offer asset in the other asset of the pair
+pub fn query_spot_price(deps: Deps, offer_asset_info: AssetInfo) -> StdResult<SpotPriceResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pools = load_reserves(deps.storage, &config)?;
+
+    let (offer_pool, ask_pool) = if offer_asset_info.equal(&pools[0].info) {
+        (pools[0].clone(), pools[1].clone())
+    } else if offer_asset_info.equal(&pools[1].info) {
+        (pools[1].clone(), pools[0].clone())
+    } else {
+        return Err(StdError::generic_err(
+            "Given offer asset doesn't belong to pairs",
+        ));
+    };
+
+    let offer_precision = query_token_precision(&deps.querier, offer_pool.info)?;
+    let ask_precision = query_token_precision(&deps.querier, ask_pool.info.clone())?;
+    let greater_precision = offer_precision.max(ask_precision);
+
+    let price = Decimal::checked_from_ratio(
+        adjust_precision(ask_pool.amount, ask_precision, greater_precision)?,
+        adjust_precision(offer_pool.amount, offer_precision, greater_precision)?,
+    )
+    .map_err(|_| StdError::generic_err("The pool has no liquidity"))?;
+
+    Ok(SpotPriceResponse {
+        offer_asset_info,
+        ask_asset_info: ask_pool.info,
+        price,
+    })
+}
+
+// Returns current configuration
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse {
+        block_time_last: config.block_time_last,
+        status: config.status,
+        params: Some(to_binary(&XykPoolConfig {
+            fee_bps: config.fee_bps,
+        })?),
+    })
+}
+
+// Used for contract migration.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    if contract_version.contract != CONTRACT_NAME {
+        return Err(ContractError::MigrationError {});
+    }
+
+    let stored_version: Version = contract_version
+        .version
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    if stored_version > new_version {
+        return Err(ContractError::MigrationError {});
+    }
+
+    // Pairs instantiated before the reserves were tracked start from their balances.
+    if RESERVES.may_load(deps.storage)?.is_none() {
+        let config: Config = CONFIG.load(deps.storage)?;
+        let pools: [Asset; 2] = config
+            .pair_info
+            .query_pools(&deps.querier, env.contract.address)?;
+        RESERVES.save(deps.storage, &[pools[0].amount, pools[1].amount])?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("previous_contract_version", contract_version.version),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
+}
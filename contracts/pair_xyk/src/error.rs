@@ -0,0 +1,85 @@
+use crate::utils::{MAX_FEE_BPS, MINIMUM_LIQUIDITY_AMOUNT};
+use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError};
+use novaswap::asset::AssetInfo;
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes constant product pair contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Operation non supported")]
+    NonSupported {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Insufficient amount of liquidity")]
+    LiquidityAmountTooSmall {},
+
+    #[error("Initial liquidity must be more than {}", MINIMUM_LIQUIDITY_AMOUNT)]
+    MinimumLiquidityAmountError {},
+
+    #[error("Withdrawn amount is less than the minimum, minimum: {minimum}, received: {received}, asset: {asset}")]
+    WithdrawAmountTooSmall {
+        minimum: u128,
+        received: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Operation exceeds max splippage tolerance")]
+    MaxSlippageAssertion {},
+
+    #[error("Provided slippage tolerance exceeds allowed limit")]
+    AllowedSlippageAssertion {},
+
+    #[error("Minted LP amount is less than the minimum, minimum: {minimum}, received: {received}")]
+    MinLpAmountAssertion { minimum: u128, received: u128 },
+
+    #[error("Native token balance mismatch between the argument and the transferred")]
+    AssetMismatch {},
+
+    #[error("Fee must be less than or equal to {} bps", MAX_FEE_BPS)]
+    IncorrectFee {},
+
+    #[error("It is not possible to provide liquidity with one token for an empty pool")]
+    InvalidProvideLPsWithSingleToken {},
+
+    #[error("Swaps are paused in this pool")]
+    SwapsPaused {},
+
+    #[error("The pool only allows withdrawals")]
+    WithdrawOnly {},
+
+    #[error("The transaction deadline has passed")]
+    Expired {},
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
+
+impl From<ConversionOverflowError> for ContractError {
+    fn from(o: ConversionOverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
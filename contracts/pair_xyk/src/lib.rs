@@ -0,0 +1,10 @@
+pub mod contract;
+pub mod error;
+pub mod state;
+pub mod utils;
+
+#[cfg(test)]
+mod test;
+
+#[cfg(test)]
+mod mock_querier;
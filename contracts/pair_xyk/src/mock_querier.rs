@@ -0,0 +1,201 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use cosmwasm_std::{
+    from_binary, from_slice,
+    testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, Coin, Decimal, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest,
+    SystemError, SystemResult, Uint128, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use novaswap::factory::{
+    ConfigResponse as FactoryConfigResponse, FeeInfoResponse, QueryMsg as FactoryQueryMsg,
+};
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
+/// This uses the Astroport CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+        custom_query_type: PhantomData,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    token_querier: TokenQuerier,
+    factory_querier: FactoryQuerier,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TokenQuerier {
+    // This lets us iterate over all pairs that match the first string
+    balances: HashMap<String, HashMap<String, Uint128>>,
+}
+
+impl TokenQuerier {
+    pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
+        TokenQuerier {
+            balances: balances_to_map(balances),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FactoryQuerier {
+    fee_address: Option<String>,
+    maker_fee_bps: u16,
+}
+
+pub(crate) fn balances_to_map(
+    balances: &[(&String, &[(&String, &Uint128)])],
+) -> HashMap<String, HashMap<String, Uint128>> {
+    let mut balances_map: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
+    for (contract_addr, balances) in balances.iter() {
+        let mut contract_balances_map: HashMap<String, Uint128> = HashMap::new();
+        for (addr, balance) in balances.iter() {
+            contract_balances_map.insert(addr.to_string(), **balance);
+        }
+
+        balances_map.insert(contract_addr.to_string(), contract_balances_map);
+    }
+    balances_map
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == "factory" =>
+            {
+                match from_binary(msg).unwrap() {
+                    FactoryQueryMsg::Config {} => SystemResult::Ok(
+                        to_binary(&FactoryConfigResponse {
+                            owner: Addr::unchecked("owner0000"),
+                            pair_configs: vec![],
+                            token_code_id: 10u64,
+                            fee_address: self
+                                .factory_querier
+                                .fee_address
+                                .as_ref()
+                                .map(Addr::unchecked),
+                        })
+                        .into(),
+                    ),
+                    FactoryQueryMsg::FeeInfo { .. } => SystemResult::Ok(
+                        to_binary(&FeeInfoResponse {
+                            fee_address: self
+                                .factory_querier
+                                .fee_address
+                                .as_ref()
+                                .map(Addr::unchecked),
+                            maker_fee_rate: Decimal::from_ratio(
+                                self.factory_querier.maker_fee_bps,
+                                10000u128,
+                            ),
+                        })
+                        .into(),
+                    ),
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                match from_binary(msg).unwrap() {
+                    Cw20QueryMsg::TokenInfo {} => {
+                        let balances: &HashMap<String, Uint128> =
+                            match self.token_querier.balances.get(contract_addr) {
+                                Some(balances) => balances,
+                                None => {
+                                    return SystemResult::Err(SystemError::Unknown {});
+                                }
+                            };
+
+                        let mut total_supply = Uint128::zero();
+
+                        for balance in balances {
+                            total_supply += *balance.1;
+                        }
+
+                        SystemResult::Ok(
+                            to_binary(&TokenInfoResponse {
+                                name: "SuperNovaToken".to_string(),
+                                symbol: "SNT".to_string(),
+                                decimals: 6,
+                                total_supply,
+                            })
+                            .into(),
+                        )
+                    }
+                    Cw20QueryMsg::Balance { address } => {
+                        let balances: &HashMap<String, Uint128> =
+                            match self.token_querier.balances.get(contract_addr) {
+                                Some(balances) => balances,
+                                None => {
+                                    return SystemResult::Err(SystemError::Unknown {});
+                                }
+                            };
+
+                        let balance = match balances.get(&address) {
+                            Some(v) => v,
+                            None => {
+                                return SystemResult::Err(SystemError::Unknown {});
+                            }
+                        };
+
+                        SystemResult::Ok(to_binary(&BalanceResponse { balance: *balance }).into())
+                    }
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Raw { .. }) => {
+                panic!("DO NOT ENTER HERE");
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            token_querier: TokenQuerier::default(),
+            factory_querier: FactoryQuerier::default(),
+        }
+    }
+
+    // Configure the mint whitelist mock querier
+    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
+
+    // Configure the fee split returned by the factory
+    pub fn with_fee_info(&mut self, fee_address: Option<&str>, maker_fee_bps: u16) {
+        self.factory_querier = FactoryQuerier {
+            fee_address: fee_address.map(String::from),
+            maker_fee_bps,
+        };
+    }
+}
@@ -0,0 +1,30 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::Item;
+use novaswap::pair::PoolStatus;
+use novaswap::pairinfo::PairInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub pair_info: PairInfo,
+    pub factory_addr: Addr,
+
+    // used for calculatring TAWP.
+    pub block_time_last: u64,
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+
+    // swap fee charged by this pair, in basis points.
+    pub fee_bps: u16,
+
+    // operations allowed by the pool, pools stored before the status default to active.
+    #[serde(default)]
+    pub status: PoolStatus,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// the reserves of the pair, in the order of `pair_info.asset_infos`. Tokens sent to the pair
+// outside of its messages are not part of the reserves, they can be skimmed or synced.
+pub const RESERVES: Item<[Uint128; 2]> = Item::new("reserves");
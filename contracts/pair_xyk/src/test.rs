@@ -0,0 +1,1066 @@
+use crate::{
+    contract::{
+        execute, instantiate, migrate, query, query_config, query_pair_info, query_pool,
+        query_reverse_simulation, query_simulation, reply,
+    },
+    error::ContractError,
+    mock_querier::{mock_dependencies, WasmMockQuerier},
+    state::{Config, RESERVES},
+    utils::{accumulate_prices, compute_offer_amount, compute_swap, MINIMUM_LIQUIDITY_AMOUNT},
+};
+use cosmwasm_std::{
+    attr, from_binary,
+    testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, OwnedDeps, Reply, StdError,
+    SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_utils::Expiration;
+use novaswap::{
+    asset::{Asset, AssetInfo},
+    pair::{
+        ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolStatus,
+        PoolStatusUpdateParams, QueryMsg, XykPoolConfig, XykPoolParams,
+    },
+    pairinfo::{PairInfo, PairType},
+    token::{InstantiateMarketingInfo, InstantiateMsg as TokenInstantiateMsg},
+};
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+struct MsgInstantiateContractResponse {
+    #[prost(string, tag = "1")]
+    pub contract_address: ::prost::alloc::string::String,
+    #[prost(bytes, tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+    };
+
+    let mut encoded_instantiate_reply = Vec::<u8>::with_capacity(data.encoded_len());
+    data.encode(&mut encoded_instantiate_reply).unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(encoded_instantiate_reply.into()),
+        }),
+    };
+
+    reply(deps, mock_env(), reply_msg).unwrap();
+}
+
+// 페어를 거쳐 예치된 것처럼 reserve 를 저장한다.
+fn store_reserves(deps: DepsMut, reserves: [u128; 2]) {
+    RESERVES
+        .save(
+            deps.storage,
+            &[Uint128::new(reserves[0]), Uint128::new(reserves[1])],
+        )
+        .unwrap();
+}
+
+fn pair_asset_infos() -> [AssetInfo; 2] {
+    [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    ]
+}
+
+fn instantiate_msg(init_params: Option<XykPoolParams>) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos: pair_asset_infos(),
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: init_params.map(|params| to_binary(&params).unwrap()),
+    }
+}
+
+// uusd 를 `native_amount`, asset0000 을 `token_amount` 만큼 가지고 있는 페어를 만든다.
+fn pair_with_liquidity(
+    native_amount: u128,
+    token_amount: u128,
+    total_share: u128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(native_amount),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(token_amount),
+            )],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(total_share))],
+        ),
+    ]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(None),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), [native_amount, token_amount]);
+
+    deps
+}
+
+fn provide_msg(native_amount: u128, token_amount: u128) -> ExecuteMsg {
+    ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: pair_asset_infos()[0].clone(),
+                amount: Uint128::new(native_amount),
+            },
+            Asset {
+                info: pair_asset_infos()[1].clone(),
+                amount: Uint128::new(token_amount),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    }
+}
+
+/// 수수료를 지정하지 않으면 30 bps 로 생성되고, 한도를 넘는 수수료나 중복 자산은 거부된다.
+#[test]
+fn proper_initialization() {
+    let deps = pair_with_liquidity(0, 0, 0);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.liquidity_token, Addr::unchecked("liquidity0000"));
+    assert_eq!(pair_info.pair_type, PairType::Xyk {});
+
+    let config = query_config(deps.as_ref()).unwrap();
+    let params: XykPoolConfig = from_binary(&config.params.unwrap()).unwrap();
+    assert_eq!(params.fee_bps, 30);
+
+    let mut deps = mock_dependencies(&[]);
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(Some(XykPoolParams {
+            fee_bps: Some(1001),
+            lp_token_marketing: None,
+        })),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IncorrectFee {});
+
+    let mut msg = instantiate_msg(None);
+    msg.asset_infos[1] = msg.asset_infos[0].clone();
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+}
+
+/// LP 토큰의 심볼은 자산 심볼로 만들고, 소수점 자리수는 자산들의 평균, 마케팅 정보는 init params 에서 가져온다.
+#[test]
+fn lp_token_metadata() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let marketing = InstantiateMarketingInfo {
+        project: Some("Novaswap".to_string()),
+        description: None,
+        marketing: Some("addr0000".to_string()),
+        logo: None,
+    };
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(Some(XykPoolParams {
+            fee_bps: None,
+            lp_token_marketing: Some(marketing.clone()),
+        })),
+    )
+    .unwrap();
+
+    let token_msg: TokenInstantiateMsg = match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => from_binary(msg).unwrap(),
+        _ => panic!("DO NOT ENTER HERE"),
+    };
+    assert_eq!(token_msg.name, "UUSD-SNT-LP");
+    assert_eq!(token_msg.symbol, "UUSD-SNT-LP");
+    assert_eq!(token_msg.decimals, 6);
+    assert_eq!(token_msg.marketing, Some(marketing));
+    assert!(token_msg.validate().is_ok());
+}
+
+/// 첫 유동성 공급은 sqrt(x * y) 만큼의 LP 중 MINIMUM_LIQUIDITY_AMOUNT 를 페어에 잠근다.
+#[test]
+fn provide_initial_liquidity() {
+    let mut deps = pair_with_liquidity(100_000000, 0, 0);
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(100_000000, 400_000000),
+    )
+    .unwrap();
+
+    // sqrt(100 * 400) = 200
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|m| m.msg.clone())
+            .collect::<Vec<CosmosMsg>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(400_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::new(MINIMUM_LIQUIDITY_AMOUNT),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(200_000000 - MINIMUM_LIQUIDITY_AMOUNT),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // the initial share must be more than the locked amount
+    let mut deps = pair_with_liquidity(1000, 0, 0);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, provide_msg(1000, 1000)).unwrap_err();
+    assert_eq!(err, ContractError::MinimumLiquidityAmountError {});
+
+    // an empty pool can not be provided with a single asset
+    let mut deps = pair_with_liquidity(0, 0, 0);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        provide_msg(0, 100_000000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidProvideLPsWithSingleToken {});
+}
+
+/// 이후 유동성 공급은 풀 비율에 못 미치는 자산을 기준으로 LP 를 받고, slippage_tolerance 와 min_lp_to_receive 를 확인한다.
+#[test]
+fn provide_liquidity() {
+    // 100 uusd and 100 asset0000 in the pool, 50 uusd are being deposited
+    let mut deps = pair_with_liquidity(150_000000, 100_000000, 100_000000);
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(50_000000),
+        }],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide_msg(50_000000, 100_000000),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0000"),
+                amount: Uint128::new(50_000000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(150_000000), Uint128::new(200_000000)]
+    );
+
+    // the mocked LP supply does not follow the mint
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+
+    // the share is 2/3 of a deposit valued at the pool price
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: pair_asset_infos()[0].clone(),
+                    amount: Uint128::new(50_000000),
+                },
+                Asset {
+                    info: pair_asset_infos()[1].clone(),
+                    amount: Uint128::new(100_000000),
+                },
+            ],
+            slippage_tolerance: Some(Decimal::percent(20)),
+            min_lp_to_receive: None,
+            receiver: None,
+            deadline: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MaxSlippageAssertion {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::ProvideLiquidity {
+            assets: [
+                Asset {
+                    info: pair_asset_infos()[0].clone(),
+                    amount: Uint128::new(50_000000),
+                },
+                Asset {
+                    info: pair_asset_infos()[1].clone(),
+                    amount: Uint128::new(50_000000),
+                },
+            ],
+            slippage_tolerance: Some(Decimal::percent(1)),
+            min_lp_to_receive: Some(Uint128::new(50_000001)),
+            receiver: None,
+            deadline: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinLpAmountAssertion {
+            minimum: 50_000001,
+            received: 50_000000,
+        }
+    );
+}
+
+/// 유동성 회수는 LP 지분만큼 두 자산을 비례하여 돌려주고, 최소 회수량을 확인한다.
+#[test]
+fn withdraw_liquidity() {
+    let mut deps = pair_with_liquidity(100_000000, 200_000000, 100_000000);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(10_000000),
+    });
+
+    // only the liquidity token can withdraw
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|m| m.msg.clone())
+            .collect::<Vec<CosmosMsg>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(10_000000),
+                }],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(20_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(10_000000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(90_000000), Uint128::new(180_000000)]
+    );
+
+    // the mocked LP supply does not follow the burn
+    store_reserves(deps.as_mut(), [100_000000, 200_000000]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: Some([
+                Asset {
+                    info: pair_asset_infos()[0].clone(),
+                    amount: Uint128::new(10_000000),
+                },
+                Asset {
+                    info: pair_asset_infos()[1].clone(),
+                    amount: Uint128::new(20_000001),
+                },
+            ]),
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(10_000000),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawAmountTooSmall {
+            minimum: 20_000001,
+            received: 20_000000,
+            asset: pair_asset_infos()[1].clone(),
+        }
+    );
+}
+
+/// x * y = k 로 native 를 token 으로 스왑하고, 수수료 중 프로토콜 몫을 fee address 로 보낸다.
+#[test]
+fn swap_native_to_token() {
+    let offer_amount = 1_500000u128;
+    let mut deps = pair_with_liquidity(1000_000000 + offer_amount, 1000_000000, 1000_000000);
+    store_reserves(deps.as_mut(), [1000_000000, 1000_000000]);
+    deps.querier.with_fee_info(Some("fee_address"), 5000);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(offer_amount),
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: pair_asset_infos()[0].clone(),
+                amount: Uint128::new(offer_amount),
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        },
+    )
+    .unwrap();
+
+    // ask_pool * offer / (offer_pool + offer)
+    let expected_ret_amount = 1000_000000u128 * offer_amount / (1000_000000 + offer_amount);
+    let expected_spread_amount = offer_amount - expected_ret_amount;
+    let expected_commission_amount = expected_ret_amount * 3 / 1000;
+    let expected_return_amount = expected_ret_amount - expected_commission_amount;
+    let expected_maker_fee_amount = expected_commission_amount / 2;
+
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|m| m.msg.clone())
+            .collect::<Vec<CosmosMsg>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(expected_return_amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("fee_address"),
+                    amount: Uint128::new(expected_maker_fee_amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(res.attributes[6].value, expected_return_amount.to_string());
+    assert_eq!(res.attributes[7].value, expected_spread_amount.to_string());
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [
+            Uint128::new(1000_000000 + offer_amount),
+            Uint128::new(1000_000000 - expected_return_amount - expected_maker_fee_amount)
+        ]
+    );
+
+    // the pool without the offer amount gives the same simulation
+    let mut deps = pair_with_liquidity(1000_000000, 1000_000000, 1000_000000);
+    deps.querier.with_fee_info(Some("fee_address"), 5000);
+
+    let simulation = query_simulation(
+        deps.as_ref(),
+        Asset {
+            info: pair_asset_infos()[0].clone(),
+            amount: Uint128::new(offer_amount),
+        },
+    )
+    .unwrap();
+    assert_eq!(simulation.return_amount.u128(), expected_return_amount);
+    assert_eq!(simulation.spread_amount.u128(), expected_spread_amount);
+    assert_eq!(
+        simulation.commission_amount.u128(),
+        expected_commission_amount
+    );
+    assert_eq!(
+        simulation.maker_fee_amount.u128(),
+        expected_maker_fee_amount
+    );
+
+    // the reverse simulation asks for at least the same offer amount
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: pair_asset_infos()[1].clone(),
+            amount: Uint128::new(expected_return_amount),
+        },
+    )
+    .unwrap();
+    assert!((offer_amount..=offer_amount + 1).contains(&reverse_simulation.offer_amount.u128()));
+}
+
+/// token 을 native 로 스왑할 때 max spread 를 넘으면 스왑되지 않는다.
+#[test]
+fn swap_token_to_native_with_max_spread() {
+    let mut deps = pair_with_liquidity(100_000000, 100_000000 + 10_000000, 100_000000);
+    store_reserves(deps.as_mut(), [100_000000, 100_000000]);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(10_000000),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(5)),
+            to: Some(String::from("third_party")),
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+
+    // only the pair assets can be offered
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0001", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // 10% of the pool has a spread of about 9%
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(10_000000),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: Some(Decimal::percent(10)),
+            to: Some(String::from("third_party")),
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+
+    let expected_ret_amount = 100_000000u128 * 10_000000 / 110_000000;
+    let expected_return_amount = expected_ret_amount - expected_ret_amount * 3 / 1000;
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("third_party"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(expected_return_amount),
+            }],
+        })
+    );
+}
+
+/// 기한이 지난 스왑은 실패한다.
+#[test]
+fn expired_swap() {
+    let offer_amount = 10_000000u128;
+    let mut deps = pair_with_liquidity(1000_000000 + offer_amount, 1000_000000, 1000_000000);
+    let env = mock_env();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(offer_amount),
+            }],
+        ),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: pair_asset_infos()[0].clone(),
+                amount: Uint128::new(offer_amount),
+            },
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: Some(Expiration::AtTime(env.block.time)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+}
+
+/// 스테이블 페어 전용 메시지는 지원하지 않는다.
+#[test]
+fn stable_only_operations_are_not_supported() {
+    let mut deps = pair_with_liquidity(100_000000, 100_000000, 100_000000);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidityOneCoin {
+            asset_info: pair_asset_infos()[0].clone(),
+            min_amount: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(10_000000),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NonSupported {});
+
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: pair_asset_infos()[1].clone(),
+            amount: Uint128::new(1_000000),
+        },
+        max_offer_amount: Uint128::new(2_000000),
+        to: None,
+        deadline: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(2_000000),
+            }],
+        ),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NonSupported {});
+
+    let err = query(deps.as_ref(), mock_env(), QueryMsg::Rates {}).unwrap_err();
+    assert_eq!(err, StdError::generic_err("Operation non supported"));
+}
+
+/// 풀 상태만 변경할 수 있고, 출금 전용 상태에서는 스왑과 유동성 공급을 막는다.
+#[test]
+fn update_pool_status() {
+    let mut deps = pair_with_liquidity(100_000000, 100_000000, 100_000000);
+
+    // the fee of constant product pairs is fixed at instantiation
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_binary(&XykPoolParams {
+                fee_bps: Some(10),
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::ParseErr { .. })));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&PoolStatusUpdateParams::UpdateStatus {
+            status: PoolStatus::WithdrawOnly,
+        })
+        .unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("factory", &[]), msg).unwrap();
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.status, PoolStatus::WithdrawOnly);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1_000000),
+            }],
+        ),
+        provide_msg(1_000000, 1_000000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawOnly {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            forward_msg: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(1_000000),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::WithdrawOnly {});
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(10_000000),
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        msg,
+    )
+    .unwrap();
+}
+
+/// 풀에 직접 보낸 토큰은 skim 으로 꺼내거나, 팩토리 owner 의 sync 로 리저브에 반영한다.
+#[test]
+fn skim_and_sync() {
+    let mut deps = pair_with_liquidity(1000_000000, 1000_000000, 1000_000000);
+    // 100 uusd 가 리저브에 반영되지 않은 채로 풀에 들어와 있다
+    store_reserves(deps.as_mut(), [900_000000, 1000_000000]);
+
+    let res = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::new(900_000000));
+
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000000),
+            }],
+        }))]
+    );
+    // skim 은 리저브를 바꾸지 않는다
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(900_000000), Uint128::new(1000_000000)]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr("reserves", "1000000000uusd, 1000000000asset0000"),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1000_000000), Uint128::new(1000_000000)]
+    );
+
+    // 리저브와 잔고가 같으면 skim 할 것이 없다
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert!(res.messages.is_empty());
+}
+
+/// 리저브를 저장하기 전에 생성된 페어는 마이그레이션할 때 잔고로 리저브를 채운다.
+#[test]
+fn migrate_seeds_reserves() {
+    let mut deps = pair_with_liquidity(1000_000000, 2000_000000, 1000_000000);
+    RESERVES.remove(deps.as_mut().storage);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(1000_000000), Uint128::new(2000_000000)]
+    );
+
+    // 이미 저장된 리저브는 그대로 둔다
+    store_reserves(deps.as_mut(), [900_000000, 2000_000000]);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        [Uint128::new(900_000000), Uint128::new(2000_000000)]
+    );
+}
+
+/// 스왑 수량과 역산한 제공 수량이 x * y = k 를 만족하는지 확인
+#[test]
+fn constant_product_math() {
+    let offer_pool = Uint128::new(3000_000000);
+    let ask_pool = Uint128::new(1000_000000);
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool,
+        ask_pool,
+        Uint128::new(300_000000),
+        Decimal::zero(),
+    )
+    .unwrap();
+    // 3000 * 1000 = 3300 * 909.09..
+    assert_eq!(return_amount, Uint128::new(90_909090));
+    assert_eq!(spread_amount, Uint128::new(9_090910));
+    assert_eq!(commission_amount, Uint128::zero());
+
+    let (offer_amount, _, _) =
+        compute_offer_amount(offer_pool, ask_pool, return_amount, Decimal::zero()).unwrap();
+    assert!(offer_amount <= Uint128::new(300_000000));
+    let (return_amount_again, _, _) =
+        compute_swap(offer_pool, ask_pool, offer_amount, Decimal::zero()).unwrap();
+    assert_eq!(return_amount_again, return_amount);
+
+    // the ask pool can not be drained
+    let err =
+        compute_offer_amount(offer_pool, ask_pool, ask_pool, Decimal::permille(3)).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Not enough liquidity in the ask pool")
+    );
+
+    let err =
+        compute_swap(Uint128::zero(), ask_pool, offer_pool, Decimal::permille(3)).unwrap_err();
+    assert_eq!(err, StdError::generic_err("The pool has no liquidity"));
+}
+
+/// TWAP price 계산, 자산의 소수점 자리수가 달라도 같은 가격을 누적한다.
+#[test]
+fn test_accumulate_prices() {
+    let mut env = mock_env();
+    let config = Config {
+        pair_info: PairInfo {
+            asset_infos: pair_asset_infos(),
+            contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            liquidity_token: Addr::unchecked("liquidity0000"),
+            pair_type: PairType::Xyk {},
+        },
+        factory_addr: Addr::unchecked("factory"),
+        block_time_last: env.block.time.seconds(),
+        price0_cumulative_last: Uint128::new(1_000000),
+        price1_cumulative_last: Uint128::new(2_000000),
+        fee_bps: 30,
+        status: PoolStatus::Active,
+    };
+
+    // no time elapsed
+    assert_eq!(
+        accumulate_prices(
+            env.clone(),
+            &config,
+            Uint128::new(1000_000000),
+            6,
+            Uint128::new(2000_000000),
+            6
+        )
+        .unwrap(),
+        None
+    );
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let expected = Some((
+        Uint128::new(1_000000 + 100 * 2_000000),
+        Uint128::new(2_000000 + 100 * 500000),
+        env.block.time.seconds(),
+    ));
+
+    // 1 token of x is worth 2 tokens of y
+    assert_eq!(
+        accumulate_prices(
+            env.clone(),
+            &config,
+            Uint128::new(1000_000000),
+            6,
+            Uint128::new(2000_000000),
+            6
+        )
+        .unwrap(),
+        expected
+    );
+    assert_eq!(
+        accumulate_prices(
+            env,
+            &config,
+            Uint128::new(1000_000000),
+            6,
+            Uint128::new(2000_00000000),
+            8
+        )
+        .unwrap(),
+        expected
+    );
+}
+
+/// 다른 컨트랙트나 하위 버전으로의 마이그레이션은 막고, 마이그레이션하면 버전을 올린다.
+#[test]
+fn migrate_contract_version() {
+    let mut deps = pair_with_liquidity(1000_000000, 1000_000000, 1000_000000);
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-concentrated", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-xyk", "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-xyk", "0.9.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", "0.9.0"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
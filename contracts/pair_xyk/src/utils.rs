@@ -0,0 +1,325 @@
+use crate::error::ContractError;
+use crate::state::{Config, CONFIG, RESERVES};
+use cosmwasm_std::{
+    to_binary, Addr, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut, Env, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_utils::Expiration;
+use novaswap::asset::Asset;
+use novaswap::factory::FeeInfoResponse;
+use novaswap::pair::{PoolStatus, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION};
+use novaswap::querier::query_supply;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+pub const DEFAULT_FEE_BPS: u16 = 30;
+pub const MAX_FEE_BPS: u16 = 1000;
+const FEE_DENOMINATOR: u128 = 10000;
+
+// LP tokens locked in the pair by the first liquidity provider,
+// so the pool can never be drained back to an empty state.
+pub const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
+
+// Returns the fee rate of the pair
+pub fn compute_fee_rate(config: &Config) -> Decimal {
+    Decimal::from_ratio(Uint128::from(config.fee_bps), Uint128::new(FEE_DENOMINATOR))
+}
+
+// Returns the part of the commission which goes to the factory fee address.
+// Nothing is taken when the fee address is not set.
+pub fn compute_maker_fee(fee_info: &FeeInfoResponse, commission_amount: Uint128) -> Uint128 {
+    if fee_info.fee_address.is_some() {
+        commission_amount * fee_info.maker_fee_rate
+    } else {
+        Uint128::zero()
+    }
+}
+
+/// compute swap then returns return_amount, spread_amount and commission_amount.
+pub fn compute_swap(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // offer => ask
+    if offer_pool.is_zero() || ask_pool.is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    // x * y = k, so return_amount = ask_pool * offer_amount / (offer_pool + offer_amount)
+    let return_amount = ask_pool
+        .checked_multiply_ratio(offer_amount, offer_pool.checked_add(offer_amount)?)
+        .map_err(|_| StdError::generic_err("Failed to compute the return amount"))?;
+
+    // spread_amount = offer_amount * ask_pool / offer_pool - return_amount
+    let spread_amount = offer_amount
+        .checked_multiply_ratio(ask_pool, offer_pool)
+        .map_err(|_| StdError::generic_err("Failed to compute the spread amount"))?
+        .saturating_sub(return_amount);
+
+    let commission_amount: Uint128 = return_amount * commission_rate;
+
+    // The commission will be absorbed by the pool
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount)?;
+
+    Ok((return_amount, spread_amount, commission_amount))
+}
+
+// Returns an amount of offer assets for a specified amount of ask assets.
+pub fn compute_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // ask => offer
+    if offer_pool.is_zero() || ask_pool.is_zero() {
+        return Err(StdError::generic_err("The pool has no liquidity"));
+    }
+
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let inv_one_minus_commission: Decimal = Decimal::one() / one_minus_commission;
+    let before_commission_deduction = ask_amount * inv_one_minus_commission;
+
+    if before_commission_deduction >= ask_pool {
+        return Err(StdError::generic_err(
+            "Not enough liquidity in the ask pool",
+        ));
+    }
+
+    // offer_amount = offer_pool * ask_amount / (ask_pool - ask_amount), rounded up
+    let remaining_ask_pool = ask_pool - before_commission_deduction;
+    let offer_amount = offer_pool
+        .full_mul(before_commission_deduction)
+        .checked_add(remaining_ask_pool.into())?
+        .checked_sub(1u8.into())?
+        .checked_div(remaining_ask_pool.into())?;
+    let offer_amount = Uint128::try_from(offer_amount)?;
+
+    // spread_amount = offer_amount * ask_pool / offer_pool - ask_amount
+    let spread_amount = offer_amount
+        .checked_multiply_ratio(ask_pool, offer_pool)
+        .map_err(|_| StdError::generic_err("Failed to compute the spread amount"))?
+        .saturating_sub(before_commission_deduction);
+
+    let commission_amount = before_commission_deduction * commission_rate;
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
+// Returns a [`ContractError`] when the deadline of the message has passed at the current block.
+pub fn assert_deadline(
+    block: &BlockInfo,
+    deadline: Option<Expiration>,
+) -> Result<(), ContractError> {
+    match deadline {
+        Some(deadline) if deadline.is_expired(block) => Err(ContractError::Expired {}),
+        _ => Ok(()),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow swaps.
+pub fn assert_swaps_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active => Ok(()),
+        PoolStatus::SwapsPaused => Err(ContractError::SwapsPaused {}),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow deposits.
+pub fn assert_deposits_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active | PoolStatus::SwapsPaused => Ok(()),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Updates the operations allowed by the pool.
+pub fn update_status(
+    mut config: Config,
+    deps: DepsMut,
+    status: PoolStatus,
+) -> Result<(), ContractError> {
+    config.status = status;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] on failure.
+// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+// otherwise we just use the swap spread to check `max_spread`.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount * (Decimal::one() / belief_price);
+        let spread_amount = expected_return
+            .checked_sub(return_amount)
+            .unwrap_or_else(|_| Uint128::zero());
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] on failure.
+// `expected_share` is the amount of LP tokens minted for the deposit valued at the pool price,
+// `share` may fall short of it by at most `slippage_tolerance`.
+pub fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    share: Uint128,
+    expected_share: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(slippage_tolerance) = slippage_tolerance {
+        if slippage_tolerance > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+            return Err(ContractError::AllowedSlippageAssertion {});
+        }
+
+        if share < expected_share * (Decimal::one() - slippage_tolerance) {
+            return Err(ContractError::MaxSlippageAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+// adjust given value from `current_precision` to `new_precision`
+pub fn adjust_precision(
+    value: Uint128,
+    current_precision: u8,
+    new_precision: u8,
+) -> StdResult<Uint128> {
+    Ok(match current_precision.cmp(&new_precision) {
+        Ordering::Equal => value,
+        Ordering::Less => value.checked_mul(Uint128::new(
+            // value = value * 10^(new - curr)
+            10_u128.pow((new_precision - current_precision) as u32),
+        ))?,
+        Ordering::Greater => value.checked_div(Uint128::new(
+            // value = value / 10^(curr - new)
+            10_u128.pow((current_precision - new_precision) as u32),
+        ))?,
+    })
+}
+
+// Mint LP tokens for a beneficiary
+pub fn mint_liquidity_token_message(
+    config: &Config,
+    recipient: Addr,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    let lp_token = config.pair_info.liquidity_token.clone();
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: lp_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+// calculate accumulate prices
+pub fn accumulate_prices(
+    env: Env,
+    config: &Config,
+    x: Uint128,
+    x_precision: u8,
+    y: Uint128,
+    y_precision: u8,
+) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+    let block_time = env.block.time.seconds();
+    if block_time <= config.block_time_last {
+        return Ok(None);
+    }
+
+    let greater_precision = x_precision.max(y_precision);
+    let x = adjust_precision(x, x_precision, greater_precision)?;
+    let y = adjust_precision(y, y_precision, greater_precision)?;
+    let one = adjust_precision(Uint128::new(1), 0, TWAP_PRECISION)?;
+
+    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+
+    let mut pcl0 = config.price0_cumulative_last;
+    let mut pcl1 = config.price1_cumulative_last;
+
+    if !x.is_zero() && !y.is_zero() {
+        // the spot prices y / x and x / y in TWAP precision
+        pcl0 = config
+            .price0_cumulative_last
+            .wrapping_add(time_elapsed.checked_mul(one.multiply_ratio(y, x))?);
+        pcl1 = config
+            .price1_cumulative_last
+            .wrapping_add(time_elapsed.checked_mul(one.multiply_ratio(x, y))?);
+    };
+
+    Ok(Some((pcl0, pcl1, block_time)))
+}
+
+pub fn get_share_in_assets(
+    pools: &[Asset; 2],
+    amount: Uint128,
+    total_share: Uint128,
+) -> [Asset; 2] {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    [
+        Asset {
+            info: pools[0].info.clone(),
+            amount: pools[0].amount * share_ratio,
+        },
+        Asset {
+            info: pools[1].info.clone(),
+            amount: pools[1].amount * share_ratio,
+        },
+    ]
+}
+
+pub fn pool_info(deps: Deps, config: Config) -> StdResult<([Asset; 2], Uint128)> {
+    let pools = load_reserves(deps.storage, &config)?;
+    let total_supply: Uint128 = query_supply(&deps.querier, config.pair_info.liquidity_token)?;
+
+    Ok((pools, total_supply))
+}
+
+// Returns the tracked reserves of the pair, balances above them are not counted.
+pub fn load_reserves(storage: &dyn Storage, config: &Config) -> StdResult<[Asset; 2]> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok([
+        Asset {
+            info: config.pair_info.asset_infos[0].clone(),
+            amount: reserves[0],
+        },
+        Asset {
+            info: config.pair_info.asset_infos[1].clone(),
+            amount: reserves[1],
+        },
+    ])
+}
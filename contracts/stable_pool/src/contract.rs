@@ -0,0 +1,944 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use novaswap::asset::{format_lp_token_name, format_lp_token_symbol, Asset, AssetInfo};
+use novaswap::pair::{
+    PoolStatus, ReverseSimulationResponse, SimulationResponse, StablePoolConfig, StablePoolParams,
+    StablePoolUpdateParams,
+};
+use novaswap::pairinfo::{PairType, PoolInfo};
+use novaswap::pool::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
+    MAX_POOL_ASSETS, MIN_POOL_ASSETS,
+};
+use novaswap::querier::{
+    query_factory_config, query_fee_info, query_supply, query_token_precision,
+};
+use novaswap::token::InstantiateMsg as TokenInstantiateMsg;
+use semver::Version;
+
+use crate::error::ContractError;
+use crate::math::{compute_current_amp, compute_d, AMP_PRECISION, MAX_AMP};
+use crate::state::{Config, CONFIG, RESERVES};
+use crate::utils::{
+    adjust_precision, assert_deadline, assert_deposits_allowed, assert_max_spread,
+    assert_slippage_tolerance, assert_swaps_allowed, compute_fee_rate, compute_imbalance_fee_rate,
+    compute_imbalance_fees, compute_maker_fee, compute_offer_amount, compute_swap,
+    get_share_in_assets, greatest_precision, load_reserves, mint_liquidity_token_message,
+    normalize_amounts, pool_info, query_precisions, start_changing_amp, stop_changing_amp,
+    update_fee, update_status, DEFAULT_FEE_BPS, MAX_FEE_BPS,
+};
+
+const CONTRACT_NAME: &str = "novaswap-stable-pool";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+
+// instantiate new novaswap multi-asset stable pool contract.
+// this will mint new token represents LP shares.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.asset_infos.len() < MIN_POOL_ASSETS || msg.asset_infos.len() > MAX_POOL_ASSETS {
+        return Err(ContractError::InvalidNumberOfAssets {});
+    }
+
+    for asset_info in msg.asset_infos.iter() {
+        asset_info.check(deps.api)?;
+    }
+
+    let unique_assets: HashSet<String> = msg.asset_infos.iter().map(|a| a.to_string()).collect();
+    if unique_assets.len() != msg.asset_infos.len() {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    let init_params = msg
+        .init_params
+        .ok_or(ContractError::InitParamsNotFound {})?;
+    let params: StablePoolParams = from_binary(&init_params)?;
+
+    if params.amp == 0 || params.amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    if params.rates.is_some() {
+        return Err(ContractError::RatesNotSupported {});
+    }
+
+    let fee_bps = params.fee_bps.unwrap_or(DEFAULT_FEE_BPS);
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // set config
+    let config = Config {
+        pool_info: PoolInfo {
+            contract_addr: env.contract.address.clone(),
+            liquidity_token: Addr::unchecked(""),
+            asset_infos: msg.asset_infos.clone(),
+            pair_type: PairType::StablePool {},
+        },
+        factory_addr: deps.api.addr_validate(msg.factory_addr.as_str())?,
+        init_amp: params.amp * AMP_PRECISION,
+        init_amp_time: env.block.time.seconds(),
+        next_amp: params.amp * AMP_PRECISION,
+        next_amp_time: env.block.time.seconds(),
+        fee_bps,
+        status: PoolStatus::Active,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    RESERVES.save(deps.storage, &vec![Uint128::zero(); msg.asset_infos.len()])?;
+
+    let token_name = format_lp_token_name(&msg.asset_infos, &deps.querier)?;
+    let token_symbol = format_lp_token_symbol(&msg.asset_infos, &deps.querier, "LP")?;
+    // the minted shares are scaled to the greatest precision of the assets.
+    let mut token_decimals = 0;
+    for asset_info in msg.asset_infos.iter() {
+        token_decimals =
+            token_decimals.max(query_token_precision(&deps.querier, asset_info.clone())?);
+    }
+
+    // Create LP Token
+    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+        msg: WasmMsg::Instantiate {
+            code_id: msg.token_code_id,
+            msg: to_binary(&TokenInstantiateMsg {
+                name: token_name,
+                symbol: token_symbol,
+                decimals: token_decimals,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+                marketing: params.lp_token_marketing,
+            })?,
+            funds: vec![],
+            admin: None,
+            label: String::from("Novaswap LP token"),
+        }
+        .into(),
+        id: INSTANTIATE_TOKEN_REPLY_ID,
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }];
+
+    Ok(Response::new().add_submessages(sub_msg))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    if config.pool_info.liquidity_token != Addr::unchecked("") {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let res = cw_utils::parse_reply_instantiate_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    config.pool_info.liquidity_token = deps.api.addr_validate(res.contract_address.as_str())?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("liquidity_token_addr", config.pool_info.liquidity_token))
+}
+
+pub struct SwapParams {
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+}
+
+// Exposes all the execute functions.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::Swap {
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            let to_addr = if let Some(addr) = to {
+                Some(deps.api.addr_validate(addr.as_str())?)
+            } else {
+                None
+            };
+
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                SwapParams {
+                    offer_asset,
+                    ask_asset_info,
+                    belief_price,
+                    max_spread,
+                    to: to_addr,
+                },
+            )
+        }
+        ExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            min_lp_to_receive,
+            receiver,
+            deadline,
+        } => {
+            assert_deadline(&env.block, deadline)?;
+
+            provide_liquidity(
+                deps,
+                env,
+                info,
+                assets,
+                slippage_tolerance,
+                min_lp_to_receive,
+                receiver,
+            )
+        }
+        ExecuteMsg::Skim { to } => skim(deps, env, to),
+        ExecuteMsg::Sync {} => sync(deps, env, info),
+    }
+}
+
+fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let contract_addr = info.sender.clone();
+
+    match from_binary(&msg.msg) {
+        Ok(Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            let authorized = config.pool_info.asset_infos.iter().any(|asset_info| {
+                matches!(asset_info, AssetInfo::Token { contract_addr } if contract_addr == &info.sender)
+            });
+
+            if !authorized {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let to_addr = if let Some(to_addr) = to {
+                Some(deps.api.addr_validate(to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let sender = deps.api.addr_validate(msg.sender.as_str())?;
+
+            swap(
+                deps,
+                env,
+                info,
+                sender,
+                SwapParams {
+                    offer_asset: Asset {
+                        info: AssetInfo::Token { contract_addr },
+                        amount: msg.amount,
+                    },
+                    ask_asset_info,
+                    belief_price,
+                    max_spread,
+                    to: to_addr,
+                },
+            )
+        }
+        Ok(Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out,
+            receiver,
+            deadline,
+        }) => {
+            assert_deadline(&env.block, deadline)?;
+
+            withdraw_liquidity(
+                deps,
+                info,
+                Addr::unchecked(msg.sender),
+                msg.amount,
+                min_assets_out,
+                receiver,
+            )
+        }
+        Err(err) => Err(ContractError::Std(err)),
+    }
+}
+
+// Returns the index of `asset_info` in the pool
+fn asset_index(pools: &[Asset], asset_info: &AssetInfo) -> Result<usize, ContractError> {
+    pools
+        .iter()
+        .position(|pool| pool.info.equal(asset_info))
+        .ok_or(ContractError::AssetMismatch {})
+}
+
+fn provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    min_lp_to_receive: Option<Uint128>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    // check the amount listed in messages are equal to actually received native coin.
+    for asset in assets.iter() {
+        asset.info.check(deps.api)?;
+        asset.assert_sent_native_token_balance(&info)?;
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_deposits_allowed(&config)?;
+
+    let pools = load_reserves(deps.storage, &config)?;
+
+    // get the amount of coins the user want to deposit, in the order of the pool.
+    let mut deposits = vec![Uint128::zero(); pools.len()];
+    let mut listed = vec![false; pools.len()];
+    for asset in assets.iter() {
+        let idx = asset_index(&pools, &asset.info)?;
+        if listed[idx] {
+            return Err(ContractError::DoublingAssets {});
+        }
+        listed[idx] = true;
+        deposits[idx] = asset.amount;
+    }
+
+    if deposits.iter().all(|deposit| deposit.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (i, pool) in pools.iter().enumerate() {
+        if deposits[i].is_zero() && pool.amount.is_zero() {
+            return Err(ContractError::InvalidProvideLPsWithSingleToken {});
+        }
+
+        if !deposits[i].is_zero() {
+            if let AssetInfo::Token { contract_addr } = &pool.info {
+                // Add TransferFrom message to messages.
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount: deposits[i],
+                    })?,
+                    funds: vec![],
+                }))
+            }
+        }
+    }
+
+    // decimals of each token.
+    let precisions = query_precisions(&deps.querier, &pools)?;
+    let greater_precision = greatest_precision(&precisions)?;
+
+    let pool_amounts: Vec<Uint128> = pools.iter().map(|pool| pool.amount).collect();
+    let old_balances = normalize_amounts(&pool_amounts, &precisions, greater_precision)?;
+    let deposit_amounts = normalize_amounts(&deposits, &precisions, greater_precision)?;
+    let mut new_balances = old_balances
+        .iter()
+        .zip(deposit_amounts.iter())
+        .map(|(balance, deposit)| {
+            Ok(Uint128::new(*balance)
+                .checked_add(Uint128::new(*deposit))?
+                .u128())
+        })
+        .collect::<StdResult<Vec<u128>>>()?;
+
+    // total supply of liquidity token
+    let total_supply = query_supply(&deps.querier, config.pool_info.liquidity_token.clone())?;
+
+    let leverage = compute_current_amp(&config, &env)?
+        .checked_mul(pools.len() as u64)
+        .ok_or(ContractError::MathOverflow {})?;
+    let compute_invariant = |balances: &[u128]| compute_d(leverage, balances);
+
+    let mut fee_amounts = vec![Uint128::zero(); pools.len()];
+    let share = if total_supply.is_zero() {
+        // If I'm a first liquidity provider.
+        let lp_token_precision = query_token_precision(
+            &deps.querier,
+            AssetInfo::Token {
+                contract_addr: config.pool_info.liquidity_token.clone(),
+            },
+        )?;
+
+        adjust_precision(
+            Uint128::new(compute_invariant(&new_balances)?),
+            greater_precision,
+            lp_token_precision,
+        )?
+    } else {
+        let d_before_addition_liquidity = compute_invariant(&old_balances)?;
+        let d_after_addition_liquidity = compute_invariant(&new_balances)?;
+
+        if d_before_addition_liquidity >= d_after_addition_liquidity {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // charge the imbalance fee, otherwise a lopsided deposit followed by
+        // a proportional withdrawal would be a swap without fees.
+        let fees = compute_imbalance_fees(
+            &old_balances,
+            &new_balances,
+            d_before_addition_liquidity,
+            d_after_addition_liquidity,
+            compute_imbalance_fee_rate(compute_fee_rate(&config), pools.len()),
+        );
+        for (i, fee) in fees.iter().enumerate() {
+            fee_amounts[i] =
+                adjust_precision(Uint128::new(*fee), greater_precision, precisions[i])?;
+            new_balances[i] = Uint128::new(new_balances[i])
+                .checked_sub(Uint128::new(*fee))?
+                .u128();
+        }
+
+        let d_after_fee = compute_invariant(&new_balances)?;
+
+        if d_before_addition_liquidity >= d_after_fee {
+            return Err(ContractError::LiquidityAmountTooSmall {});
+        }
+
+        // the fee stays in the pool and is credited to the existing providers
+        // before minting, so the depositor does not get a part of it back.
+        let share = total_supply.multiply_ratio(
+            d_after_fee - d_before_addition_liquidity,
+            d_before_addition_liquidity + (d_after_addition_liquidity - d_after_fee),
+        );
+
+        // Assert that slippage tolerance is respected
+        let deposit_sum = deposit_amounts
+            .iter()
+            .try_fold(Uint128::zero(), |sum, deposit| {
+                sum.checked_add(Uint128::new(*deposit))
+            })?;
+        let expected_share = total_supply.multiply_ratio(deposit_sum, d_before_addition_liquidity);
+        assert_slippage_tolerance(slippage_tolerance, share, expected_share)?;
+
+        share
+    };
+
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    if let Some(min_lp_to_receive) = min_lp_to_receive {
+        if share < min_lp_to_receive {
+            return Err(ContractError::MinLpAmountAssertion {
+                minimum: min_lp_to_receive.u128(),
+                received: share.u128(),
+            });
+        }
+    }
+
+    // the imbalance fees stay in the pool, so the whole deposit is added to the reserves
+    let reserves = pools
+        .iter()
+        .zip(deposits.iter())
+        .map(|(pool, deposit)| pool.amount.checked_add(*deposit))
+        .collect::<Result<Vec<Uint128>, _>>()?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    // mint lp token
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    messages.push(mint_liquidity_token_message(
+        &config,
+        deps.api.addr_validate(receiver.as_str())?,
+        share,
+    )?);
+
+    let deposits_evaluated: Vec<String> = pools
+        .iter()
+        .zip(deposits.iter())
+        .map(|(pool, deposit)| format!("{}{}", deposit, pool.info))
+        .collect();
+    let fee_amounts: Vec<String> = pools
+        .iter()
+        .zip(fee_amounts.iter())
+        .map(|(pool, fee)| format!("{}{}", fee, pool.info))
+        .collect();
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "provide_liquidity"),
+        attr("sender", info.sender.as_str()),
+        attr("receiver", receiver.as_str()),
+        attr("deposits_evaluated", deposits_evaluated.join(", ")),
+        attr("share", share.to_string()),
+        attr("fee_amounts", fee_amounts.join(", ")),
+    ]))
+}
+
+fn withdraw_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    min_assets_out: Option<Vec<Asset>>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pool_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => sender.clone(),
+    };
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    let refund_assets: Vec<Asset> = get_share_in_assets(&pools, amount, total_share);
+
+    if let Some(min_assets_out) = min_assets_out {
+        for min_asset in min_assets_out.iter() {
+            let refund_asset = &refund_assets[asset_index(&refund_assets, &min_asset.info)?];
+
+            if refund_asset.amount < min_asset.amount {
+                return Err(ContractError::WithdrawAmountTooSmall {
+                    minimum: min_asset.amount.u128(),
+                    received: refund_asset.amount.u128(),
+                    asset: min_asset.info.clone(),
+                });
+            }
+        }
+    }
+
+    let reserves = pools
+        .iter()
+        .zip(refund_assets.iter())
+        .map(|(pool, refund_asset)| pool.amount.checked_sub(refund_asset.amount))
+        .collect::<Result<Vec<Uint128>, _>>()?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for refund_asset in refund_assets.iter() {
+        if !refund_asset.amount.is_zero() {
+            messages.push(refund_asset.clone().transfer_msg(receiver.clone())?);
+        }
+    }
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.pool_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+
+    let refund_assets: Vec<String> = refund_assets.iter().map(|a| a.to_string()).collect();
+    let attributes = vec![
+        attr("action", "withdraw_liquidity"),
+        attr("sender", sender.as_str()),
+        attr("withdrawn_share", amount.to_string()),
+        attr("refund_assets", refund_assets.join(", ")),
+        attr("receiver", receiver.as_str()),
+    ];
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// Update configuration (mainly amplification parameter)
+fn update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+
+    let params = from_binary::<StablePoolUpdateParams>(&params)?;
+
+    // the factory updates the status of its pools in bulk
+    let is_factory_status_update = matches!(params, StablePoolUpdateParams::UpdateStatus { .. })
+        && info.sender == config.factory_addr;
+    if info.sender != factory_config.owner && !is_factory_status_update {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match params {
+        StablePoolUpdateParams::StartChangingAmp {
+            next_amp,
+            next_amp_time,
+        } => start_changing_amp(config, deps, env, next_amp, next_amp_time)?,
+        StablePoolUpdateParams::StopChangingAmp {} => stop_changing_amp(config, deps, env)?,
+        StablePoolUpdateParams::UpdateFee { fee_bps } => update_fee(config, deps, fee_bps)?,
+        StablePoolUpdateParams::UpdateRates { .. } => {
+            return Err(ContractError::RatesNotSupported {})
+        }
+        StablePoolUpdateParams::UpdateStatus { status } => update_status(config, deps, status)?,
+    }
+
+    Ok(Response::default())
+}
+
+fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    params: SwapParams,
+) -> Result<Response, ContractError> {
+    params.offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_swaps_allowed(&config)?;
+
+    let pools = load_reserves(deps.storage, &config)?;
+    let offer_idx = asset_index(&pools, &params.offer_asset.info)?;
+    let ask_idx = asset_index(&pools, &params.ask_asset_info)?;
+    if offer_idx == ask_idx {
+        return Err(ContractError::DoublingAssets {});
+    }
+
+    let offer_amount = params.offer_asset.amount;
+    let pool_amounts: Vec<Uint128> = pools.iter().map(|pool| pool.amount).collect();
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &pool_amounts,
+        &query_precisions(&deps.querier, &pools)?,
+        offer_idx,
+        ask_idx,
+        offer_amount,
+        compute_fee_rate(&config),
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    // Check the max spread limit
+    assert_max_spread(
+        params.belief_price,
+        params.max_spread,
+        offer_amount,
+        return_amount + commission_amount,
+        spread_amount,
+    )?;
+
+    let return_asset = Asset {
+        info: params.ask_asset_info.clone(),
+        amount: return_amount,
+    };
+
+    let receiver = params.to.unwrap_or_else(|| sender.clone());
+    let mut messages = vec![return_asset.transfer_msg(receiver.clone())?];
+
+    // Send the protocol share of the commission to the fee address
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pool_info.pair_type.clone(),
+    )?;
+    let maker_fee_amount = compute_maker_fee(&fee_info, commission_amount);
+    if let Some(fee_address) = fee_info.fee_address {
+        if !maker_fee_amount.is_zero() {
+            let maker_fee = Asset {
+                info: params.ask_asset_info.clone(),
+                amount: maker_fee_amount,
+            };
+            messages.push(maker_fee.transfer_msg(fee_address)?);
+        }
+    }
+
+    // the sent maker fee leaves the pool, the rest of the commission stays in the reserves
+    let mut reserves: Vec<Uint128> = pool_amounts;
+    reserves[offer_idx] = reserves[offer_idx].checked_add(offer_amount)?;
+    reserves[ask_idx] = reserves[ask_idx]
+        .checked_sub(return_amount)?
+        .checked_sub(maker_fee_amount)?;
+    RESERVES.save(deps.storage, &reserves)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap")
+        .add_attribute("sender", sender.as_str())
+        .add_attribute("receiver", receiver.as_str())
+        .add_attribute("offer_asset", params.offer_asset.info.to_string())
+        .add_attribute("ask_asset", params.ask_asset_info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("maker_fee_amount", maker_fee_amount.to_string()))
+}
+
+// Sends the balances of the pool above its reserves to `to`.
+fn skim(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let to = deps.api.addr_validate(&to)?;
+
+    let reserves = load_reserves(deps.storage, &config)?;
+    let balances: Vec<Asset> = config
+        .pool_info
+        .query_pools(&deps.querier, env.contract.address)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut skimmed: Vec<String> = vec![];
+    for (balance, reserve) in balances.iter().zip(reserves.iter()) {
+        let surplus = Asset {
+            info: balance.info.clone(),
+            amount: balance.amount.saturating_sub(reserve.amount),
+        };
+        if !surplus.amount.is_zero() {
+            skimmed.push(surplus.to_string());
+            messages.push(surplus.transfer_msg(to.clone())?);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "skim")
+        .add_attribute("to", to)
+        .add_attribute("assets", skimmed.join(", ")))
+}
+
+// Sets the reserves of the pool to its balances.
+// Only the factory owner can sync, as the tokens sent to the pool are credited to the providers.
+fn sync(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
+    if info.sender != factory_config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let balances: Vec<Asset> = config
+        .pool_info
+        .query_pools(&deps.querier, env.contract.address)?;
+    RESERVES.save(
+        deps.storage,
+        &balances.iter().map(|balance| balance.amount).collect(),
+    )?;
+
+    let balances: Vec<String> = balances.iter().map(|balance| balance.to_string()).collect();
+    Ok(Response::new()
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", balances.join(", ")))
+}
+
+// Exposes all query functions.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::PoolInfo {} => to_binary(&query_pool_info(deps)?),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::Share { amount } => to_binary(&query_share(deps, amount)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_binary(&query_simulation(deps, env, offer_asset, ask_asset_info)?),
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_binary(&query_reverse_simulation(
+            deps,
+            env,
+            offer_asset_info,
+            ask_asset,
+        )?),
+    }
+}
+
+// Returns pool info
+pub fn query_pool_info(deps: Deps) -> StdResult<PoolInfo> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(config.pool_info)
+}
+
+// Returns the amount of assets in the pool contract as well as the amount of LP.
+pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (assets, total_supply) = pool_info(deps, &config)?;
+
+    Ok(PoolResponse {
+        assets,
+        total_supply,
+    })
+}
+
+// Returns the amount of assets owned within the pool with the amount of LP tokens.
+pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps, &config)?;
+
+    Ok(get_share_in_assets(&pools, amount, total_share))
+}
+
+// Returns the indexes of the offer and ask assets in the pool
+fn swap_indexes(
+    pools: &[Asset],
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+) -> StdResult<(usize, usize)> {
+    let offer_idx = asset_index(pools, offer_asset_info)
+        .map_err(|_| StdError::generic_err("Given offer asset doesn't belong to the pool"))?;
+    let ask_idx = asset_index(pools, ask_asset_info)
+        .map_err(|_| StdError::generic_err("Given ask asset doesn't belong to the pool"))?;
+
+    if offer_idx == ask_idx {
+        return Err(StdError::generic_err(
+            "Offer and ask assets must be different",
+        ));
+    }
+
+    Ok((offer_idx, ask_idx))
+}
+
+// Returns information about a swap simulation
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+) -> StdResult<SimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, &config)?;
+    let (offer_idx, ask_idx) = swap_indexes(&pools, &offer_asset.info, &ask_asset_info)?;
+    let pool_amounts: Vec<Uint128> = pools.iter().map(|pool| pool.amount).collect();
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &pool_amounts,
+        &query_precisions(&deps.querier, &pools)?,
+        offer_idx,
+        ask_idx,
+        offer_asset.amount,
+        compute_fee_rate(&config),
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        config.factory_addr.clone(),
+        config.pool_info.pair_type.clone(),
+    )?;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        maker_fee_amount: compute_maker_fee(&fee_info, commission_amount),
+    })
+}
+
+// Returns information about a reverse swap simulation
+pub fn query_reverse_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset_info: AssetInfo,
+    ask_asset: Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let (pools, _) = pool_info(deps, &config)?;
+    let (offer_idx, ask_idx) = swap_indexes(&pools, &offer_asset_info, &ask_asset.info)?;
+    let pool_amounts: Vec<Uint128> = pools.iter().map(|pool| pool.amount).collect();
+
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        &pool_amounts,
+        &query_precisions(&deps.querier, &pools)?,
+        offer_idx,
+        ask_idx,
+        ask_asset.amount,
+        compute_fee_rate(&config),
+        compute_current_amp(&config, &env)?,
+    )?;
+
+    Ok(ReverseSimulationResponse {
+        offer_amount,
+        spread_amount,
+        commission_amount,
+    })
+}
+
+// Returns current configuration
+pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse {
+        status: config.status,
+        params: Some(to_binary(&StablePoolConfig {
+            amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_bps: config.fee_bps,
+        })?),
+    })
+}
+
+// Used for contract migration.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    if contract_version.contract != CONTRACT_NAME {
+        return Err(ContractError::MigrationError {});
+    }
+
+    let stored_version: Version = contract_version
+        .version
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| ContractError::MigrationError {})?;
+    if stored_version > new_version {
+        return Err(ContractError::MigrationError {});
+    }
+
+    // Pools instantiated before the reserves were tracked start from their balances.
+    if RESERVES.may_load(deps.storage)?.is_none() {
+        let config: Config = CONFIG.load(deps.storage)?;
+        let pools: Vec<Asset> = config
+            .pool_info
+            .query_pools(&deps.querier, env.contract.address)?;
+        RESERVES.save(
+            deps.storage,
+            &pools.iter().map(|pool| pool.amount).collect(),
+        )?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("previous_contract_version", contract_version.version),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
+}
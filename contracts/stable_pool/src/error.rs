@@ -0,0 +1,130 @@
+use crate::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use crate::utils::MAX_FEE_BPS;
+use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError};
+use novaswap::asset::AssetInfo;
+use novaswap::pool::{MAX_POOL_ASSETS, MIN_POOL_ASSETS};
+use thiserror::Error;
+
+/// ## Description
+/// This enum describes stableswap pool contract errors!
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Doubling assets in asset infos")]
+    DoublingAssets {},
+
+    #[error(
+        "The number of assets in a pool must be between {} and {}",
+        MIN_POOL_ASSETS,
+        MAX_POOL_ASSETS
+    )]
+    InvalidNumberOfAssets {},
+
+    #[error("Event of zero transfer")]
+    InvalidZeroAmount {},
+
+    #[error("Insufficient amount of liquidity")]
+    LiquidityAmountTooSmall {},
+
+    #[error("Withdrawn amount is less than the minimum, minimum: {minimum}, received: {received}, asset: {asset}")]
+    WithdrawAmountTooSmall {
+        minimum: u128,
+        received: u128,
+        asset: AssetInfo,
+    },
+
+    #[error("Provided spread amount exceeds allowed limit")]
+    AllowedSpreadAssertion {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Operation exceeds max splippage tolerance")]
+    MaxSlippageAssertion {},
+
+    #[error("Provided slippage tolerance exceeds allowed limit")]
+    AllowedSlippageAssertion {},
+
+    #[error("Minted LP amount is less than the minimum, minimum: {minimum}, received: {received}")]
+    MinLpAmountAssertion { minimum: u128, received: u128 },
+
+    #[error("Given asset doesn't belong to the pool")]
+    AssetMismatch {},
+
+    #[error(
+        "Amp coefficient must be greater than 0 and less than or equal to {}",
+        MAX_AMP
+    )]
+    IncorrectAmp {},
+
+    #[error(
+        "The difference between the old and new amp value must not exceed {} times",
+        MAX_AMP_CHANGE
+    )]
+    MaxAmpChangeAssertion {},
+
+    #[error(
+        "Amp coefficient cannot be changed more often than once per {} seconds",
+        MIN_AMP_CHANGING_TIME
+    )]
+    MinAmpChangingTimeAssertion {},
+
+    #[error("Fee must be less than or equal to {} bps", MAX_FEE_BPS)]
+    IncorrectFee {},
+
+    #[error("Redemption rates are not supported by the pool")]
+    RatesNotSupported {},
+
+    #[error("You need to provide init params")]
+    InitParamsNotFound {},
+
+    #[error("It is not possible to provide liquidity without every token for an empty pool")]
+    InvalidProvideLPsWithSingleToken {},
+
+    #[error("Swaps are paused in this pool")]
+    SwapsPaused {},
+
+    #[error("The pool only allows withdrawals")]
+    WithdrawOnly {},
+
+    #[error("The transaction deadline has passed")]
+    Expired {},
+
+    #[error("The stableswap invariant did not converge")]
+    NonConvergence {},
+
+    #[error("Arithmetic overflow in the pool math")]
+    MathOverflow {},
+
+    #[error("Insufficient reserves, requested: {requested}, available: {available}")]
+    InsufficientReserves { requested: u128, available: u128 },
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
+
+impl From<ConversionOverflowError> for ContractError {
+    fn from(o: ConversionOverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
+
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
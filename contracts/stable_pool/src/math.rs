@@ -0,0 +1,310 @@
+/// Stableswap Algorithm for N coins
+/// reference: https://miguelmota.com/blog/understanding-stableswap-curve/
+use crate::error::ContractError;
+use crate::state::Config;
+use cosmwasm_std::{Env, StdResult, Uint128};
+use novaswap::U256;
+use std::convert::TryFrom;
+
+const ITERATIONS: u8 = 64;
+
+pub const MAX_AMP: u64 = 1_000_000;
+pub const MAX_AMP_CHANGE: u64 = 10;
+pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
+pub const AMP_PRECISION: u64 = 100;
+
+/// ## Description
+/// Calculates the ask amount (the amount of tokens swapped to).
+/// ## Params
+/// * **balances** is a slice of [`u128`]. These are the amounts of every token currently in the pool.
+///
+/// * **offer_idx** is an object of type [`usize`]. This is the index of the offer token.
+///
+/// * **ask_idx** is an object of type [`usize`]. This is the index of the ask token.
+///
+/// * **offer_amount** is an object of type [`u128`]. This is the amount of offer tokens to swap.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_ask_amount(
+    balances: &[u128],
+    offer_idx: usize,
+    ask_idx: usize,
+    offer_amount: u128,
+    amp: u64,
+) -> Result<u128, ContractError> {
+    let leverage = amp
+        .checked_mul(balances.len() as u64)
+        .ok_or(ContractError::MathOverflow {})?;
+    let d = compute_d(leverage, balances)?;
+
+    let mut new_balances = balances.to_vec();
+    new_balances[offer_idx] = new_balances[offer_idx]
+        .checked_add(offer_amount)
+        .ok_or(ContractError::MathOverflow {})?;
+
+    let new_ask_pool = compute_y(leverage, &new_balances, ask_idx, d)?;
+
+    // The rounding of D can leave the new balance a unit above the pool for dust amounts
+    Ok(balances[ask_idx].saturating_sub(new_ask_pool))
+}
+
+/// ## Description
+/// Calculates the amount to be swapped (the offer amount).
+/// ## Params
+/// * **balances** is a slice of [`u128`]. These are the amounts of every token currently in the pool.
+///
+/// * **offer_idx** is an object of type [`usize`]. This is the index of the offer token.
+///
+/// * **ask_idx** is an object of type [`usize`]. This is the index of the ask token.
+///
+/// * **ask_amount** is an object of type [`u128`]. This is the amount of ask tokens to receive.
+///
+/// * **amp** is an object of type [`u64`]. This is the pool's amplification parameter.
+pub fn calc_offer_amount(
+    balances: &[u128],
+    offer_idx: usize,
+    ask_idx: usize,
+    ask_amount: u128,
+    amp: u64,
+) -> Result<u128, ContractError> {
+    // The whole ask pool can never be bought, its price grows without bound
+    if ask_amount >= balances[ask_idx] {
+        return Err(ContractError::InsufficientReserves {
+            requested: ask_amount,
+            available: balances[ask_idx],
+        });
+    }
+
+    let leverage = amp
+        .checked_mul(balances.len() as u64)
+        .ok_or(ContractError::MathOverflow {})?;
+    let d = compute_d(leverage, balances)?;
+
+    let mut new_balances = balances.to_vec();
+    new_balances[ask_idx] -= ask_amount;
+
+    let new_offer_pool = compute_y(leverage, &new_balances, offer_idx, d)?;
+
+    // `compute_y` rounds down, one more unit makes sure the pool is not short of the invariant
+    Ok(new_offer_pool
+        .saturating_sub(balances[offer_idx])
+        .saturating_add(1))
+}
+
+/// ## Description
+/// Computes the stableswap invariant (D) of N coins.
+///
+/// * **Equation**
+///
+/// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
+///
+/// ## Params
+/// * **leverage** is an object of type [`u64`]. This is the amplification multiplied by the number of coins.
+///
+/// * **balances** is a slice of [`u128`]. These are the amounts of every token in the pool.
+pub fn compute_d(leverage: u64, balances: &[u128]) -> Result<u128, ContractError> {
+    let n_coins = U256::from(balances.len());
+    let balances_times_coins = balances
+        .iter()
+        .map(|balance| {
+            U256::from(*balance)
+                .checked_mul(n_coins)?
+                .checked_add(U256::one())
+        })
+        .collect::<Option<Vec<U256>>>()
+        .ok_or(ContractError::MathOverflow {})?;
+    let sum_x = balances
+        .iter()
+        .try_fold(0u128, |sum, balance| sum.checked_add(*balance))
+        .ok_or(ContractError::MathOverflow {})?; // sum(x_i), a.k.a S
+
+    if sum_x == 0 {
+        return Ok(0);
+    }
+
+    let mut d_previous: U256 = U256::zero();
+    let mut d: U256 = sum_x.into();
+
+    // Newton's method to approximate D
+    for _ in 0..ITERATIONS {
+        let d_product =
+            compute_d_product(&d, &balances_times_coins).ok_or(ContractError::MathOverflow {})?;
+        d_previous = d;
+        // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
+        d = calculate_step(&d, leverage, sum_x, &d_product, n_coins)
+            .ok_or(ContractError::MathOverflow {})?;
+        // Equality with the precision of 1
+        if d == d_previous {
+            break;
+        }
+    }
+
+    if !has_converged(&d, &d_previous) {
+        return Err(ContractError::NonConvergence {});
+    }
+    u128::try_from(d).map_err(|_| ContractError::MathOverflow {})
+}
+
+/// ## Description
+/// Computes D**(n+1) / (n**n * prod(x_i)) from the amounts of each coin multiplied by n.
+fn compute_d_product(d: &U256, balances_times_coins: &[U256]) -> Option<U256> {
+    let mut d_product = *d;
+    for balance_times_coins in balances_times_coins.iter() {
+        d_product = d_product
+            .checked_mul(*d)?
+            .checked_div(*balance_times_coins)?;
+    }
+    Some(d_product)
+}
+
+/// ## Description
+/// Helper function used to calculate the D invariant as a last step in the `compute_d` public function.
+///
+/// * **Equation**:
+///
+/// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) * initial_d + (n_coins + 1) * d_product)
+fn calculate_step(
+    initial_d: &U256,
+    leverage: u64,
+    sum_x: u128,
+    d_product: &U256,
+    n_coins: U256,
+) -> Option<U256> {
+    let leverage_mul = U256::from(leverage).checked_mul(sum_x.into())? / AMP_PRECISION;
+    let d_p_mul = d_product.checked_mul(n_coins)?;
+
+    let l_val = leverage_mul.checked_add(d_p_mul)?.checked_mul(*initial_d)?;
+
+    let leverage_sub =
+        initial_d.checked_mul((leverage.checked_sub(AMP_PRECISION)?).into())? / AMP_PRECISION;
+    let n_coins_sum = d_product.checked_mul(n_coins.checked_add(U256::one())?)?;
+
+    let r_val = leverage_sub.checked_add(n_coins_sum)?;
+
+    l_val.checked_div(r_val)
+}
+
+/// ## Description
+/// Computes the balance of the coin at `target_idx` which keeps the invariant at `d_val`
+/// given the balances of all other coins.
+///
+/// * **Solve for y**
+///
+/// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+///
+/// y**2 + b*y = c
+pub fn compute_y(
+    leverage: u64,
+    balances: &[u128],
+    target_idx: usize,
+    d_val: u128,
+) -> Result<u128, ContractError> {
+    // Upscale to U256
+    let leverage: U256 = leverage.into();
+    let d_val: U256 = d_val.into();
+
+    let (b, c) = compute_y_coefficients(&leverage, balances, target_idx, &d_val)
+        .ok_or(ContractError::MathOverflow {})?;
+
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y_prev: U256 = U256::zero();
+    let mut y = d_val;
+    for _ in 0..ITERATIONS {
+        y_prev = y;
+        y = compute_y_step(&y, &b, &c, &d_val).ok_or(ContractError::MathOverflow {})?;
+        if y == y_prev {
+            break;
+        }
+    }
+
+    if !has_converged(&y, &y_prev) {
+        return Err(ContractError::NonConvergence {});
+    }
+    u128::try_from(y).map_err(|_| ContractError::MathOverflow {})
+}
+
+/// ## Description
+/// Returns the coefficients b and c of the equation solved by `compute_y`.
+fn compute_y_coefficients(
+    leverage: &U256,
+    balances: &[u128],
+    target_idx: usize,
+    d_val: &U256,
+) -> Option<(U256, U256)> {
+    let n_coins = U256::from(balances.len());
+
+    // sum' and prod' are taken over all the balances except the target one
+    // c =  D ** (n + 1) / (n ** (2 * n) * prod' * A)
+    let mut c = *d_val;
+    let mut sum = U256::zero();
+    for (i, balance) in balances.iter().enumerate() {
+        if i == target_idx {
+            continue;
+        }
+        let balance = U256::from(*balance);
+        sum = sum.checked_add(balance)?;
+        c = c
+            .checked_mul(*d_val)?
+            .checked_div(balance.checked_mul(n_coins)?)?;
+    }
+    c = c
+        .checked_mul(*d_val)?
+        .checked_mul(U256::from(AMP_PRECISION))?
+        .checked_div(leverage.checked_mul(n_coins)?)?;
+
+    // b = sum' - (A*n**n - 1) * D / (A * n**n)
+    let b = sum.checked_add(
+        d_val
+            .checked_mul(U256::from(AMP_PRECISION))?
+            .checked_div(*leverage)?,
+    )?;
+
+    Some((b, c))
+}
+
+/// ## Description
+/// A Newton step of `compute_y`: y = (y**2 + c) / (2 * y + b - D)
+fn compute_y_step(y: &U256, b: &U256, c: &U256, d_val: &U256) -> Option<U256> {
+    (y.checked_mul(*y)?.checked_add(*c)?).checked_div(
+        y.checked_mul(U256::from(2u8))?
+            .checked_add(*b)?
+            .checked_sub(*d_val)?,
+    )
+}
+
+/// ## Description
+/// Returns whether the last two estimates of Newton's method are at most one unit apart.
+/// The integer rounding can make the estimates alternate around the root instead of settling.
+fn has_converged(current: &U256, previous: &U256) -> bool {
+    let difference = if current > previous {
+        *current - *previous
+    } else {
+        *previous - *current
+    };
+    difference <= U256::one()
+}
+
+pub fn compute_current_amp(config: &Config, env: &Env) -> StdResult<u64> {
+    let block_time = env.block.time.seconds();
+
+    if block_time < config.next_amp_time {
+        let elapsed_time =
+            Uint128::from(block_time).checked_sub(Uint128::from(config.init_amp_time))?;
+        let time_range =
+            Uint128::from(config.next_amp_time).checked_sub(Uint128::from(config.init_amp_time))?;
+        let init_amp = Uint128::from(config.init_amp);
+        let next_amp = Uint128::from(config.next_amp);
+
+        if config.next_amp > config.init_amp {
+            let amp_range = next_amp - init_amp;
+            let res = init_amp + (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res.u128() as u64)
+        } else {
+            let amp_range = init_amp - next_amp;
+            let res = init_amp - (amp_range * elapsed_time).checked_div(time_range)?;
+            Ok(res.u128() as u64)
+        }
+    } else {
+        Ok(config.next_amp)
+    }
+}
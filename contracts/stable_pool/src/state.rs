@@ -0,0 +1,31 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::Item;
+use novaswap::pair::PoolStatus;
+use novaswap::pairinfo::PoolInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub pool_info: PoolInfo,
+    pub factory_addr: Addr,
+
+    // used for stableswap pool algorithm.
+    pub init_amp: u64,
+    pub init_amp_time: u64,
+    pub next_amp: u64,
+    pub next_amp_time: u64,
+
+    // swap fee charged by this pool, in basis points.
+    pub fee_bps: u16,
+
+    // operations allowed by the pool, pools stored before the status default to active.
+    #[serde(default)]
+    pub status: PoolStatus,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// the reserves of the pool, in the order of `pool_info.asset_infos`. Tokens sent to the pool
+// outside of its messages are not part of the reserves, they can be skimmed or synced.
+pub const RESERVES: Item<Vec<Uint128>> = Item::new("reserves");
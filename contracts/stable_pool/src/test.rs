@@ -0,0 +1,1086 @@
+use crate::{
+    contract::{
+        execute, instantiate, migrate, query_config, query_pool, query_pool_info,
+        query_reverse_simulation, query_share, query_simulation, reply,
+    },
+    error::ContractError,
+    math::{calc_ask_amount, calc_offer_amount, compute_d, compute_y, AMP_PRECISION},
+    mock_querier::{mock_dependencies, WasmMockQuerier},
+    state::RESERVES,
+    utils::assert_max_spread,
+};
+use cosmwasm_std::{
+    attr,
+    testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, OwnedDeps, Reply, StdError,
+    SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_utils::Expiration;
+use novaswap::{
+    asset::{Asset, AssetInfo},
+    pair::{PoolStatus, PoolStatusUpdateParams, StablePoolParams, StablePoolUpdateParams},
+    pairinfo::PairType,
+    pool::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg},
+};
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+struct MsgInstantiateContractResponse {
+    #[prost(string, tag = "1")]
+    pub contract_address: ::prost::alloc::string::String,
+    #[prost(bytes, tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let data = MsgInstantiateContractResponse {
+        contract_address: contract_addr,
+        data: vec![],
+    };
+
+    let mut encoded_instantiate_reply = Vec::<u8>::with_capacity(data.encoded_len());
+    data.encode(&mut encoded_instantiate_reply).unwrap();
+
+    let reply_msg = Reply {
+        id: msg_id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(encoded_instantiate_reply.into()),
+        }),
+    };
+
+    reply(deps, mock_env(), reply_msg).unwrap();
+}
+
+fn pool_asset_infos() -> Vec<AssetInfo> {
+    vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0001"),
+        },
+    ]
+}
+
+fn instantiate_msg(asset_infos: Vec<AssetInfo>) -> InstantiateMsg {
+    InstantiateMsg {
+        asset_infos,
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                fee_bps: None,
+                rates: None,
+                lp_token_marketing: None,
+            })
+            .unwrap(),
+        ),
+    }
+}
+
+// uusd, asset0000, asset0001 를 각각 `amount` 만큼 가지고 있는 풀을 만든다.
+fn pool_with_liquidity(
+    amount: u128,
+    total_share: u128,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(amount),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(amount))],
+        ),
+        (
+            &String::from("asset0001"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(amount))],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(total_share))],
+        ),
+    ]);
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(pool_asset_infos()),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+    store_reserves(deps.as_mut(), &[amount, amount, amount]);
+
+    deps
+}
+
+// 풀의 리저브를 잔고와 무관하게 지정한다.
+fn store_reserves(deps: DepsMut, reserves: &[u128]) {
+    RESERVES
+        .save(
+            deps.storage,
+            &reserves
+                .iter()
+                .map(|reserve| Uint128::new(*reserve))
+                .collect(),
+        )
+        .unwrap();
+}
+
+/// 3개의 자산으로 풀을 생성하고 풀 정보를 조회한다.
+#[test]
+fn proper_initialization() {
+    let deps = pool_with_liquidity(0, 0);
+
+    let pool_info = query_pool_info(deps.as_ref()).unwrap();
+    assert_eq!(pool_info.asset_infos, pool_asset_infos());
+    assert_eq!(pool_info.liquidity_token, Addr::unchecked("liquidity0000"));
+    assert_eq!(pool_info.pair_type, PairType::StablePool {});
+}
+
+/// 자산의 개수가 범위를 벗어나거나 중복된 자산이 있으면 풀을 생성할 수 없다.
+#[test]
+fn initialization_should_fail() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(pool_asset_infos()[..1].to_vec()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidNumberOfAssets {});
+
+    let too_many_assets: Vec<AssetInfo> = (0..6)
+        .map(|i| AssetInfo::NativeToken {
+            denom: format!("denom{}", i),
+        })
+        .collect();
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(too_many_assets),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidNumberOfAssets {});
+
+    let mut doubled_assets = pool_asset_infos();
+    doubled_assets.push(AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    });
+    let err = instantiate(deps.as_mut(), env, info, instantiate_msg(doubled_assets)).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+}
+
+/// 첫 유동성 공급은 모든 자산을 필요로 하고, 이후 균형 잡힌 공급은 수수료 없이 비율대로 LP 를 받는다.
+#[test]
+fn provide_liquidity() {
+    let mut deps = pool_with_liquidity(0, 0);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    )]);
+
+    // first provider must deposit every asset
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: pool_asset_infos()[0].clone(),
+                amount: Uint128::new(100_000000),
+            },
+            Asset {
+                info: pool_asset_infos()[1].clone(),
+                amount: Uint128::new(100_000000),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidProvideLPsWithSingleToken {});
+
+    // assets which are not in the pool are rejected
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            amount: Uint128::new(100_000000),
+        }],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    // the same asset can not be listed twice
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: pool_asset_infos()[0].clone(),
+                amount: Uint128::new(100_000000),
+            },
+            Asset {
+                info: pool_asset_infos()[0].clone(),
+                amount: Uint128::new(100_000000),
+            },
+        ],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    let all_assets = |amount: u128| -> Vec<Asset> {
+        pool_asset_infos()
+            .into_iter()
+            .map(|info| Asset {
+                info,
+                amount: Uint128::new(amount),
+            })
+            .collect()
+    };
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: all_assets(100_000000),
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: Some("addr0001".to_string()),
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000000),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // token deposits are transferred, LP tokens are minted to the receiver
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: String::from("addr0000"),
+                recipient: String::from(MOCK_CONTRACT_ADDR),
+                amount: Uint128::new(100_000000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0001"),
+                amount: Uint128::new(300_000000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // a balanced deposit mints proportionally and pays no imbalance fee
+    let mut deps = pool_with_liquidity(100_000000, 300_000000);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(150_000000),
+        }],
+    )]);
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: all_assets(50_000000),
+        slippage_tolerance: Some(Decimal::permille(1)),
+        min_lp_to_receive: Some(Uint128::new(150_000000)),
+        receiver: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(50_000000),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.attributes[4].value, "150000000");
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::new(150_000000); 3]
+    );
+    assert_eq!(
+        res.attributes[5].value,
+        "0uusd, 0asset0000, 0asset0001".to_string()
+    );
+
+    // a one-sided deposit pays the imbalance fee
+    let mut deps = pool_with_liquidity(100_000000, 300_000000);
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: pool_asset_infos()[2].clone(),
+            amount: Uint128::new(30_000000),
+        }],
+        slippage_tolerance: None,
+        min_lp_to_receive: Some(Uint128::new(30_000000)),
+        receiver: None,
+        deadline: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    match err {
+        ContractError::MinLpAmountAssertion { minimum, received } => {
+            assert_eq!(minimum, 30_000000);
+            assert!(received < 30_000000 && received > 29_000000);
+        }
+        _ => panic!("Must return MinLpAmountAssertion error"),
+    }
+}
+
+/// 첫번째 자산이 아닌 자산끼리도 스왑할 수 있다.
+#[test]
+fn swap_between_any_assets() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    deps.querier.with_fee_info(Some("fee_address"), 5000);
+
+    // native -> asset0001
+    let offer_amount = Uint128::new(10_000000);
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: pool_asset_infos()[0].clone(),
+            amount: offer_amount,
+        },
+        pool_asset_infos()[2].clone(),
+    )
+    .unwrap();
+    // the pool is balanced, so the return is the offer minus the 0.3% fee and a small spread
+    assert!(simulation.return_amount < Uint128::new(9_970000));
+    assert!(simulation.return_amount > Uint128::new(9_960000));
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000_000000) + offer_amount,
+        }],
+    )]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: pool_asset_infos()[0].clone(),
+            amount: offer_amount,
+        },
+        ask_asset_info: pool_asset_infos()[2].clone(),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0001"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: simulation.return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    // half of the commission goes to the fee address
+    assert_eq!(
+        simulation.maker_fee_amount,
+        simulation.commission_amount * Decimal::percent(50)
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("asset0001"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("fee_address"),
+                amount: simulation.maker_fee_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    // the returned amount and the sent maker fee leave the reserves
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![
+            Uint128::new(1000_000000) + offer_amount,
+            Uint128::new(1000_000000),
+            Uint128::new(1000_000000) - simulation.return_amount - simulation.maker_fee_amount,
+        ]
+    );
+
+    // asset0000 -> uusd through the cw20 hook
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: pool_asset_infos()[1].clone(),
+            amount: offer_amount,
+        },
+        pool_asset_infos()[0].clone(),
+    )
+    .unwrap();
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(Uint128::new(1000_000000) + offer_amount),
+            )],
+        ),
+        (
+            &String::from("asset0001"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(1000_000000),
+            )],
+        ),
+    ]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: pool_asset_infos()[0].clone(),
+            belief_price: None,
+            max_spread: None,
+            to: Some(String::from("addr0001")),
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: simulation.return_amount,
+            }],
+        })
+    );
+
+    // only the tokens of the pool can call the hook
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0002", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // offer and ask assets must be different
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: pool_asset_infos()[0].clone(),
+            amount: offer_amount,
+        },
+        ask_asset_info: pool_asset_infos()[0].clone(),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+}
+
+/// 역방향 시뮬레이션으로 구한 제공량으로 정방향 시뮬레이션을 하면 원하는 양을 받아야 한다.
+#[test]
+fn reverse_simulation() {
+    let deps = pool_with_liquidity(1000_000000, 3000_000000);
+
+    let ask_amount = Uint128::new(10_000000);
+    let reverse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        pool_asset_infos()[1].clone(),
+        Asset {
+            info: pool_asset_infos()[2].clone(),
+            amount: ask_amount,
+        },
+    )
+    .unwrap();
+    assert!(reverse.offer_amount > ask_amount);
+
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: pool_asset_infos()[1].clone(),
+            amount: reverse.offer_amount,
+        },
+        pool_asset_infos()[2].clone(),
+    )
+    .unwrap();
+    assert!(simulation.return_amount >= ask_amount);
+    assert!(simulation.return_amount - ask_amount < Uint128::new(10));
+
+    // the offer amount is rounded up, so swapping it never returns less than asked
+    for ask_amount in [1u128, 7, 999, 123_457, 99_999999, 500_000001] {
+        let reverse = query_reverse_simulation(
+            deps.as_ref(),
+            mock_env(),
+            pool_asset_infos()[0].clone(),
+            Asset {
+                info: pool_asset_infos()[1].clone(),
+                amount: Uint128::new(ask_amount),
+            },
+        )
+        .unwrap();
+        let simulation = query_simulation(
+            deps.as_ref(),
+            mock_env(),
+            Asset {
+                info: pool_asset_infos()[0].clone(),
+                amount: reverse.offer_amount,
+            },
+            pool_asset_infos()[1].clone(),
+        )
+        .unwrap();
+        assert!(simulation.return_amount >= Uint128::new(ask_amount));
+    }
+
+    let err = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            amount: ask_amount,
+        },
+        pool_asset_infos()[2].clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Given offer asset doesn't belong to the pool")
+    );
+}
+
+/// 유동성 회수 시 최소 수령량보다 적게 받으면 실패한다.
+#[test]
+fn withdraw_liquidity() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(pool.total_supply, Uint128::new(3000_000000));
+    let share = query_share(deps.as_ref(), Uint128::new(300_000000)).unwrap();
+    assert_eq!(share.len(), 3);
+    assert!(share
+        .iter()
+        .all(|asset| asset.amount == Uint128::new(100_000000)));
+
+    let withdraw_msg = |min_assets_out: Option<Vec<Asset>>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(300_000000),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_out,
+                receiver: None,
+                deadline: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(Some(vec![Asset {
+            info: pool_asset_infos()[2].clone(),
+            amount: Uint128::new(100_000001),
+        }])),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawAmountTooSmall {
+            minimum: 100_000001,
+            received: 100_000000,
+            asset: pool_asset_infos()[2].clone(),
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        withdraw_msg(None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(Some(vec![Asset {
+            info: pool_asset_infos()[2].clone(),
+            amount: Uint128::new(100_000000),
+        }])),
+    )
+    .unwrap();
+    // three refunds and a burn
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::new(900_000000); 3]
+    );
+    assert_eq!(
+        res.messages[3].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: Uint128::new(300_000000),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.attributes[3].value,
+        "100000000uusd, 100000000asset0000, 100000000asset0001"
+    );
+}
+
+/// 균형 잡힌 풀의 D 는 잔고의 합과 같고, compute_y 는 D 를 보존하는 잔고를 돌려준다.
+#[test]
+fn n_coin_math() {
+    let leverage = 100 * AMP_PRECISION * 4;
+    let balances = [1_000_000u128, 1_000_000, 1_000_000, 1_000_000];
+    let d = compute_d(leverage, &balances).unwrap();
+    assert!(d.abs_diff(4_000_000) <= 1);
+
+    let imbalanced = [1_500_000u128, 500_000, 1_000_000, 1_000_000];
+    let d_imbalanced = compute_d(leverage, &imbalanced).unwrap();
+    assert!(d_imbalanced < 4_000_000);
+
+    let y = compute_y(leverage, &imbalanced, 1, d_imbalanced).unwrap();
+    assert!(y.abs_diff(500_000) <= 1);
+
+    // the ask amount of a small swap in a balanced pool is close to the offer amount
+    let ask_amount = calc_ask_amount(&balances, 0, 3, 1_000, 100 * AMP_PRECISION).unwrap();
+    assert!((998..=1_000).contains(&ask_amount));
+
+    // the whole ask pool can not be bought
+    let err = calc_offer_amount(&balances, 0, 3, 1_000_000, 100 * AMP_PRECISION).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientReserves {
+            requested: 1_000_000,
+            available: 1_000_000,
+        }
+    );
+
+    // balances beyond the range of the solver fail instead of panicking
+    let err = compute_d(leverage, &[u128::MAX, 1, 1, 1]).unwrap_err();
+    assert_eq!(err, ContractError::MathOverflow {});
+}
+
+/// belief price 가 0 이면 패닉 대신 에러를 돌려준다.
+#[test]
+fn zero_belief_price() {
+    let err = assert_max_spread(
+        Some(Decimal::zero()),
+        Some(Decimal::percent(1)),
+        Uint128::new(1_000000),
+        Uint128::new(1_000000),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Belief price must be greater than zero"
+        ))
+    );
+}
+
+/// 풀에 직접 보낸 토큰은 skim 으로 꺼내거나, 팩토리 owner 의 sync 로 리저브에 반영한다.
+#[test]
+fn skim_and_sync() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    // 100 uusd 가 리저브에 반영되지 않은 채로 풀에 들어와 있다
+    store_reserves(deps.as_mut(), &[900_000000, 1000_000000, 1000_000000]);
+
+    let res = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::new(900_000000));
+
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000000),
+            }],
+        }))]
+    );
+    // skim 은 리저브를 바꾸지 않는다
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![
+            Uint128::new(900_000000),
+            Uint128::new(1000_000000),
+            Uint128::new(1000_000000),
+        ]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::Sync {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "sync"),
+            attr(
+                "reserves",
+                "1000000000uusd, 1000000000asset0000, 1000000000asset0001"
+            ),
+        ]
+    );
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::new(1000_000000); 3]
+    );
+
+    // 리저브와 잔고가 같으면 skim 할 것이 없다
+    let msg = ExecuteMsg::Skim {
+        to: String::from("addr0001"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    assert!(res.messages.is_empty());
+}
+
+/// 리저브를 저장하기 전에 생성된 풀은 마이그레이션할 때 잔고로 리저브를 채운다.
+#[test]
+fn migrate_seeds_reserves() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    RESERVES.remove(deps.as_mut().storage);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap(),
+        vec![Uint128::new(1000_000000); 3]
+    );
+
+    // 이미 저장된 리저브는 그대로 둔다
+    store_reserves(deps.as_mut(), &[900_000000, 1000_000000, 1000_000000]);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        RESERVES.load(deps.as_ref().storage).unwrap()[0],
+        Uint128::new(900_000000)
+    );
+}
+
+/// 팩토리 owner 와 팩토리는 풀의 상태를 바꿀 수 있고, 멈춘 풀은 스왑과 예치를 거부한다.
+#[test]
+fn pool_status() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    let env = mock_env();
+
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.status, PoolStatus::Active);
+
+    // only the factory owner can pause the pool
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateStatus {
+            status: PoolStatus::SwapsPaused,
+        })
+        .unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), env.clone(), mock_info("owner0000", &[]), msg).unwrap();
+    let res = query_config(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.status, PoolStatus::SwapsPaused);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: pool_asset_infos()[0].clone(),
+            amount: Uint128::new(10u128),
+        },
+        ask_asset_info: pool_asset_infos()[1].clone(),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10u128),
+        }],
+    );
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::SwapsPaused {});
+
+    // the factory updates the status of its pools, but no other parameters
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFee { fee_bps: 1 }).unwrap(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), mock_info("factory", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&PoolStatusUpdateParams::UpdateStatus {
+            status: PoolStatus::WithdrawOnly,
+        })
+        .unwrap(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("factory", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: pool_asset_infos()[1].clone(),
+            amount: Uint128::new(10u128),
+        }],
+        slippage_tolerance: None,
+        min_lp_to_receive: None,
+        receiver: None,
+        deadline: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::WithdrawOnly {});
+
+    // withdrawals remain available
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            min_assets_out: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(300_000000),
+    });
+    let res = execute(deps.as_mut(), env, mock_info("liquidity0000", &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 4);
+}
+
+/// 기한이 지난 스왑, 예치, 회수는 실패한다.
+#[test]
+fn deadline() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+    let env = mock_env();
+    let now = env.block.time;
+
+    let swap_msg = |deadline: Option<Expiration>| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: pool_asset_infos()[0].clone(),
+            amount: Uint128::new(10_000000),
+        },
+        ask_asset_info: pool_asset_infos()[1].clone(),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(10_000000),
+        }],
+    );
+
+    // the deadline passes at the given time or height
+    for deadline in [
+        Expiration::AtTime(now),
+        Expiration::AtHeight(env.block.height),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            swap_msg(Some(deadline)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProvideLiquidity {
+            assets: vec![Asset {
+                info: pool_asset_infos()[1].clone(),
+                amount: Uint128::new(10_000000),
+            }],
+            slippage_tolerance: None,
+            min_lp_to_receive: None,
+            receiver: None,
+            deadline: Some(Expiration::AtTime(now.minus_seconds(1))),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                ask_asset_info: pool_asset_infos()[0].clone(),
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                deadline: Some(Expiration::AtTime(now.minus_seconds(1))),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("liquidity0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            amount: Uint128::new(10_000000),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                min_assets_out: None,
+                receiver: None,
+                deadline: Some(Expiration::AtHeight(env.block.height)),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    // messages within the deadline execute
+    execute(
+        deps.as_mut(),
+        env,
+        info,
+        swap_msg(Some(Expiration::AtTime(now.plus_seconds(1)))),
+    )
+    .unwrap();
+}
+
+/// 다른 컨트랙트나 하위 버전으로의 마이그레이션은 막고, 마이그레이션하면 버전을 올린다.
+#[test]
+fn migrate_contract_version() {
+    let mut deps = pool_with_liquidity(1000_000000, 3000_000000);
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-stable", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-stable-pool", "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, ContractError::MigrationError {});
+
+    set_contract_version(deps.as_mut().storage, "novaswap-stable-pool", "0.9.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", "0.9.0"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
@@ -0,0 +1,467 @@
+use crate::error::ContractError;
+use crate::math::{
+    calc_ask_amount, calc_offer_amount, compute_current_amp, AMP_PRECISION, MAX_AMP,
+    MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
+};
+use crate::state::{Config, CONFIG, RESERVES};
+use cosmwasm_std::{
+    to_binary, Addr, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut, Env, Fraction, QuerierWrapper,
+    StdError, StdResult, Storage, Uint128, Uint256, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_utils::Expiration;
+use novaswap::asset::Asset;
+use novaswap::factory::FeeInfoResponse;
+use novaswap::pair::{PoolStatus, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
+use novaswap::querier::{query_supply, query_token_precision};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+pub const DEFAULT_FEE_BPS: u16 = 30;
+pub const MAX_FEE_BPS: u16 = 1000;
+const FEE_DENOMINATOR: u128 = 10000;
+
+// Returns the fee rate of the pool
+pub fn compute_fee_rate(config: &Config) -> Decimal {
+    Decimal::from_ratio(Uint128::from(config.fee_bps), Uint128::new(FEE_DENOMINATOR))
+}
+
+// Returns the fee rate charged on the imbalanced part of a deposit.
+// fee * n / (4 * (n - 1)), so that a deposit followed by a withdrawal costs as much as a swap.
+pub fn compute_imbalance_fee_rate(fee_rate: Decimal, n_coins: usize) -> Decimal {
+    let n_coins = n_coins as u128;
+    fee_rate * Decimal::from_ratio(n_coins, 4 * (n_coins - 1))
+}
+
+// Returns the part of the commission which goes to the factory fee address.
+// Nothing is taken when the fee address is not set.
+pub fn compute_maker_fee(fee_info: &FeeInfoResponse, commission_amount: Uint128) -> Uint128 {
+    if fee_info.fee_address.is_some() {
+        commission_amount * fee_info.maker_fee_rate
+    } else {
+        Uint128::zero()
+    }
+}
+
+// Returns a [`ContractError`] when the deadline of the message has passed at the current block.
+pub fn assert_deadline(
+    block: &BlockInfo,
+    deadline: Option<Expiration>,
+) -> Result<(), ContractError> {
+    match deadline {
+        Some(deadline) if deadline.is_expired(block) => Err(ContractError::Expired {}),
+        _ => Ok(()),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow swaps.
+pub fn assert_swaps_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active => Ok(()),
+        PoolStatus::SwapsPaused => Err(ContractError::SwapsPaused {}),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Returns a [`ContractError`] when the pool status does not allow deposits.
+pub fn assert_deposits_allowed(config: &Config) -> Result<(), ContractError> {
+    match config.status {
+        PoolStatus::Active | PoolStatus::SwapsPaused => Ok(()),
+        PoolStatus::WithdrawOnly => Err(ContractError::WithdrawOnly {}),
+    }
+}
+
+// Updates the operations allowed by the pool.
+pub fn update_status(
+    mut config: Config,
+    deps: DepsMut,
+    status: PoolStatus,
+) -> Result<(), ContractError> {
+    config.status = status;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Returns `amount * ratio`, failing instead of panicking on overflow.
+pub fn checked_mul_decimal(amount: Uint128, ratio: Decimal) -> Result<Uint128, ContractError> {
+    amount
+        .checked_multiply_ratio(ratio.numerator(), ratio.denominator())
+        .map_err(|_| ContractError::MathOverflow {})
+}
+
+// Returns `value * numerator / denominator` rounded up.
+pub fn multiply_ratio_ceil(
+    value: Uint128,
+    numerator: Uint128,
+    denominator: Uint128,
+) -> Result<Uint128, ContractError> {
+    if denominator.is_zero() {
+        return Err(ContractError::MathOverflow {});
+    }
+
+    let product = value.full_mul(numerator);
+    let denominator = Uint256::from(denominator);
+    let mut result = product / denominator;
+    if !(product % denominator).is_zero() {
+        result += Uint256::from(1u128);
+    }
+    Uint128::try_from(result).map_err(|_| ContractError::MathOverflow {})
+}
+
+// Returns the greatest precision among the pool assets, every amount is normalized to it.
+pub fn greatest_precision(precisions: &[u8]) -> StdResult<u8> {
+    precisions
+        .iter()
+        .max()
+        .copied()
+        .ok_or_else(|| StdError::generic_err("The pool has no assets"))
+}
+
+// Returns a [`ContractError`] on failure.
+// If `belief_price` and `max_spread` are both specified, we compute a new spread,
+// otherwise we just use the swap spread to check `max_spread`.
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let inverse_belief_price =
+            Decimal::checked_from_ratio(Decimal::one().numerator(), belief_price.numerator())
+                .map_err(|_| StdError::generic_err("Belief price must be greater than zero"))?;
+        let expected_return = checked_mul_decimal(offer_amount, inverse_belief_price)?;
+        let spread_amount = expected_return
+            .checked_sub(return_amount)
+            .unwrap_or_else(|_| Uint128::zero());
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount.checked_add(spread_amount)?)
+        > max_spread
+    {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
+
+// Returns a [`ContractError`] on failure.
+// `expected_share` is the amount of LP tokens minted for the deposit valued at par,
+// `share` may fall short of it by at most `slippage_tolerance`.
+pub fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    share: Uint128,
+    expected_share: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(slippage_tolerance) = slippage_tolerance {
+        if slippage_tolerance > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+            return Err(ContractError::AllowedSlippageAssertion {});
+        }
+
+        if share < expected_share * (Decimal::one() - slippage_tolerance) {
+            return Err(ContractError::MaxSlippageAssertion {});
+        }
+    }
+
+    Ok(())
+}
+
+// Start changing the AMP value. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn start_changing_amp(
+    mut config: Config,
+    deps: DepsMut,
+    env: Env,
+    next_amp: u64,
+    next_amp_time: u64,
+) -> Result<(), ContractError> {
+    if next_amp == 0 || next_amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp {});
+    }
+
+    let current_amp = compute_current_amp(&config, &env)?;
+
+    let next_amp_with_precision = next_amp * AMP_PRECISION;
+
+    if next_amp_with_precision * MAX_AMP_CHANGE < current_amp
+        || next_amp_with_precision > current_amp * MAX_AMP_CHANGE
+    {
+        return Err(ContractError::MaxAmpChangeAssertion {});
+    }
+
+    let block_time = env.block.time.seconds();
+
+    if block_time < config.init_amp_time + MIN_AMP_CHANGING_TIME
+        || next_amp_time < block_time + MIN_AMP_CHANGING_TIME
+    {
+        return Err(ContractError::MinAmpChangingTimeAssertion {});
+    }
+
+    config.init_amp = current_amp;
+    config.next_amp = next_amp_with_precision;
+    config.init_amp_time = block_time;
+    config.next_amp_time = next_amp_time;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Stop changing the AMP value. Returns [`Ok`].
+pub fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<()> {
+    let current_amp = compute_current_amp(&config, &env)?;
+    let block_time = env.block.time.seconds();
+
+    config.init_amp = current_amp;
+    config.next_amp = current_amp;
+    config.init_amp_time = block_time;
+    config.next_amp_time = block_time;
+
+    // now (block_time < next_amp_time) is always False, so we return the saved AMP
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// Update the swap fee of the pool. Returns a [`ContractError`] on failure, otherwise returns [`Ok`].
+pub fn update_fee(mut config: Config, deps: DepsMut, fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::IncorrectFee {});
+    }
+
+    config.fee_bps = fee_bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+// adjust given value from `current_precision` to `new_precision`
+pub fn adjust_precision(
+    value: Uint128,
+    current_precision: u8,
+    new_precision: u8,
+) -> StdResult<Uint128> {
+    Ok(match current_precision.cmp(&new_precision) {
+        Ordering::Equal => value,
+        Ordering::Less => value.checked_mul(Uint128::new(
+            // value = value * 10^(new - curr)
+            10_u128.pow((new_precision - current_precision) as u32),
+        ))?,
+        Ordering::Greater => value.checked_div(Uint128::new(
+            // value = value / 10^(curr - new)
+            10_u128.pow((current_precision - new_precision) as u32),
+        ))?,
+    })
+}
+
+// Returns the decimals of every asset in the pool
+pub fn query_precisions(querier: &QuerierWrapper, pools: &[Asset]) -> StdResult<Vec<u8>> {
+    pools
+        .iter()
+        .map(|pool| query_token_precision(querier, pool.info.clone()))
+        .collect()
+}
+
+// Scales `amounts` from their `precisions` to the common `greater_precision`
+pub fn normalize_amounts(
+    amounts: &[Uint128],
+    precisions: &[u8],
+    greater_precision: u8,
+) -> StdResult<Vec<u128>> {
+    amounts
+        .iter()
+        .zip(precisions)
+        .map(|(amount, precision)| {
+            Ok(adjust_precision(*amount, *precision, greater_precision)?.u128())
+        })
+        .collect()
+}
+
+// Mint LP tokens for a beneficiary
+pub fn mint_liquidity_token_message(
+    config: &Config,
+    recipient: Addr,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    let lp_token = config.pool_info.liquidity_token.clone();
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: lp_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+pub fn get_share_in_assets(pools: &[Asset], amount: Uint128, total_share: Uint128) -> Vec<Asset> {
+    let mut share_ratio = Decimal::zero();
+    if !total_share.is_zero() {
+        share_ratio = Decimal::from_ratio(amount, total_share);
+    }
+
+    pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: pool.amount * share_ratio,
+        })
+        .collect()
+}
+
+pub fn pool_info(deps: Deps, config: &Config) -> StdResult<(Vec<Asset>, Uint128)> {
+    let pools = load_reserves(deps.storage, config)?;
+    let total_supply: Uint128 =
+        query_supply(&deps.querier, config.pool_info.liquidity_token.clone())?;
+
+    Ok((pools, total_supply))
+}
+
+// Returns the tracked reserves of the pool, balances above them are not counted.
+pub fn load_reserves(storage: &dyn Storage, config: &Config) -> StdResult<Vec<Asset>> {
+    let reserves = RESERVES.load(storage)?;
+
+    Ok(config
+        .pool_info
+        .asset_infos
+        .iter()
+        .zip(reserves)
+        .map(|(info, amount)| Asset {
+            info: info.clone(),
+            amount,
+        })
+        .collect())
+}
+
+/// compute swap from `offer_idx` to `ask_idx` then returns return_amount, spread_amount and commission_amount.
+pub fn compute_swap(
+    pools: &[Uint128],
+    precisions: &[u8],
+    offer_idx: usize,
+    ask_idx: usize,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    // offer => ask
+
+    let greater_precision = greatest_precision(precisions)?;
+    let balances = normalize_amounts(pools, precisions, greater_precision)?;
+    let offer_amount = adjust_precision(offer_amount, precisions[offer_idx], greater_precision)?;
+
+    let return_amount = Uint128::new(calc_ask_amount(
+        &balances,
+        offer_idx,
+        ask_idx,
+        offer_amount.u128(),
+        amp,
+    )?);
+
+    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. So any exchange rate <1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+
+    let commission_amount: Uint128 = return_amount * commission_rate;
+
+    // The commission will be absorbed by the pool
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount)?;
+
+    let ask_precision = precisions[ask_idx];
+    let return_amount = adjust_precision(return_amount, greater_precision, ask_precision)?;
+    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
+    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+
+    Ok((return_amount, spread_amount, commission_amount))
+}
+
+// Returns an amount of offer assets for a specified amount of ask assets.
+// The offer amount is rounded up in favor of the pool, so swapping it returns at least `ask_amount`.
+pub fn compute_offer_amount(
+    pools: &[Uint128],
+    precisions: &[u8],
+    offer_idx: usize,
+    ask_idx: usize,
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+    amp: u64,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    // ask => offer
+
+    let greater_precision = greatest_precision(precisions)?;
+    let balances = normalize_amounts(pools, precisions, greater_precision)?;
+    let ask_amount = adjust_precision(ask_amount, precisions[ask_idx], greater_precision)?;
+
+    // The least amount which is still at least `ask_amount` after the commission,
+    // which is rounded down in `compute_swap`, is deducted
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let before_commission_deduction = if ask_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        (ask_amount - Uint128::new(1))
+            .checked_multiply_ratio(Decimal::one().atomics(), one_minus_commission.atomics())
+            .map_err(|_| ContractError::MathOverflow {})?
+            .checked_add(Uint128::new(1))?
+    };
+
+    let offer_amount = Uint128::new(calc_offer_amount(
+        &balances,
+        offer_idx,
+        ask_idx,
+        before_commission_deduction.u128(),
+        amp,
+    )?);
+
+    // We assume the assets should stay in a 1:1 ratio, so the true exchange rate is 1. Any exchange rate < 1 could be considered the spread
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+
+    let commission_amount = before_commission_deduction * commission_rate;
+
+    let ask_precision = precisions[ask_idx];
+    let offer_amount = multiply_ratio_ceil(
+        offer_amount,
+        Uint128::new(1),
+        Uint128::new(10_u128.pow((greater_precision - precisions[offer_idx]) as u32)),
+    )?;
+    let spread_amount = adjust_precision(spread_amount, greater_precision, ask_precision)?;
+    let commission_amount = adjust_precision(commission_amount, greater_precision, ask_precision)?;
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
+// Returns the imbalance fee charged on each balance for a liquidity operation
+// moving the pool from `old_balances` (invariant `d_before`) to `new_balances` (invariant `d_after`).
+pub fn compute_imbalance_fees(
+    old_balances: &[u128],
+    new_balances: &[u128],
+    d_before: u128,
+    d_after: u128,
+    imbalance_fee_rate: Decimal,
+) -> Vec<u128> {
+    old_balances
+        .iter()
+        .zip(new_balances)
+        .map(|(old_balance, new_balance)| {
+            let ideal_balance = Uint128::new(*old_balance).multiply_ratio(d_after, d_before);
+            let new_balance = Uint128::new(*new_balance);
+            let difference = if ideal_balance > new_balance {
+                ideal_balance - new_balance
+            } else {
+                new_balance - ideal_balance
+            };
+            (difference * imbalance_fee_rate).u128()
+        })
+        .collect()
+}
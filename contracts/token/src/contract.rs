@@ -1,4 +1,7 @@
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    attr, entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
+use cw2::{get_contract_version, set_contract_version};
 
 use cw20_base::contract::{
     execute as cw20_execute, instantiate as cw20_instantiate, query as cw20_query,
@@ -7,16 +10,29 @@ use cw20_base::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 use cw20_base::ContractError;
 
 use novaswap::token::MigrateMsg;
+use semver::Version;
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "novaswap-token";
+
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Contract name stored by cw20-base, tokens up to 1.0.0 were stored under this name.
+const CW20_BASE_CONTRACT_NAME: &str = "crates.io:cw20-base";
 
 /// ## Description
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    cw20_instantiate(deps, _env, _info, msg)
+    let res = cw20_instantiate(deps.branch(), _env, _info, msg)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
 }
 
 /// execute messages
@@ -37,7 +53,31 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 /// migration messsages
+/// rejects other contracts and downgrades, the token state is kept as is.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    let migration_error = || StdError::generic_err("Contract can't be migrated!");
+
+    if contract_version.contract == CONTRACT_NAME {
+        let stored_version: Version = contract_version
+            .version
+            .parse()
+            .map_err(|_| migration_error())?;
+        let new_version: Version = CONTRACT_VERSION.parse().map_err(|_| migration_error())?;
+        if stored_version > new_version {
+            return Err(migration_error().into());
+        }
+    } else if contract_version.contract != CW20_BASE_CONTRACT_NAME {
+        // tokens instantiated by cw20-base store the version of cw20-base, which is not
+        // comparable with the version of this contract
+        return Err(migration_error().into());
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("previous_contract_version", contract_version.version),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
 }
@@ -0,0 +1,135 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{attr, StdError, Uint128};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse, Cw20Coin, TokenInfoResponse};
+use cw20_base::contract::{instantiate as cw20_instantiate, query_balance, query_token_info};
+use cw20_base::msg::InstantiateMsg;
+use cw20_base::ContractError;
+use novaswap::token::MigrateMsg;
+
+use crate::contract::{instantiate, migrate};
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        name: "Novaswap LP token".to_string(),
+        symbol: "NLP".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: "addr0000".to_string(),
+            amount: Uint128::new(1000_000000),
+        }],
+        mint: None,
+        marketing: None,
+    }
+}
+
+fn migration_error() -> ContractError {
+    StdError::generic_err("Contract can't be migrated!").into()
+}
+
+/// 1.0.0 의 토큰은 cw20-base 의 이름으로 저장되어 있고, 마이그레이션 후에도 잔고와 정보가 유지된다.
+#[test]
+fn migrate_from_v100() {
+    let mut deps = mock_dependencies();
+    // tokens up to 1.0.0 were instantiated by cw20-base as is
+    cw20_instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+    let stored = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(stored.contract, "crates.io:cw20-base");
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", stored.version),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.contract, "novaswap-token");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    assert_eq!(
+        query_token_info(deps.as_ref()).unwrap(),
+        TokenInfoResponse {
+            name: "Novaswap LP token".to_string(),
+            symbol: "NLP".to_string(),
+            decimals: 6,
+            total_supply: Uint128::new(1000_000000),
+        }
+    );
+    assert_eq!(
+        query_balance(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        BalanceResponse {
+            balance: Uint128::new(1000_000000),
+        }
+    );
+
+    // migrating again keeps the state
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        query_balance(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        BalanceResponse {
+            balance: Uint128::new(1000_000000),
+        }
+    );
+}
+
+/// 다른 컨트랙트와 더 높은 버전으로부터의 마이그레이션은 거부한다.
+#[test]
+fn migrate_rejects_other_contracts_and_downgrades() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    set_contract_version(deps.as_mut().storage, "novaswap-pair-stable", "1.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, migration_error());
+
+    set_contract_version(deps.as_mut().storage, "novaswap-token", "9.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(err, migration_error());
+
+    set_contract_version(deps.as_mut().storage, "novaswap-token", "1.0.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+}
+
+/// cw20-base 로 저장된 토큰은 cw20-base 의 버전과 무관하게 이름만 확인하고 마이그레이션한다.
+#[test]
+fn migrate_ignores_cw20_base_version() {
+    let mut deps = mock_dependencies();
+    cw20_instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+
+    set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "9.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("previous_contract_version", "9.0.0"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .contract,
+        "novaswap-token"
+    );
+}
@@ -3,11 +3,12 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use cosmwasm_std::{
-    to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, MessageInfo, QuerierWrapper, StdError,
+    to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, MessageInfo, QuerierWrapper, StdError,
     StdResult, Uint128, WasmMsg,
 };
 
 use crate::querier::{query_balance, query_token_balance, query_token_symbol};
+use crate::token::is_valid_symbol;
 use cw20::Cw20ExecuteMsg;
 
 // Asset : 네이티브 코인과 CW20 토큰을 모두 표현하기 위한 구조체
@@ -89,6 +90,32 @@ impl Asset {
             })),
         }
     }
+
+    /// into [`CosmosMsg`] to send a token to the contract along with `msg`. Tokens are sent with
+    /// a cw20 `Send`, and native tokens are attached as funds to the execution of `msg`.
+    pub fn send_msg(self, contract: Addr, msg: Binary) -> StdResult<CosmosMsg> {
+        let amount = self.amount;
+
+        match &self.info {
+            AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: contract.to_string(),
+                    amount,
+                    msg,
+                })?,
+                funds: vec![],
+            })),
+            AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg,
+                funds: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            })),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -204,23 +231,70 @@ pub fn token_asset_info(contract_addr: Addr) -> AssetInfo {
 
 const TOKEN_SYMBOL_MAX_LENGTH: usize = 4;
 
-/// format lp token name for two asset
+/// The maximum length of a cw20 token symbol, see [`is_valid_symbol`]
+const LP_TOKEN_SYMBOL_MAX_LENGTH: usize = 12;
+
+/// format lp token name for the given assets
 pub fn format_lp_token_name(
-    asset_infos: [AssetInfo; 2],
+    asset_infos: &[AssetInfo],
     querier: &QuerierWrapper,
 ) -> StdResult<String> {
     let mut short_symbols: Vec<String> = vec![];
-    for asset_info in asset_infos {
-        let short_symbol = match asset_info {
-            AssetInfo::NativeToken { denom } => {
-                denom.chars().take(TOKEN_SYMBOL_MAX_LENGTH).collect()
-            }
-            AssetInfo::Token { contract_addr } => {
-                let token_symbol = query_token_symbol(querier, contract_addr)?;
-                token_symbol.chars().take(TOKEN_SYMBOL_MAX_LENGTH).collect()
-            }
-        };
+    for asset_info in asset_infos.iter() {
+        let short_symbol = query_asset_symbol(querier, asset_info)?
+            .chars()
+            .take(TOKEN_SYMBOL_MAX_LENGTH)
+            .collect();
+        short_symbols.push(short_symbol);
+    }
+    Ok(format!("{}-LP", short_symbols.join("-")).to_uppercase())
+}
+
+/// format lp token symbol for the given assets, e.g. `UUSD-SNT-LP` with the `LP` suffix.
+/// the asset symbols are stripped to letters and shortened so that the symbol is a valid cw20 symbol.
+pub fn format_lp_token_symbol(
+    asset_infos: &[AssetInfo],
+    querier: &QuerierWrapper,
+    suffix: &str,
+) -> StdResult<String> {
+    // every asset symbol is followed by a dash
+    let max_length = LP_TOKEN_SYMBOL_MAX_LENGTH
+        .checked_sub(suffix.len() + asset_infos.len())
+        .map(|length| length / asset_infos.len().max(1))
+        .filter(|length| *length > 0)
+        .ok_or_else(|| StdError::generic_err("The lp token symbol suffix is too long"))?;
+
+    let mut short_symbols: Vec<String> = vec![];
+    for asset_info in asset_infos.iter() {
+        let short_symbol: String = query_asset_symbol(querier, asset_info)?
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .take(max_length)
+            .collect();
+        if short_symbol.is_empty() {
+            return Err(StdError::generic_err(format!(
+                "Cannot derive a symbol for {}",
+                asset_info
+            )));
+        }
         short_symbols.push(short_symbol);
     }
-    Ok(format!("{}-{}-LP", short_symbols[0], short_symbols[1]).to_uppercase())
+
+    let symbol = format!("{}-{}", short_symbols.join("-"), suffix).to_uppercase();
+    if !is_valid_symbol(&symbol) {
+        return Err(StdError::generic_err(format!(
+            "Invalid lp token symbol {}",
+            symbol
+        )));
+    }
+
+    Ok(symbol)
+}
+
+/// Returns the denom of a native token or the symbol of a cw20 token.
+fn query_asset_symbol(querier: &QuerierWrapper, asset_info: &AssetInfo) -> StdResult<String> {
+    match asset_info {
+        AssetInfo::NativeToken { denom } => Ok(denom.to_string()),
+        AssetInfo::Token { contract_addr } => query_token_symbol(querier, contract_addr.clone()),
+    }
 }
@@ -3,9 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     asset::AssetInfo,
-    pairinfo::{PairConfig, PairInfo, PairType},
+    pair::PoolStatus,
+    pairinfo::{PairConfig, PairInfo, PairType, PoolInfo},
 };
-use cosmwasm_std::{Addr, Binary};
+use cosmwasm_std::{Addr, Binary, Decimal};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -17,6 +18,9 @@ pub struct InstantiateMsg {
 
     // owner : config 설정 관리하는 관리자 지갑 주소
     pub owner: String,
+
+    // fee_address : 스왑 수수료 중 프로토콜 몫을 받는 주소
+    pub fee_address: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -24,6 +28,7 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     UpdateConfig {
         token_code_id: Option<u64>,
+        fee_address: Option<String>,
     },
     UpdatePairConfig {
         config: PairConfig,
@@ -33,9 +38,18 @@ pub enum ExecuteMsg {
         asset_infos: [AssetInfo; 2],
         init_params: Option<Binary>,
     },
+    CreatePool {
+        pair_type: PairType,
+        asset_infos: Vec<AssetInfo>,
+        init_params: Option<Binary>,
+    },
     UpdateOwner {
         new_owner: String,
     },
+    UpdatePairsStatus {
+        pairs: Vec<String>,
+        status: PoolStatus,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -49,6 +63,16 @@ pub enum QueryMsg {
         start_after: Option<[AssetInfo; 2]>,
         limit: Option<u32>,
     },
+    FeeInfo {
+        pair_type: PairType,
+    },
+    Pool {
+        asset_infos: Vec<AssetInfo>,
+    },
+    Pools {
+        start_after: Option<Vec<AssetInfo>>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -56,6 +80,7 @@ pub struct ConfigResponse {
     pub owner: Addr,
     pub pair_configs: Vec<PairConfig>,
     pub token_code_id: u64,
+    pub fee_address: Option<Addr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -63,8 +88,31 @@ pub struct PairsResponse {
     pub pairs: Vec<PairInfo>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolsResponse {
+    pub pools: Vec<PoolInfo>,
+}
+
+// This structure holds the fee split a pair applies to its swap commission.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeInfoResponse {
+    /// The address that receives the protocol share of the commission
+    pub fee_address: Option<Addr>,
+    /// The share of the commission sent to `fee_address`
+    pub maker_fee_rate: Decimal,
+}
+
 // This structure stores the parameters used in a migration message.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MigrateMsg {
     pub params: Binary,
 }
+
+// This structure describes the `params` of a [`MigrateMsg`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MigrationParams {
+    /// The address that receives the protocol share of the commission, kept when not set
+    pub fee_address: Option<String>,
+    /// The maker fee share (in bps) of the pair configs stored before maker fees, defaults to 0
+    pub maker_fee_bps: Option<u16>,
+}
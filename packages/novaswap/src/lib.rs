@@ -2,6 +2,7 @@ pub mod asset;
 pub mod factory;
 pub mod pair;
 pub mod pairinfo;
+pub mod pool;
 pub mod querier;
 pub mod token;
 
@@ -2,9 +2,11 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::asset::{Asset, AssetInfo};
+use crate::token::InstantiateMarketingInfo;
 
-use cosmwasm_std::{Binary, Decimal, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
 
 /// The default swap slippage
 pub const DEFAULT_SLIPPAGE: &str = "0.005";
@@ -38,8 +40,14 @@ pub enum ExecuteMsg {
     ProvideLiquidity {
         /// The assets available in the pool
         assets: [Asset; 2],
+        /// The maximum allowed shortfall of the minted LP tokens against a deposit at par
+        slippage_tolerance: Option<Decimal>,
+        /// The minimum amount of LP tokens to receive
+        min_lp_to_receive: Option<Uint128>,
         /// The receiver of LP tokens
         receiver: Option<String>,
+        /// The block time or height after which the deposit fails
+        deadline: Option<Expiration>,
     },
     /// Swap operation
     Swap {
@@ -47,10 +55,44 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// A message executed on `to` with the return asset, which is sent with a cw20 `Send`
+        /// for tokens and attached as funds for native tokens
+        forward_msg: Option<Binary>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
+    },
+    /// Swap for an exact amount of the ask asset, refunding the part of the offer that is not needed
+    SwapExactOut {
+        /// The exact asset to receive
+        ask_asset: Asset,
+        /// The maximum amount of the offer asset to pay, it must match the native funds sent
+        max_offer_amount: Uint128,
+        to: Option<String>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
     },
     UpdateConfig {
         params: Binary,
     },
+    /// Lends assets of the pool to `callback_contract` and executes `msg` on it. The assets and
+    /// a flash fee at the swap fee rate must be sent back to the pair before the callback returns
+    FlashLoan {
+        assets: Vec<Asset>,
+        callback_contract: String,
+        msg: Binary,
+    },
+    /// Sends `ask_asset` to the caller and executes `callback` on it. The offer amount of a swap to
+    /// `ask_asset`, including the swap fee, must be paid to the pair before the callback returns
+    FlashSwap {
+        ask_asset: Asset,
+        callback: Binary,
+    },
+    /// Sends the balances of the pair above its tracked reserves to `to`
+    Skim {
+        to: String,
+    },
+    /// Sets the tracked reserves of the pair to its balances, only the factory owner can sync
+    Sync {},
 }
 
 /// This structure describes a CW20 hook message.
@@ -62,9 +104,42 @@ pub enum Cw20HookMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// A message executed on `to` with the return asset, which is sent with a cw20 `Send`
+        /// for tokens and attached as funds for native tokens
+        forward_msg: Option<Binary>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
+    },
+    /// Swap the sent tokens for an exact amount of the ask asset, the sent amount is the maximum
+    /// offer amount and the part that is not needed is refunded
+    SwapExactOut {
+        ask_asset: Asset,
+        to: Option<String>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
     },
     /// Withdraw liquidity from the pool
-    WithdrawLiquidity {},
+    WithdrawLiquidity {
+        /// The minimum amount of each asset to receive
+        min_assets_out: Option<[Asset; 2]>,
+        /// The receiver of the withdrawn assets
+        receiver: Option<String>,
+        /// The block time or height after which the withdrawal fails
+        deadline: Option<Expiration>,
+    },
+    /// Withdraw liquidity from the pool into a single asset
+    WithdrawLiquidityOneCoin {
+        asset_info: AssetInfo,
+        min_amount: Option<Uint128>,
+    },
+    /// Withdraw exact amounts of assets from the pool, burning only the LP tokens needed
+    /// and refunding the rest
+    WithdrawLiquidityImbalance {
+        /// The exact assets to receive
+        assets: [Asset; 2],
+        /// The maximum amount of LP tokens to burn, defaults to the amount sent
+        max_burn_amount: Option<Uint128>,
+    },
 }
 
 /// This structure describes the query messages available in the contract.
@@ -85,6 +160,26 @@ pub enum QueryMsg {
     ReverseSimulation { ask_asset: Asset },
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
     CumulativePrices {},
+    /// Returns the amount received for withdrawing LP tokens into one asset in a [`SimulateWithdrawOneCoinResponse`] object.
+    SimulateWithdrawOneCoin {
+        lp_amount: Uint128,
+        asset_info: AssetInfo,
+    },
+    /// Returns the assets refunded for withdrawing LP tokens in a [`SimulateWithdrawResponse`] object.
+    SimulateWithdraw { lp_amount: Uint128 },
+    /// Returns the redemption rates used by the pair in a [`RatesResponse`] object.
+    Rates {},
+    /// Returns the value of one LP token in the pool assets in a [`VirtualPriceResponse`] object.
+    VirtualPrice {},
+    /// Returns the marginal price of `offer_asset_info` in the other asset in a [`SpotPriceResponse`] object.
+    SpotPrice { offer_asset_info: AssetInfo },
+    /// Returns the cumulative prices `seconds_ago` from the current block in an [`ObserveResponse`] object.
+    Observe { seconds_ago: Vec<u64> },
+    /// Returns the average price of `asset_info` over the last `window_seconds` in a [`TwapPriceResponse`] object.
+    TwapPrice {
+        asset_info: AssetInfo,
+        window_seconds: u64,
+    },
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and the two assets in a specific pool.
@@ -101,6 +196,8 @@ pub struct PoolResponse {
 pub struct ConfigResponse {
     /// Last timestamp when the cumulative prices in the pool were updated
     pub block_time_last: u64,
+    /// The operations currently allowed by the pool
+    pub status: PoolStatus,
     /// The pool's parameters
     pub params: Option<Binary>,
 }
@@ -114,6 +211,8 @@ pub struct SimulationResponse {
     pub spread_amount: Uint128,
     /// The amount of fees charged by the transaction
     pub commission_amount: Uint128,
+    /// The part of the commission sent to the factory fee address
+    pub maker_fee_amount: Uint128,
 }
 
 /// This structure holds the parameters that are returned from a reverse swap simulation response.
@@ -127,6 +226,40 @@ pub struct ReverseSimulationResponse {
     pub commission_amount: Uint128,
 }
 
+/// This structure holds the value of one LP token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VirtualPriceResponse {
+    /// The pool invariant divided by the total amount of LP tokens
+    pub virtual_price: Decimal,
+}
+
+/// This structure holds the marginal price of an asset, fees are not included.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpotPriceResponse {
+    /// The asset being priced
+    pub offer_asset_info: AssetInfo,
+    /// The asset the price is expressed in
+    pub ask_asset_info: AssetInfo,
+    /// The amount of ask tokens for one offer token, in whole tokens
+    pub price: Decimal,
+}
+
+/// This structure holds the parameters that are returned from a withdrawal simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateWithdrawResponse {
+    /// The assets refunded by the withdrawal
+    pub refund_assets: [Asset; 2],
+}
+
+/// This structure holds the parameters that are returned from a single asset withdrawal simulation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateWithdrawOneCoinResponse {
+    /// The amount of the asset returned by the withdrawal
+    pub return_amount: Uint128,
+    /// The imbalance fee charged by the withdrawal
+    pub fee_amount: Uint128,
+}
+
 /// This structure is used to return a cumulative prices query response.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CumulativePricesResponse {
@@ -140,12 +273,118 @@ pub struct CumulativePricesResponse {
     pub price1_cumulative_last: Uint128,
 }
 
+/// This structure holds the cumulative prices of the pool at a point in time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Observation {
+    /// The block time of the observation in seconds
+    pub timestamp: u64,
+    /// The value for the token0 cumulative price
+    pub price0_cumulative: Uint128,
+    /// The value for the token1 cumulative price
+    pub price1_cumulative: Uint128,
+}
+
+/// This structure is used to return an observe query response.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObserveResponse {
+    /// The observations in the order of the requested `seconds_ago`
+    pub observations: Vec<Observation>,
+}
+
+/// This structure holds the time-weighted average price of an asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapPriceResponse {
+    /// The asset being priced
+    pub asset_info: AssetInfo,
+    /// The asset the price is expressed in
+    pub ask_asset_info: AssetInfo,
+    /// The average amount of ask tokens for one token of `asset_info` over the window
+    pub price: Decimal,
+}
+
+/// This structure holds constant product pool parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct XykPoolParams {
+    /// The swap fee charged by the pool in basis points (defaults to 30 bps)
+    pub fee_bps: Option<u16>,
+    /// The project, description and logo of the LP token
+    pub lp_token_marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// This structure stores a constant product pool's configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct XykPoolConfig {
+    /// The swap fee charged by the pool in basis points
+    pub fee_bps: u16,
+}
+
 /// This structure holds stableswap pool parameters.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct StablePoolParams {
     /// The current stableswap pool amplification
     pub amp: u64,
+    /// The swap fee charged by the pool in basis points (defaults to 30 bps)
+    pub fee_bps: Option<u16>,
+    /// The redemption rates of the assets, the assets trade 1:1 when not set
+    pub rates: Option<RateParams>,
+    /// The project, description and logo of the LP token
+    pub lp_token_marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// This structure describes the redemption rates of a stable pair's assets.
+/// Balances are valued at these rates before they enter the stableswap invariant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RateParams {
+    /// Where the rates are taken from
+    pub source: RateSource,
+    /// The maximum age of the rates in seconds, the rates never go stale when not set
+    pub max_rate_age: Option<u64>,
+}
+
+/// This enum describes where a stable pair takes the redemption rates of its assets from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateSource {
+    /// The rates are set by the factory owner, in the order of the pair's assets
+    Owner { rates: [Decimal; 2] },
+    /// The rates are queried from a contract implementing [`RateProviderQueryMsg`]
+    Provider { contract_addr: String },
+}
+
+/// This structure describes the query messages a rate provider contract must implement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateProviderQueryMsg {
+    /// Returns the redemption rate of an asset in a [`RateProviderResponse`] object.
+    Rate { asset_info: AssetInfo },
+}
+
+/// This structure holds the redemption rate returned by a rate provider.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateProviderResponse {
+    /// The value of one unit of the asset
+    pub rate: Decimal,
+    /// The time the rate was last updated, in seconds
+    pub updated_at: u64,
+}
+
+/// This structure holds the redemption rates used by a stable pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RatesResponse {
+    /// The assets of the pair
+    pub asset_infos: [AssetInfo; 2],
+    /// The redemption rate of each asset, in the order of `asset_infos`
+    pub rates: [Decimal; 2],
+    /// The time the oldest of the rates was updated, in seconds
+    pub updated_at: u64,
+    /// The rate provider contract, not set when the rates are set by the owner
+    pub rate_provider: Option<Addr>,
+    /// The maximum age of the rates in seconds
+    pub max_rate_age: Option<u64>,
 }
 
 /// This structure stores a stableswap pool's configuration.
@@ -154,6 +393,29 @@ pub struct StablePoolParams {
 pub struct StablePoolConfig {
     /// The stableswap pool amplification
     pub amp: Decimal,
+    /// The swap fee charged by the pool in basis points
+    pub fee_bps: u16,
+}
+
+/// This enum describes the operations allowed by a pool, withdrawals are always allowed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolStatus {
+    /// Swaps and deposits are allowed
+    #[default]
+    Active,
+    /// Deposits are allowed, swaps are halted
+    SwapsPaused,
+    /// Swaps and deposits are halted
+    WithdrawOnly,
+}
+
+/// This enum describes the pool status update accepted by the `UpdateConfig` of every pair type.
+/// It is sent by the factory to update pairs in bulk.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolStatusUpdateParams {
+    UpdateStatus { status: PoolStatus },
 }
 
 /// This enum stores the options available to start and stop changing a stableswap pool's amplification.
@@ -162,6 +424,72 @@ pub struct StablePoolConfig {
 pub enum StablePoolUpdateParams {
     StartChangingAmp { next_amp: u64, next_amp_time: u64 },
     StopChangingAmp {},
+    UpdateFee { fee_bps: u16 },
+    UpdateRates { rates: [Decimal; 2] },
+    UpdateStatus { status: PoolStatus },
+}
+
+/// This structure holds concentrated liquidity pool parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConcentratedPoolParams {
+    /// The pool amplification around the price scale
+    pub amp: u64,
+    /// The swap fee charged by the pool in basis points (defaults to 30 bps)
+    pub fee_bps: Option<u16>,
+    /// The initial price of the second asset in units of the first asset
+    pub initial_price_scale: Decimal,
+    /// The part of the profit which is not spent on repegging
+    pub allowed_extra_profit: Decimal,
+    /// The minimum relative step of a repeg towards the price oracle
+    pub adjustment_step: Decimal,
+    /// The half time of the price oracle moving average in seconds
+    pub ma_half_time: u64,
+    /// The project, description and logo of the LP token
+    pub lp_token_marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// This structure stores a concentrated liquidity pool's configuration and price state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConcentratedPoolConfig {
+    /// The pool amplification
+    pub amp: Decimal,
+    /// The swap fee charged by the pool in basis points
+    pub fee_bps: u16,
+    /// The price of the second asset the liquidity is concentrated around
+    pub price_scale: Decimal,
+    /// The exponential moving average of the traded prices
+    pub price_oracle: Decimal,
+    /// The price of the last trade
+    pub last_price: Decimal,
+    /// The growth of the virtual price since the pool was created, repegging included
+    pub xcp_profit: Decimal,
+    /// The value of one LP token in the balanced pool at the price scale
+    pub virtual_price: Decimal,
+    /// The part of the profit which is not spent on repegging
+    pub allowed_extra_profit: Decimal,
+    /// The minimum relative step of a repeg towards the price oracle
+    pub adjustment_step: Decimal,
+    /// The half time of the price oracle moving average in seconds
+    pub ma_half_time: u64,
+}
+
+/// This enum stores the options available to update a concentrated liquidity pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcentratedPoolUpdateParams {
+    UpdateFee {
+        fee_bps: u16,
+    },
+    UpdateRepegParams {
+        allowed_extra_profit: Option<Decimal>,
+        adjustment_step: Option<Decimal>,
+        ma_half_time: Option<u64>,
+    },
+    UpdateStatus {
+        status: PoolStatus,
+    },
 }
 
 /// This structure describes a migration message.
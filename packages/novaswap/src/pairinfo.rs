@@ -13,6 +13,10 @@ pub enum PairType {
     Xyk {},
     /// Stable pair type
     Stable {},
+    /// Stable pool type with up to [`crate::pool::MAX_POOL_ASSETS`] assets
+    StablePool {},
+    /// Concentrated liquidity pair type, a stableswap curve around a repegging price scale
+    Concentrated {},
 }
 
 /// This struct describes pair info of created pools;
@@ -43,12 +47,41 @@ impl PairInfo {
     }
 }
 
+/// This struct describes pool info of created multi-asset pools;
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolInfo {
+    pub asset_infos: Vec<AssetInfo>,
+    pub contract_addr: Addr,
+    pub liquidity_token: Addr,
+    pub pair_type: PairType,
+}
+
+impl PoolInfo {
+    pub fn query_pools(
+        &self,
+        querier: &QuerierWrapper,
+        contract_addr: Addr,
+    ) -> StdResult<Vec<Asset>> {
+        self.asset_infos
+            .iter()
+            .map(|asset_info| {
+                Ok(Asset {
+                    amount: asset_info.query_pool(querier, contract_addr.clone())?,
+                    info: asset_info.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
 // Return a name of each pool type
 impl Display for PairType {
     fn fmt(&self, fmt: &mut Formatter) -> Result {
         match self {
             PairType::Xyk {} => fmt.write_str("xyk"),
             PairType::Stable {} => fmt.write_str("stable"),
+            PairType::StablePool {} => fmt.write_str("stable_pool"),
+            PairType::Concentrated {} => fmt.write_str("concentrated"),
         }
     }
 }
@@ -60,7 +93,16 @@ pub struct PairConfig {
     pub code_id: u64,
     /// The pair type (provided in a [`PairType`])
     pub pair_type: PairType,
+    /// The share of the swap commission (in bps) sent to the factory fee address
+    pub maker_fee_bps: u16,
     /// Whether a pair type is disabled or not. If it is disabled, new pairs cannot be
     /// created, but existing ones can still read the pair configuration
     pub is_disabled: bool,
 }
+
+impl PairConfig {
+    /// Returns true if the maker fee share is within a valid range (0 to 100%)
+    pub fn valid_fee_bps(&self) -> bool {
+        self.maker_fee_bps <= 10_000
+    }
+}
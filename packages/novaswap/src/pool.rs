@@ -0,0 +1,140 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{Asset, AssetInfo};
+use crate::pair::PoolStatus;
+
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
+
+/// The minimum number of assets in a stable pool
+pub const MIN_POOL_ASSETS: usize = 2;
+
+/// The maximum number of assets in a stable pool
+pub const MAX_POOL_ASSETS: usize = 5;
+
+/// This structure describes the parameters used for creating a contract.
+/// The pool parameters are the same as the stable pair's [`crate::pair::StablePoolParams`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Information about the assets in the pool
+    pub asset_infos: Vec<AssetInfo>,
+    /// The token contract code ID used for the tokens in the pool
+    pub token_code_id: u64,
+    /// The factory contract address
+    pub factory_addr: String,
+    /// initial amplication parameter.
+    pub init_params: Option<Binary>,
+}
+
+/// This structure describes the execute messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Receives a message of type [`Cw20ReceiveMsg`]
+    Receive(Cw20ReceiveMsg),
+    /// ProvideLiquidity allows someone to provide liquidity in the pool
+    ProvideLiquidity {
+        /// The assets to deposit, assets of the pool which are not listed are not deposited
+        assets: Vec<Asset>,
+        /// The maximum allowed shortfall of the minted LP tokens against a deposit at par
+        slippage_tolerance: Option<Decimal>,
+        /// The minimum amount of LP tokens to receive
+        min_lp_to_receive: Option<Uint128>,
+        /// The receiver of LP tokens
+        receiver: Option<String>,
+        /// The block time or height after which the deposit fails
+        deadline: Option<Expiration>,
+    },
+    /// Swap a native asset for any other asset of the pool
+    Swap {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
+    },
+    UpdateConfig {
+        params: Binary,
+    },
+    /// Sends the balances of the pool above its tracked reserves to `to`
+    Skim {
+        to: String,
+    },
+    /// Sets the tracked reserves of the pool to its balances, only the factory owner can sync
+    Sync {},
+}
+
+/// This structure describes a CW20 hook message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Swap a given amount of asset for `ask_asset_info`
+    Swap {
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        /// The block time or height after which the swap fails
+        deadline: Option<Expiration>,
+    },
+    /// Withdraw liquidity from the pool
+    WithdrawLiquidity {
+        /// The minimum amount of each listed asset to receive
+        min_assets_out: Option<Vec<Asset>>,
+        /// The receiver of the withdrawn assets
+        receiver: Option<String>,
+        /// The block time or height after which the withdrawal fails
+        deadline: Option<Expiration>,
+    },
+}
+
+/// This structure describes the query messages available in the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns information about a pool in an object of type [`super::pairinfo::PoolInfo`].
+    PoolInfo {},
+    /// Returns the assets and the LP supply of a pool in an object of type [`PoolResponse`].
+    Pool {},
+    /// Returns contract configuration settings in a custom [`ConfigResponse`] structure.
+    Config {},
+    /// Returns information about the share of the pool in a vector that contains objects of type [`Asset`].
+    Share { amount: Uint128 },
+    /// Returns information about a swap simulation in a [`crate::pair::SimulationResponse`] object.
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    },
+    /// Returns information about a reverse swap simulation in a [`crate::pair::ReverseSimulationResponse`] object.
+    ReverseSimulation {
+        offer_asset_info: AssetInfo,
+        ask_asset: Asset,
+    },
+}
+
+/// This struct is used to return a query result with the total amount of LP tokens and the assets in a specific pool.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolResponse {
+    /// The assets in the pool together with asset amounts
+    pub assets: Vec<Asset>,
+    /// The total amount of LP tokens currently issued
+    pub total_supply: Uint128,
+}
+
+/// This struct is used to return a query result with the general contract configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    /// The operations currently allowed by the pool
+    pub status: PoolStatus,
+    /// The pool's parameters in a [`crate::pair::StablePoolConfig`] object
+    pub params: Option<Binary>,
+}
+
+/// This structure describes a migration message.
+/// We currently take no arguments for migrations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
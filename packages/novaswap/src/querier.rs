@@ -1,9 +1,13 @@
 use crate::asset::{Asset, AssetInfo};
 use crate::factory::{
-    ConfigResponse as FactoryConfigResponse, PairsResponse, QueryMsg as FactoryQueryMsg,
+    ConfigResponse as FactoryConfigResponse, FeeInfoResponse, PairsResponse, PoolsResponse,
+    QueryMsg as FactoryQueryMsg,
 };
-use crate::pair::{QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
-use crate::pairinfo::PairInfo;
+use crate::pair::{
+    QueryMsg as PairQueryMsg, RateProviderQueryMsg, RateProviderResponse,
+    ReverseSimulationResponse, SimulationResponse,
+};
+use crate::pairinfo::{PairInfo, PairType, PoolInfo};
 
 use cosmwasm_std::{
     to_binary, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Coin, QuerierWrapper,
@@ -14,9 +18,9 @@ use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoRespon
 
 const NATIVE_TOKEN_PRECISION: u8 = 6;
 
-/// --------------------
-/// Bank queries
-/// --------------------
+// --------------------
+// Bank queries
+// --------------------
 
 /// Returns a native token's balance for a specific account.
 pub fn query_balance(
@@ -40,9 +44,9 @@ pub fn query_all_balances(querier: &QuerierWrapper, account_addr: Addr) -> StdRe
     Ok(all_balances.amount)
 }
 
-/// --------------------
-/// CW20 token queries.
-/// --------------------
+// --------------------
+// CW20 token queries.
+// --------------------
 
 /// Returns a token balance for an account.
 pub fn query_token_balance(
@@ -98,9 +102,9 @@ pub fn query_token_precision(querier: &QuerierWrapper, asset_info: AssetInfo) ->
     })
 }
 
-/// --------------------------
-/// factory contract queries
-/// --------------------------
+// --------------------------
+// factory contract queries
+// --------------------------
 
 /// Returns the configuration for the factory contract.
 pub fn query_factory_config(
@@ -113,9 +117,21 @@ pub fn query_factory_config(
     }))
 }
 
-/// --------------------------
-/// pair contract queries
-/// --------------------------
+/// Returns the fee split applied by pairs of the given type.
+pub fn query_fee_info(
+    querier: &QuerierWrapper,
+    factory_contract: Addr,
+    pair_type: PairType,
+) -> StdResult<FeeInfoResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::FeeInfo { pair_type })?,
+    }))
+}
+
+// --------------------------
+// pair contract queries
+// --------------------------
 
 /// Accepts two tokens as input and returns a pair's information.
 pub fn query_pair_info(
@@ -144,6 +160,33 @@ pub fn query_pairs_info(
     }))
 }
 
+/// Accepts the tokens of a multi-asset pool as input and returns the pool's information.
+pub fn query_pool_info(
+    querier: &QuerierWrapper,
+    factory_contract: Addr,
+    asset_infos: &[AssetInfo],
+) -> StdResult<PoolInfo> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Pool {
+            asset_infos: asset_infos.to_vec(),
+        })?,
+    }))
+}
+
+/// Returns a vector that contains items of type [`PoolInfo`] which symbolize multi-asset pools instantiated in the factory
+pub fn query_pools_info(
+    querier: &QuerierWrapper,
+    factory_contract: Addr,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<PoolsResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Pools { start_after, limit })?,
+    }))
+}
+
 /// Returns information about a swap simulation using a [`SimulationResponse`] object.
 pub fn simulate(
     querier: &QuerierWrapper,
@@ -171,3 +214,16 @@ pub fn reverse_simulate(
         })?,
     }))
 }
+
+// --------------------------
+// rate provider queries
+// --------------------------
+
+/// Returns the redemption rate of an asset from a rate provider contract.
+pub fn query_rate(
+    querier: &QuerierWrapper,
+    rate_provider: Addr,
+    asset_info: AssetInfo,
+) -> StdResult<RateProviderResponse> {
+    querier.query_wasm_smart(rate_provider, &RateProviderQueryMsg::Rate { asset_info })
+}
@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{StdError, StdResult, Uint128};
-use cw20::{Cw20Coin, MinterResponse};
+use cw20::{Cw20Coin, Logo, MinterResponse};
 
 /// This structure describes the parameters used for creating a token contract.
 /// TokenContract InstantiateMsg
@@ -18,6 +18,22 @@ pub struct InstantiateMsg {
     pub initial_balances: Vec<Cw20Coin>,
     /// Minting controls specified in a [`MinterResponse`] structure
     pub mint: Option<MinterResponse>,
+    /// The project, description and logo shown for the token
+    pub marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// This structure describes the marketing info of a token.
+/// It matches the marketing info accepted by cw20-base.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMarketingInfo {
+    /// The project the token belongs to
+    pub project: Option<String>,
+    /// A description of the token
+    pub description: Option<String>,
+    /// The address allowed to update the marketing info, none can when not set
+    pub marketing: Option<String>,
+    /// The token logo, a url or an embedded image
+    pub logo: Option<Logo>,
 }
 
 /// This structure describes a migration message.
@@ -63,7 +79,7 @@ fn is_valid_name(name: &str) -> bool {
 /// Checks the validity of the token symbol
 /// ## Params
 /// * **symbol** is an object of type [`str`]. It is the token symbol to check
-fn is_valid_symbol(symbol: &str) -> bool {
+pub fn is_valid_symbol(symbol: &str) -> bool {
     let bytes = symbol.as_bytes();
     if bytes.len() < 3 || bytes.len() > 12 {
         return false;
@@ -23,6 +23,7 @@ export class Factory {
                 {
                     code_id: pairCodeId,
                     pair_type: { stable: {} },
+                    maker_fee_bps: 0,
                     is_disabled: false,
                 }
             ]